@@ -0,0 +1,712 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime};
+use flate2::read::GzDecoder;
+use log::{debug, info};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::usecases::usage::format_timestamp;
+
+const AUTH_LOG_FILE_NAME: &str = "auth.log";
+
+/// Full days without records after which logs are considered stopped
+const MAX_DAYS_SINCE_LAST_RECORD: i64 = 1;
+
+const GZIP_EXTENSION: &str = "gz";
+
+const SYSLOG_TIMESTAMP_FORMAT: &str = "%Y %b %d %H:%M:%S";
+
+const PUBLIC_KEY_LOGIN_PATTERN: &str = r"sshd(?:-session)?\[(\d+)\]: Accepted publickey for (\S+) from (\S+) port \d+ ssh2: (\S+) (\S+)(?: ID (.*) \(serial (\d+)\) CA (\S+) (\S+))?";
+
+const FAILED_PUBLIC_KEY_PATTERN: &str = r"sshd(?:-session)?\[(\d+)\]: Failed publickey for (?:invalid user )?(\S+) from (\S+) port \d+ ssh2: (\S+) (\S+)";
+
+const POSTPONED_PUBLIC_KEY_PATTERN: &str = r"sshd(?:-session)?\[(\d+)\]: Postponed publickey for (?:invalid user )?(\S+) from (\S+) port \d+ ssh2";
+
+const KEY_FOUND_PATTERN: &str = r"sshd(?:-session)?\[(\d+)\]: Accepted key (\S+) (\S+) found at ";
+
+const SESSION_CLOSED_PATTERN: &str = r"sshd(?:-session)?\[(\d+)\]: (?:pam_unix\(sshd:session\): session closed for user (\S+)|Disconnected from user (\S+) )";
+
+const CONNECTION_CLOSED_PATTERN: &str = r"sshd(?:-session)?\[(\d+)\]: (?:Connection closed by|Connection reset by|Disconnected from|Received disconnect from) ";
+
+/// Successful login with public key or certificate
+#[derive(Clone, PartialEq, Debug)]
+pub struct PublicKeyLogin {
+    pub timestamp: NaiveDateTime,
+
+    /// PID of sshd process which handles the session
+    pub pid: u32,
+
+    pub username: String,
+
+    pub source_ip: String,
+
+    /// As logged by sshd, i.e. `ED25519` or `ED25519-CERT`
+    pub key_type: String,
+
+    /// `SHA256:...`
+    pub fingerprint: String,
+
+    /// Set for logins with certificate
+    pub certificate: Option<CertificateLogin>,
+
+    /// Time of `session closed` or `Disconnected from user` line of the same sshd process and user,
+    /// empty if session is still open, logs were rotated or pid was reused without close line
+    pub session_closed: Option<NaiveDateTime>,
+}
+
+/// Certificate details from sshd log: `ID alice (serial 42) CA ED25519 SHA256:...`
+#[derive(Clone, PartialEq, Debug)]
+pub struct CertificateLogin {
+    pub key_id: String,
+
+    pub serial: u64,
+
+    pub ca_key_type: String,
+
+    /// `SHA256:...` fingerprint of CA key, the same as `cert-authority` entry fingerprint
+    pub ca_fingerprint: String,
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureKind {
+    /// Key was rejected: it isn't authorized for user or its options (i.e. `from`) don't match
+    Failed,
+
+    /// Key was accepted, but client didn't sign the challenge: key probing or client without access
+    /// to private key
+    Postponed,
+}
+
+impl Display for FailureKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            FailureKind::Failed => write!(f, "failed"),
+            FailureKind::Postponed => write!(f, "postponed"),
+        }
+    }
+}
+
+/// Unsuccessful attempt to login with public key
+#[derive(Clone, PartialEq, Debug)]
+pub struct PublicKeyFailure {
+    pub timestamp: NaiveDateTime,
+
+    pub pid: u32,
+
+    pub kind: FailureKind,
+
+    pub username: String,
+
+    pub source_ip: String,
+
+    pub key_type: String,
+
+    /// `SHA256:...`
+    pub fingerprint: String,
+}
+
+/// Time range of available auth logs
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct LogCoverage {
+    pub from: String,
+
+    pub to: String,
+
+    /// Full days between the first and the last records
+    pub days: i64,
+
+    /// Full days between the last record and now, logins after it are unknown
+    pub days_since_last_record: i64,
+}
+
+impl LogCoverage {
+    /// Logs cover the last `days` only if they go back far enough and are still written,
+    /// logs which stopped days ago don't show recent logins. Quiet hosts may have no records
+    /// for a day, so the last record may be up to `MAX_DAYS_SINCE_LAST_RECORD` full days old.
+    pub fn covers_last_days(&self, days: usize) -> bool {
+        self.days_since_last_record <= MAX_DAYS_SINCE_LAST_RECORD && self.days >= days as i64
+    }
+}
+
+impl Display for LogCoverage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {} ({} days)", self.from, self.to, self.days)
+    }
+}
+
+/// Auth logs parsed in one pass
+pub struct AuthLog {
+    /// Successful logins with public keys and certificates, oldest first
+    pub logins: Vec<PublicKeyLogin>,
+
+    /// Failed public key attempts and postponed ones which weren't followed by successful login
+    /// in the same sshd process, oldest first
+    pub failures: Vec<PublicKeyFailure>,
+
+    /// Empty if logs have no records
+    pub coverage: Option<LogCoverage>,
+
+    /// Postponed lines without `Accepted key ... found at` line, sshd logs it with `LogLevel DEBUG`
+    pub skipped_postponed_attempts: usize,
+}
+
+/// Reads `auth.log`, `auth.log.X` and `auth.log.X.gz` files line by line, oldest first.
+///
+/// `auth_log_path` is a directory with auth logs or a single log file. Fails if logs weren't found
+/// or any of them can't be read, so keys aren't reported as unused because of missing logs.
+pub fn read_auth_log(auth_log_path: &Path) -> anyhow::Result<AuthLog> {
+    info!("read auth logs from '{}'", auth_log_path.display());
+
+    let mut parser = AuthLogParser::new();
+
+    for_each_auth_log_line(auth_log_path, |line| parser.parse_line(line))?;
+
+    let auth_log = parser.finish();
+
+    info!("public key logins found: {}, failures found: {}", auth_log.logins.len(), auth_log.failures.len());
+
+    if let Some(coverage) = &auth_log.coverage {
+        info!("auth logs coverage: {coverage}");
+    }
+
+    Ok(auth_log)
+}
+
+/// Reads auth logs like `read_auth_log`, but keeps only the latest login of each key,
+/// certificate logins are kept by CA key. Memory doesn't grow with log size, so it suits large logs
+/// when only last use of keys is needed. Failures and sessions aren't collected.
+pub fn read_latest_logins(auth_log_path: &Path) -> anyhow::Result<AuthLog> {
+    info!("read latest logins from auth logs '{}'", auth_log_path.display());
+
+    let login_pattern = Regex::new(PUBLIC_KEY_LOGIN_PATTERN).expect("invalid public key login pattern");
+
+    let now = Local::now().naive_local();
+
+    let mut latest_logins: HashMap<String, PublicKeyLogin> = HashMap::new();
+
+    let mut from: Option<NaiveDateTime> = None;
+    let mut to: Option<NaiveDateTime> = None;
+
+    for_each_auth_log_line(auth_log_path, |line| {
+        let timestamp = match parse_timestamp(line, &now) {
+            Some(timestamp) => timestamp,
+            None => return
+        };
+
+        from = Some(from.map_or(timestamp, |from| from.min(timestamp)));
+        to = Some(to.map_or(timestamp, |to| to.max(timestamp)));
+
+        if let Some(login) = parse_public_key_login(line, &login_pattern, timestamp) {
+            let key = match &login.certificate {
+                Some(certificate) => certificate.ca_fingerprint.to_string(),
+                None => login.fingerprint.to_string()
+            };
+
+            if latest_logins.get(&key).is_some_and(|saved_login| saved_login.timestamp >= login.timestamp) {
+                return
+            }
+
+            latest_logins.insert(key, login);
+        }
+    })?;
+
+    let mut logins: Vec<PublicKeyLogin> = latest_logins.into_values().collect();
+    logins.sort_by_key(|login| login.timestamp);
+
+    info!("keys with logins found: {}", logins.len());
+
+    let coverage = get_log_coverage(from, to);
+
+    if let Some(coverage) = &coverage {
+        info!("auth logs coverage: {coverage}");
+    }
+
+    Ok(AuthLog { logins, failures: vec![], coverage, skipped_postponed_attempts: 0 })
+}
+
+/// Parser state, lines must be passed oldest first.
+///
+/// Postponed line doesn't contain key, so it's taken from `Accepted key ... found at` line of the same sshd process.
+/// State of sshd process is dropped when its connection is closed, so maps don't grow with log size.
+struct AuthLogParser {
+    login_pattern: Regex,
+    session_closed_pattern: Regex,
+    connection_closed_pattern: Regex,
+    failed_pattern: Regex,
+    postponed_pattern: Regex,
+    key_found_pattern: Regex,
+
+    now: NaiveDateTime,
+
+    logins: Vec<PublicKeyLogin>,
+
+    /// sshd pid -> index of login with open session, login with reused pid replaces unclosed one
+    open_sessions: HashMap<u32, usize>,
+
+    failures: Vec<PublicKeyFailure>,
+
+    /// sshd pid -> (key type, fingerprint) of the last key found in authorized_keys
+    found_keys: HashMap<u32, (String, String)>,
+
+    /// sshd pid -> postponed attempt without successful login yet
+    postponed_attempts: HashMap<u32, PublicKeyFailure>,
+
+    skipped_postponed_attempts: usize,
+
+    from: Option<NaiveDateTime>,
+
+    to: Option<NaiveDateTime>,
+}
+
+impl AuthLogParser {
+    fn new() -> AuthLogParser {
+        AuthLogParser {
+            login_pattern: Regex::new(PUBLIC_KEY_LOGIN_PATTERN).expect("invalid public key login pattern"),
+            session_closed_pattern: Regex::new(SESSION_CLOSED_PATTERN).expect("invalid session closed pattern"),
+            connection_closed_pattern: Regex::new(CONNECTION_CLOSED_PATTERN).expect("invalid connection closed pattern"),
+            failed_pattern: Regex::new(FAILED_PUBLIC_KEY_PATTERN).expect("invalid failed public key pattern"),
+            postponed_pattern: Regex::new(POSTPONED_PUBLIC_KEY_PATTERN).expect("invalid postponed public key pattern"),
+            key_found_pattern: Regex::new(KEY_FOUND_PATTERN).expect("invalid key found pattern"),
+            now: Local::now().naive_local(),
+            logins: vec![],
+            open_sessions: HashMap::new(),
+            failures: vec![],
+            found_keys: HashMap::new(),
+            postponed_attempts: HashMap::new(),
+            skipped_postponed_attempts: 0,
+            from: None,
+            to: None,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        let timestamp = match parse_timestamp(line, &self.now) {
+            Some(timestamp) => timestamp,
+            None => return
+        };
+
+        self.from = Some(self.from.map_or(timestamp, |from| from.min(timestamp)));
+        self.to = Some(self.to.map_or(timestamp, |to| to.max(timestamp)));
+
+        if let Some(login) = parse_public_key_login(line, &self.login_pattern, timestamp) {
+            self.postponed_attempts.remove(&login.pid);
+            self.found_keys.remove(&login.pid);
+
+            if let Some(unclosed_index) = self.open_sessions.insert(login.pid, self.logins.len()) {
+                debug!("sshd[{}] was reused, session from {} wasn't closed", login.pid, self.logins[unclosed_index].timestamp);
+            }
+
+            self.logins.push(login);
+
+        } else if let Some(groups) = self.session_closed_pattern.captures(line) {
+            let pid = match groups[1].parse::<u32>() {
+                Ok(pid) => pid,
+                Err(_) => return
+            };
+
+            let username = groups.get(2).or(groups.get(3)).map(|username| username.as_str());
+
+            let login_index = match self.open_sessions.get(&pid) {
+                Some(login_index) => *login_index,
+                None => return
+            };
+
+            let login = &mut self.logins[login_index];
+
+            if Some(login.username.as_str()) == username && login.timestamp <= timestamp {
+                login.session_closed = Some(timestamp);
+                self.open_sessions.remove(&pid);
+
+            } else {
+                debug!("close of sshd[{pid}] session doesn't match login of '{}' at {}, skip", login.username, login.timestamp);
+            }
+
+        } else if let Some(groups) = self.connection_closed_pattern.captures(line) {
+            if let Ok(pid) = groups[1].parse::<u32>() {
+                self.found_keys.remove(&pid);
+
+                if let Some(postponed_attempt) = self.postponed_attempts.remove(&pid) {
+                    self.failures.push(postponed_attempt);
+                }
+            }
+
+        } else if let Some(groups) = self.failed_pattern.captures(line) {
+            if let Ok(pid) = groups[1].parse::<u32>() {
+                self.failures.push(PublicKeyFailure {
+                    timestamp,
+                    pid,
+                    kind: FailureKind::Failed,
+                    username: groups[2].to_string(),
+                    source_ip: groups[3].to_string(),
+                    key_type: groups[4].to_string(),
+                    fingerprint: groups[5].to_string(),
+                });
+            }
+
+        } else if let Some(groups) = self.key_found_pattern.captures(line) {
+            if let Ok(pid) = groups[1].parse::<u32>() {
+                self.found_keys.insert(pid, (groups[2].to_string(), groups[3].to_string()));
+            }
+
+        } else if let Some(groups) = self.postponed_pattern.captures(line) {
+            let pid = match groups[1].parse::<u32>() {
+                Ok(pid) => pid,
+                Err(_) => return
+            };
+
+            let (key_type, fingerprint) = match self.found_keys.get(&pid) {
+                Some(found_key) => found_key.clone(),
+                None => {
+                    debug!("key for postponed attempt of sshd[{pid}] wasn't found, skip");
+                    self.skipped_postponed_attempts += 1;
+                    return
+                }
+            };
+
+            self.postponed_attempts.entry(pid).or_insert(PublicKeyFailure {
+                timestamp,
+                pid,
+                kind: FailureKind::Postponed,
+                username: groups[2].to_string(),
+                source_ip: groups[3].to_string(),
+                key_type,
+                fingerprint,
+            });
+        }
+    }
+
+    fn finish(mut self) -> AuthLog {
+        self.failures.extend(self.postponed_attempts.into_values());
+        self.failures.sort_by_key(|failure| failure.timestamp);
+
+        AuthLog {
+            logins: self.logins,
+            failures: self.failures,
+            coverage: get_log_coverage(self.from, self.to),
+            skipped_postponed_attempts: self.skipped_postponed_attempts,
+        }
+    }
+}
+
+/// Empty if logs have no records
+fn get_log_coverage(from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> Option<LogCoverage> {
+    match (from, to) {
+        (Some(from), Some(to)) => Some(LogCoverage {
+            from: format_timestamp(&from),
+            to: format_timestamp(&to),
+            days: (to - from).num_days(),
+            days_since_last_record: Local::now().naive_local().signed_duration_since(to).num_days().max(0),
+        }),
+        _ => None
+    }
+}
+
+/// Passes lines of auth logs to `on_line` without loading whole files, invalid UTF-8 is replaced.
+fn for_each_auth_log_line(auth_log_path: &Path, mut on_line: impl FnMut(&str)) -> anyhow::Result<()> {
+    let files = if auth_log_path.is_dir() {
+        get_auth_log_files(auth_log_path)?
+    } else {
+        vec![auth_log_path.to_path_buf()]
+    };
+
+    if files.is_empty() {
+        anyhow::bail!("auth logs weren't found in '{}'", auth_log_path.display())
+    }
+
+    for file in files {
+        debug!("read auth log '{}'", file.display());
+
+        let read_error = |e: std::io::Error| anyhow::anyhow!("unable to read auth log '{}': {}", file.display(), e);
+
+        let opened_file = File::open(&file).map_err(read_error)?;
+
+        let mut reader: Box<dyn BufRead> = if file.extension().is_some_and(|e| e == GZIP_EXTENSION) {
+            Box::new(BufReader::new(GzDecoder::new(opened_file)))
+        } else {
+            Box::new(BufReader::new(opened_file))
+        };
+
+        let mut line: Vec<u8> = vec![];
+
+        while reader.read_until(b'\n', &mut line).map_err(read_error)? > 0 {
+            on_line(String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']));
+            line.clear();
+        }
+    }
+
+    Ok(())
+}
+
+/// Rotated files go first: `auth.log.2.gz`, `auth.log.1`, `auth.log`.
+fn get_auth_log_files(auth_log_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<(usize, PathBuf)> = vec![];
+
+    for entry in fs::read_dir(auth_log_path)? {
+        let path = entry?.path();
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let rotation_index = if file_name == AUTH_LOG_FILE_NAME {
+            Some(0)
+
+        } else {
+            file_name.strip_prefix(&format!("{AUTH_LOG_FILE_NAME}."))
+                .map(|suffix| suffix.trim_end_matches(&format!(".{GZIP_EXTENSION}")))
+                .and_then(|index| index.parse::<usize>().ok())
+        };
+
+        if let Some(rotation_index) = rotation_index {
+            files.push((rotation_index, path));
+        }
+    }
+
+    files.sort_by_key(|(rotation_index, _)| Reverse(*rotation_index));
+
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+fn parse_public_key_login(line: &str, pattern: &Regex, timestamp: NaiveDateTime) -> Option<PublicKeyLogin> {
+    let groups = pattern.captures(line)?;
+
+    let certificate = match (groups.get(6), groups.get(7), groups.get(8), groups.get(9)) {
+        (Some(key_id), Some(serial), Some(ca_key_type), Some(ca_fingerprint)) => Some(CertificateLogin {
+            key_id: key_id.as_str().to_string(),
+            serial: serial.as_str().parse().ok()?,
+            ca_key_type: ca_key_type.as_str().to_string(),
+            ca_fingerprint: ca_fingerprint.as_str().to_string(),
+        }),
+        _ => None
+    };
+
+    Some(PublicKeyLogin {
+        timestamp,
+        pid: groups[1].parse().ok()?,
+        username: groups[2].to_string(),
+        source_ip: groups[3].to_string(),
+        key_type: groups[4].to_string(),
+        fingerprint: groups[5].to_string(),
+        certificate,
+        session_closed: None,
+    })
+}
+
+/// Supports syslog (`Apr  1 10:12:45`, year is taken from `now`) and RFC 3339 timestamps.
+pub fn parse_timestamp(line: &str, now: &NaiveDateTime) -> Option<NaiveDateTime> {
+    let first_token = line.split_whitespace().next()?;
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(first_token) {
+        return Some(timestamp.with_timezone(&Local).naive_local())
+    }
+
+    let syslog_timestamp = line.split_whitespace().take(3).collect::<Vec<&str>>().join(" ");
+
+    let timestamp = NaiveDateTime::parse_from_str(
+        &format!("{} {syslog_timestamp}", now.year()), SYSLOG_TIMESTAMP_FORMAT).ok()?;
+
+    // logs from December read in January
+    if timestamp > *now + Duration::days(1) {
+        return timestamp.with_year(now.year() - 1)
+    }
+
+    Some(timestamp)
+}
+
+#[cfg(test)]
+mod auth_log_tests {
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+
+    use chrono::NaiveDateTime;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use crate::authlog::{CertificateLogin, FailureKind, LogCoverage, parse_timestamp, read_auth_log, read_latest_logins};
+    use crate::keysfile::KeysFile;
+    use crate::tests_common::get_temp_dir;
+
+    #[test]
+    fn parse_key_and_certificate_logins() {
+        let dir = get_temp_dir();
+
+        fs::write(dir.path().join("auth.log.1"), "Apr  1 10:12:45 host sshd[1]: Accepted publickey for bob from 10.0.0.2 port 50122 ssh2: RSA SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8\n").unwrap();
+        fs::write(dir.path().join("auth.log"), "2023-04-02T10:00:00+00:00 host sshd[2]: Accepted publickey for alice from 10.0.0.1 port 50123 ssh2: ED25519-CERT SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE ID alice@corp (serial 42) CA ED25519 SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI\n\
+                                          2023-04-02T10:00:01+00:00 host sshd[2]: pam_unix(sshd:session): session opened for user alice\n").unwrap();
+        fs::write(dir.path().join("syslog"), "").unwrap();
+
+        let logins = read_auth_log(dir.path()).unwrap().logins;
+
+        assert_eq!(logins.len(), 2);
+
+        assert_eq!(logins[0].username, "bob");
+        assert_eq!(logins[0].source_ip, "10.0.0.2");
+        assert_eq!(logins[0].certificate, None);
+
+        assert_eq!(logins[1].key_type, "ED25519-CERT");
+        assert_eq!(logins[1].certificate, Some(CertificateLogin {
+            key_id: "alice@corp".to_string(),
+            serial: 42,
+            ca_key_type: "ED25519".to_string(),
+            ca_fingerprint: "SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI".to_string(),
+        }));
+    }
+
+    #[test]
+    fn only_latest_login_of_each_key_should_be_kept() {
+        let dir = get_temp_dir();
+
+        fs::write(dir.path().join("auth.log.1"), "\
+Apr  1 10:00:00 host sshd[1]: Accepted publickey for bob from 10.0.0.2 port 50122 ssh2: RSA SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8
+Apr  1 10:00:01 host sshd[2]: Accepted publickey for alice from 10.0.0.1 port 50123 ssh2: ED25519-CERT SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE ID alice@corp (serial 42) CA ED25519 SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI
+").unwrap();
+        fs::write(dir.path().join("auth.log"), "\
+Apr  2 10:00:00 host sshd[3]: Accepted publickey for root from 10.0.0.3 port 50124 ssh2: RSA SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8
+Apr  2 10:00:01 host sshd[4]: Accepted publickey for carol from 10.0.0.4 port 50125 ssh2: ED25519-CERT SHA256:XtJwbEGBoJG/ARrGFpLWCSvOSHiZTThkaFXdEs+dbJk ID carol@corp (serial 7) CA ED25519 SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI
+").unwrap();
+
+        let auth_log = read_latest_logins(dir.path()).unwrap();
+
+        let logins: Vec<(&str, &str)> = auth_log.logins.iter()
+            .map(|login| (login.username.as_str(), login.source_ip.as_str()))
+            .collect();
+
+        assert_eq!(logins, vec![("root", "10.0.0.3"), ("carol", "10.0.0.4")]);
+        assert!(auth_log.coverage.unwrap().from.ends_with("-04-01 10:00:00"));
+    }
+
+    #[test]
+    fn log_coverage_should_be_taken_from_oldest_and_latest_records() {
+        let dir = get_temp_dir();
+
+        fs::write(dir.path().join("auth.log.1"), "Jan  2 10:00:00 host sshd[1]: Server listening on 0.0.0.0 port 22.\n").unwrap();
+        fs::write(dir.path().join("auth.log"), "Jan  5 09:00:00 host sshd[2]: Connection closed by 10.0.0.1 port 50122\n").unwrap();
+
+        let coverage = read_auth_log(dir.path()).unwrap().coverage.unwrap();
+
+        assert!(coverage.from.ends_with("-01-02 10:00:00"));
+        assert!(coverage.to.ends_with("-01-05 09:00:00"));
+        assert_eq!(coverage.days, 2);
+
+        assert!(read_auth_log(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn logs_which_stopped_days_ago_should_not_cover_last_days() {
+        let coverage = LogCoverage { from: String::new(), to: String::new(), days: 45, days_since_last_record: 0 };
+
+        assert!(coverage.covers_last_days(31));
+        assert!(!coverage.covers_last_days(60));
+
+        let coverage = LogCoverage { days_since_last_record: 1, ..coverage };
+
+        assert!(coverage.covers_last_days(31));
+
+        let coverage = LogCoverage { days_since_last_record: 2, ..coverage };
+
+        assert!(!coverage.covers_last_days(31));
+    }
+
+    #[test]
+    fn parse_failed_and_unfinished_postponed_attempts() {
+        let dir = get_temp_dir();
+
+        fs::write(dir.path().join("auth.log"), "\
+Apr  1 10:00:00 host sshd[1]: Failed publickey for root from 10.0.0.9 port 50122 ssh2: RSA SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8
+Apr  1 10:00:01 host sshd[2]: Accepted key ED25519 SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE found at /home/alice/.ssh/authorized_keys:1
+Apr  1 10:00:01 host sshd[2]: Postponed publickey for alice from 10.0.0.1 port 50123 ssh2 [preauth]
+Apr  1 10:00:02 host sshd[2]: Accepted publickey for alice from 10.0.0.1 port 50123 ssh2: ED25519 SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE
+Apr  1 10:00:03 host sshd[3]: Accepted key ED25519 SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI found at /home/bob/.ssh/authorized_keys:1
+Apr  1 10:00:03 host sshd[3]: Postponed publickey for bob from 10.0.0.2 port 50124 ssh2 [preauth]
+Apr  1 10:00:04 host sshd[3]: Connection closed by authenticating user bob 10.0.0.2 port 50124 [preauth]
+Apr  1 10:00:05 host sshd[4]: Postponed publickey for carol from 10.0.0.3 port 50125 ssh2 [preauth]
+").unwrap();
+
+        let auth_log = read_auth_log(dir.path()).unwrap();
+
+        assert_eq!(auth_log.skipped_postponed_attempts, 1);
+
+        let failures = auth_log.failures;
+
+        let failures: Vec<(FailureKind, String, String)> = failures.iter()
+            .map(|f| (f.kind.clone(), f.username.to_string(), f.fingerprint.to_string())).collect();
+
+        assert_eq!(failures, vec![
+            (FailureKind::Failed, "root".to_string(), "SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8".to_string()),
+            (FailureKind::Postponed, "bob".to_string(), "SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn session_should_be_closed_by_the_same_process_and_user() {
+        let dir = get_temp_dir();
+
+        fs::write(dir.path().join("auth.log"), "\
+Apr  1 10:00:00 host sshd[1]: Accepted publickey for alice from 10.0.0.1 port 50122 ssh2: ED25519 SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE
+Apr  1 11:00:00 host sshd[1]: Accepted publickey for bob from 10.0.0.2 port 50123 ssh2: ED25519 SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE
+Apr  1 11:00:05 host sshd[1]: pam_unix(sshd:session): session closed for user alice
+Apr  1 11:00:10 host sshd[1]: Disconnected from user bob 10.0.0.2 port 50123
+Apr  1 11:00:10 host sshd[1]: pam_unix(sshd:session): session closed for user bob
+").unwrap();
+
+        let logins = read_auth_log(dir.path()).unwrap().logins;
+
+        let sessions: Vec<Option<i64>> = logins.iter()
+            .map(|login| login.session_closed.map(|closed| (closed - login.timestamp).num_seconds())).collect();
+
+        assert_eq!(sessions, vec![None, Some(10)]);
+    }
+
+    #[test]
+    fn timestamp_from_future_should_be_moved_to_previous_year() {
+        let now = NaiveDateTime::parse_from_str("2023-01-02 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+
+        assert_eq!(parse_timestamp("Jan  2 09:00:00 host sshd[1]: ...", &now),
+                   NaiveDateTime::parse_from_str("2023-01-02 09:00:00", "%Y-%m-%d %H:%M:%S").ok());
+        assert_eq!(parse_timestamp("Dec 31 23:00:00 host sshd[1]: ...", &now),
+                   NaiveDateTime::parse_from_str("2022-12-31 23:00:00", "%Y-%m-%d %H:%M:%S").ok());
+        assert_eq!(parse_timestamp("garbage", &now), None);
+    }
+
+    #[test]
+    fn security_key_login_should_match_key_fingerprint() {
+        let logins = read_auth_log(Path::new("test-data/auth.log")).unwrap().logins;
+
+        let login = logins.iter().find(|login| login.key_type == "ED25519-SK").unwrap();
+
+        let keys_file = KeysFile::parse(include_str!("../test-data/authorized_keys"));
+
+        let entry = keys_file.lines.iter()
+            .filter_map(|line| line.get_entry())
+            .find(|entry| entry.key_type == "sk-ssh-ed25519@openssh.com")
+            .unwrap();
+
+        assert_eq!(login.fingerprint, format!("SHA256:{}", entry.get_fingerprint()));
+
+        assert_eq!(login.pid, 1011102);
+        assert_eq!(login.session_closed.map(|closed| (closed - login.timestamp).num_seconds()), Some(141));
+    }
+
+    #[test]
+    fn gzipped_and_unreadable_logs() {
+        let dir = get_temp_dir();
+
+        let mut encoder = GzEncoder::new(vec![], Compression::fast());
+        encoder.write_all(b"Apr  1 10:12:45 host sshd-session[1]: Accepted publickey for bob from 10.0.0.2 port 50122 ssh2: RSA SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8\n").unwrap();
+        fs::write(dir.path().join("auth.log.2.gz"), encoder.finish().unwrap()).unwrap();
+
+        assert_eq!(read_auth_log(dir.path()).unwrap().logins.len(), 1);
+
+        fs::create_dir(dir.path().join("auth.log")).unwrap();
+
+        assert!(read_auth_log(dir.path()).is_err());
+        assert!(read_auth_log(get_temp_dir().path()).is_err());
+    }
+}
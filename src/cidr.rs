@@ -0,0 +1,140 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const IPV4_BITS: u8 = 32;
+
+const IPV6_BITS: u8 = 128;
+
+/// Network in CIDR notation, i.e. `10.0.0.0/24`. Address is always masked by prefix length.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Cidr {
+    pub address: IpAddr,
+
+    pub prefix_length: u8,
+}
+
+impl Cidr {
+    /// Prefix length is limited by address family: 32 for IPv4, 128 for IPv6.
+    pub fn new(address: IpAddr, prefix_length: u8) -> Cidr {
+        let prefix_length = prefix_length.min(get_address_bits(&address));
+
+        let bits = get_bits(&address) & get_mask(prefix_length, get_address_bits(&address));
+
+        Cidr { address: get_address(bits, address.is_ipv4()), prefix_length }
+    }
+
+    /// Network contains address or the whole other network.
+    pub fn contains(&self, other: &Cidr) -> bool {
+        self.address.is_ipv4() == other.address.is_ipv4() &&
+            self.prefix_length <= other.prefix_length &&
+            Cidr::new(other.address, self.prefix_length).address == self.address
+    }
+
+    fn get_parent(&self) -> Option<Cidr> {
+        match self.prefix_length {
+            0 => None,
+            prefix_length => Some(Cidr::new(self.address, prefix_length - 1))
+        }
+    }
+}
+
+/// Single address is shown without prefix length, `sshd` accepts both forms in `from=` option.
+impl Display for Cidr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.prefix_length == get_address_bits(&self.address) {
+            write!(f, "{}", self.address)
+        } else {
+            write!(f, "{}/{}", self.address, self.prefix_length)
+        }
+    }
+}
+
+/// Minimal set of networks covering the same addresses: nested networks are dropped,
+/// sibling networks are merged into parent one.
+pub fn aggregate(networks: &[Cidr]) -> Vec<Cidr> {
+    let mut networks = networks.to_vec();
+
+    loop {
+        networks.sort();
+        networks.dedup();
+
+        let all_networks = networks.clone();
+
+        networks.retain(|network| !all_networks.iter().any(|other| other != network && other.contains(network)));
+
+        let siblings = networks.windows(2).position(|pair|
+            pair[0].prefix_length == pair[1].prefix_length && pair[0].get_parent().is_some() &&
+                pair[0].get_parent() == pair[1].get_parent());
+
+        match siblings {
+            Some(index) => {
+                networks[index] = networks[index].get_parent().expect("parent network is expected");
+                networks.remove(index + 1);
+            }
+            None => return networks
+        }
+    }
+}
+
+fn get_address_bits(address: &IpAddr) -> u8 {
+    if address.is_ipv4() { IPV4_BITS } else { IPV6_BITS }
+}
+
+fn get_bits(address: &IpAddr) -> u128 {
+    match address {
+        IpAddr::V4(address) => u32::from(*address) as u128,
+        IpAddr::V6(address) => u128::from(*address)
+    }
+}
+
+fn get_address(bits: u128, is_ipv4: bool) -> IpAddr {
+    if is_ipv4 {
+        IpAddr::V4(Ipv4Addr::from(bits as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(bits))
+    }
+}
+
+fn get_mask(prefix_length: u8, address_bits: u8) -> u128 {
+    let host_bits = (address_bits - prefix_length) as u32;
+    u128::MAX.checked_shl(host_bits).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod cidr_tests {
+    use std::net::IpAddr;
+
+    use crate::cidr::{aggregate, Cidr};
+
+    #[test]
+    fn network_address_should_be_masked() {
+        let network = Cidr::new(get_address("10.1.2.3"), 24);
+
+        assert_eq!(network.to_string(), "10.1.2.0/24");
+        assert!(network.contains(&Cidr::new(get_address("10.1.2.200"), 32)));
+        assert!(!network.contains(&Cidr::new(get_address("10.1.3.1"), 32)));
+        assert!(!network.contains(&Cidr::new(get_address("::1"), 128)));
+
+        assert_eq!(Cidr::new(get_address("10.1.2.3"), 40).to_string(), "10.1.2.3");
+        assert_eq!(Cidr::new(get_address("2001:db8::1"), 32).to_string(), "2001:db8::/32");
+        assert_eq!(Cidr::new(get_address("10.1.2.3"), 0).to_string(), "0.0.0.0/0");
+    }
+
+    #[test]
+    fn aggregate_networks() {
+        let networks: Vec<Cidr> = ["10.0.0.0", "10.0.0.1", "10.0.0.2", "10.0.0.3", "10.0.0.5", "192.168.1.7", "::1"]
+            .iter().map(|address| Cidr::new(get_address(address), 128)).collect();
+
+        let networks: Vec<String> = aggregate(&networks).iter().map(|n| n.to_string()).collect();
+
+        assert_eq!(networks, vec!["10.0.0.0/30", "10.0.0.5", "192.168.1.7", "::1"]);
+
+        let networks = vec![Cidr::new(get_address("10.0.0.1"), 24), Cidr::new(get_address("10.0.0.77"), 32)];
+
+        assert_eq!(aggregate(&networks), vec![Cidr::new(get_address("10.0.0.0"), 24)]);
+    }
+
+    fn get_address(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+}
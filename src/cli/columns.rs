@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use authorized_keys::authorizedkeys::AuthorizedKey;
+use serde::Serialize;
+
+use crate::usecases::audit::AuditFinding;
+use crate::usecases::certs::CertificateAuthority;
+use crate::usecases::failures::KeyFailures;
+use crate::usecases::find::FoundKey;
+use crate::usecases::fingerprints::FingerprintedKey;
+use crate::usecases::lint::LintIssue;
+use crate::usecases::oldkeys::RemovalCandidate;
+use crate::usecases::perms::PermissionIssue;
+use crate::usecases::records::KeyRecord;
+use crate::usecases::revoke::RevokeRecord;
+use crate::usecases::revokedkeys::{RevokedKey, RevokedKeyEntry};
+use crate::usecases::stats::SessionStats;
+use crate::usecases::suggestfrom::FromSuggestion;
+use crate::usecases::sync::SyncAction;
+use crate::usecases::usage::KeyUsage;
+
+/// Columns of csv, tsv and table output in order of serialized fields.
+///
+/// Columns are fixed for type, so optional fields and empty results don't change the header.
+pub trait TableColumns {
+    fn get_columns() -> Vec<&'static str>;
+}
+
+impl TableColumns for AuthorizedKey {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["key_type", "key", "id", "row_index"]
+    }
+}
+
+impl<T: Display + Serialize + TableColumns> TableColumns for FingerprintedKey<T> {
+    fn get_columns() -> Vec<&'static str> {
+        let mut columns = T::get_columns();
+        columns.extend(["fingerprint", "md5_fingerprint", "bits"]);
+        columns
+    }
+}
+
+impl TableColumns for RemovalCandidate {
+    fn get_columns() -> Vec<&'static str> {
+        let mut columns = AuthorizedKey::get_columns();
+        columns.extend(["reason", "last_seen", "days_since"]);
+        columns
+    }
+}
+
+impl TableColumns for KeyRecord {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["user", "file", "row", "type", "bits", "fingerprint", "comment", "last_seen", "days_since", "reason"]
+    }
+}
+
+impl TableColumns for AuditFinding {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["rule", "file", "line", "fingerprint", "key_id", "message"]
+    }
+}
+
+impl TableColumns for LintIssue {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["rule", "line", "message", "suggestion"]
+    }
+}
+
+impl TableColumns for PermissionIssue {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["path", "problem", "message", "fixed"]
+    }
+}
+
+impl TableColumns for SyncAction {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["action", "line", "fingerprint", "entry", "previous_entry"]
+    }
+}
+
+impl TableColumns for FoundKey {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["username", "file", "row_index", "fingerprint", "key_type", "key_id", "last_seen"]
+    }
+}
+
+impl TableColumns for RevokeRecord {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["timestamp", "action", "username", "file", "row_index", "fingerprint", "message"]
+    }
+}
+
+impl TableColumns for RevokedKey {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["kind", "fingerprint", "key"]
+    }
+}
+
+impl TableColumns for RevokedKeyEntry {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["username", "file", "row_index", "fingerprint", "key_type", "key_id"]
+    }
+}
+
+impl TableColumns for CertificateAuthority {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["username", "file", "row_index", "fingerprint", "key_type", "key_id", "certificates"]
+    }
+}
+
+impl TableColumns for KeyUsage {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["username", "file", "row_index", "fingerprint", "key_type", "key_id", "logins", "first_seen", "last_seen",
+             "source_addresses"]
+    }
+}
+
+impl TableColumns for KeyFailures {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["fingerprint", "key_type", "key_id", "failed", "postponed", "usernames", "first_seen", "last_seen",
+             "source_addresses"]
+    }
+}
+
+impl TableColumns for SessionStats {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["name", "key_id", "sessions", "closed_sessions", "total_duration_seconds", "median_duration_seconds",
+             "first_seen", "last_seen"]
+    }
+}
+
+impl TableColumns for FromSuggestion {
+    fn get_columns() -> Vec<&'static str> {
+        vec!["action", "username", "file", "row_index", "fingerprint", "key_id", "logins", "current", "suggested",
+             "message"]
+    }
+}
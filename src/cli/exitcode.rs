@@ -0,0 +1,107 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{EXIT_CODE_CANDIDATES_FOUND, EXIT_CODE_ISSUES_FOUND, EXIT_CODE_LOG_COVERAGE_INSUFFICIENT};
+
+/// show-keys reports keys for removal only, policy checks are done by other commands
+pub const SHOW_KEYS_FAIL_ON_VALUES: [&str; 3] = ["candidates", "coverage", "none"];
+
+/// audit, lint, check-perms and krl check
+pub const VIOLATIONS_FAIL_ON_VALUES: [&str; 2] = ["violations", "none"];
+
+/// Findings which cause non-zero exit code
+#[derive(Clone, PartialEq, Debug)]
+pub enum FailOn {
+    /// Keys for removal were found
+    Candidates,
+
+    /// Audit findings, lint and permission issues, revoked keys
+    Violations,
+
+    /// Auth logs cover less days than stale keys threshold
+    Coverage,
+
+    None
+}
+
+impl From<&str> for FailOn {
+    fn from(value: &str) -> Self {
+        let lowercase_value = value.to_lowercase();
+
+        match lowercase_value.as_str() {
+            "candidates" => FailOn::Candidates,
+            "violations" => FailOn::Violations,
+            "coverage" => FailOn::Coverage,
+            _ => FailOn::None
+        }
+    }
+}
+
+impl Display for FailOn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            FailOn::Candidates => write!(f, "candidates"),
+            FailOn::Violations => write!(f, "violations"),
+            FailOn::Coverage => write!(f, "coverage"),
+            FailOn::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Without `--fail-on` option coverage doesn't affect exit code, logs are often kept for less days than threshold.
+pub fn get_default_fail_on() -> Vec<FailOn> {
+    vec![FailOn::Candidates, FailOn::Violations]
+}
+
+/// `none` disables all findings, so it can't be combined with other values.
+pub fn check_fail_on(fail_on: &[FailOn]) -> anyhow::Result<()> {
+    if fail_on.contains(&FailOn::None) && fail_on.len() > 1 {
+        let values = fail_on.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(",");
+        anyhow::bail!("invalid --fail-on '{values}': 'none' can't be combined with other values")
+    }
+
+    Ok(())
+}
+
+/// Exit code for `found` findings selected by `fail_on`, `0` if nothing was selected.
+///
+/// Insufficient coverage goes first: candidates can't be trusted without enough logs.
+pub fn get_exit_code(fail_on: &[FailOn], found: &[FailOn]) -> i32 {
+    let is_failed = |kind: FailOn| fail_on.contains(&kind) && found.contains(&kind);
+
+    if is_failed(FailOn::Coverage) {
+        EXIT_CODE_LOG_COVERAGE_INSUFFICIENT
+
+    } else if is_failed(FailOn::Violations) {
+        EXIT_CODE_ISSUES_FOUND
+
+    } else if is_failed(FailOn::Candidates) {
+        EXIT_CODE_CANDIDATES_FOUND
+
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod exit_code_tests {
+    use crate::cli::exitcode::{check_fail_on, FailOn, get_default_fail_on, get_exit_code};
+
+    #[test]
+    fn only_selected_findings_should_cause_non_zero_exit_code() {
+        let found = vec![FailOn::Candidates, FailOn::Coverage];
+
+        assert_eq!(get_exit_code(&get_default_fail_on(), &found), 2);
+        assert_eq!(get_exit_code(&get_default_fail_on(), &[]), 0);
+        assert_eq!(get_exit_code(&[FailOn::Candidates, FailOn::Coverage], &found), 4);
+        assert_eq!(get_exit_code(&[FailOn::Violations], &found), 0);
+        assert_eq!(get_exit_code(&[FailOn::None], &found), 0);
+        assert_eq!(get_exit_code(&get_default_fail_on(), &[FailOn::Candidates, FailOn::Violations]), 3);
+    }
+
+    #[test]
+    fn none_should_not_be_combined_with_other_values() {
+        assert!(check_fail_on(&[FailOn::None]).is_ok());
+        assert!(check_fail_on(&[FailOn::Candidates, FailOn::Coverage]).is_ok());
+        assert!(check_fail_on(&[FailOn::None, FailOn::Candidates]).is_err());
+    }
+}
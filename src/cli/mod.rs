@@ -1,33 +1,137 @@
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::exit;
 
-use clap::{Arg, ArgMatches, Command, value_parser};
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use serde::Serialize;
 
-use crate::cli::output::OutputFormat;
+use crate::EXIT_CODE_ERROR;
+use crate::cli::exitcode::{FailOn, SHOW_KEYS_FAIL_ON_VALUES, VIOLATIONS_FAIL_ON_VALUES};
+use crate::cli::output::{OUTPUT_FORMAT_VALUES, OutputFormat, REPORT_OUTPUT_FORMAT_VALUES, SARIF_OUTPUT_FORMAT_VALUES};
 use crate::logging::get_logging_config;
+use crate::usecases::fingerprints::FingerprintFormat;
+use crate::usecases::stats::StatsGroup;
 
+pub mod columns;
+pub mod exitcode;
 pub mod output;
+pub mod report;
+pub mod sarif;
 
-const USER_HOME_VAR: &'static str = "HOME";
+const USER_HOME_VAR: &str = "HOME";
 
 pub const LOG_LEVEL_ARGUMENT: &str = "log-level";
 pub const LOG_LEVEL_DEFAULT_VALUE: &str = "off";
 
+pub const CONFIG_ARGUMENT: &str = "config";
+
 pub const SHOW_KEYS_COMMAND: &str = "show-keys";
 
+pub const AUDIT_COMMAND: &str = "audit";
+
+pub const SYNC_COMMAND: &str = "sync";
+
+pub const LINT_COMMAND: &str = "lint";
+
+pub const CHECK_PERMS_COMMAND: &str = "check-perms";
+
+pub const FIND_COMMAND: &str = "find";
+
+pub const REVOKE_COMMAND: &str = "revoke";
+
+pub const CERTS_COMMAND: &str = "certs";
+
+pub const KEY_USAGE_COMMAND: &str = "key-usage";
+
+pub const KEY_FAILURES_COMMAND: &str = "key-failures";
+
+pub const SUGGEST_FROM_COMMAND: &str = "suggest-from";
+
+pub const STATS_COMMAND: &str = "stats";
+
+pub const METRICS_COMMAND: &str = "metrics";
+
+pub const KRL_COMMAND: &str = "krl";
+
+pub const KRL_LIST_COMMAND: &str = "list";
+
+pub const KRL_ADD_COMMAND: &str = "add";
+
+pub const KRL_CHECK_COMMAND: &str = "check";
+
+pub const QUERY_ARGUMENT: &str = "query";
+
 pub const OLDER_THAN_DAYS_OPTION: &str = "older-than-days";
-pub const OLDER_THAN_DAYS_DEFAULT_VALUE: usize = 31;
 
 pub const AUTH_LOG_PATH_OPTION: &str = "auth-log-path";
-pub const DEFAULT_AUTH_LOG_PATH: &str = "/var/log";
 
 pub const FILE_OPTION: &str = "file-path";
 
 pub const FORMAT_OPTION: &str = "format";
 
+pub const FINGERPRINT_OPTION: &str = "fingerprint";
+
+pub const PROTECTED_KEYS_FILE_OPTION: &str = "protected-keys-file";
+
+pub const USER_OPTION: &str = "user";
+
+pub const FROM_OPTION: &str = "from";
+
+pub const DRY_RUN_OPTION: &str = "dry-run";
+
+pub const FIX_OPTION: &str = "fix";
+
+pub const ALL_USERS_OPTION: &str = "all-users";
+
+pub const REVOKED_KEYS_FILE_OPTION: &str = "revoked-keys-file";
+
+pub const IPS_OPTION: &str = "ips";
+
+pub const IPV4_PREFIX_OPTION: &str = "ipv4-prefix";
+
+pub const IPV6_PREFIX_OPTION: &str = "ipv6-prefix";
+
+pub const APPLY_OPTION: &str = "apply";
+
+pub const SHARED_KEYS_OPTION: &str = "shared-keys";
+
+pub const BY_OPTION: &str = "by";
+
+pub const OUTPUT_OPTION: &str = "output";
+
+pub const FAIL_ON_OPTION: &str = "fail-on";
+
+const USER_VAR: &str = "USER";
+
+const HOSTNAME_VAR: &str = "HOSTNAME";
+
+const HOSTNAME_FILE_PATH: &str = "/proc/sys/kernel/hostname";
+
 const VERSION: &str = "0.2.1";
 
+/// show-keys options for JSON output
+#[derive(Serialize)]
+pub struct ShowKeysParameters {
+    pub file: PathBuf,
+
+    pub older_than_days: Option<usize>,
+
+    pub auth_log_path: Option<PathBuf>,
+}
+
 pub fn get_cli_app() -> ArgMatches {
+    get_cli_command()
+        .try_get_matches()
+        .unwrap_or_else(|e| {
+            // clap uses exit code 2 for usage errors, it's reserved for candidates found
+            let _ = e.print();
+            exit(if e.use_stderr() { EXIT_CODE_ERROR } else { 0 })
+        })
+}
+
+fn get_cli_command() -> Command {
     Command::new("akt")
         .about("Authorized Keys Tool for SSH")
         .version(VERSION)
@@ -41,22 +145,32 @@ pub fn get_cli_app() -> ArgMatches {
                 .default_value(LOG_LEVEL_DEFAULT_VALUE)
         )
 
+        .arg(
+            Arg::new(CONFIG_ARGUMENT)
+                .help("set path to config file. \
+                       default: /etc/akt/akt.toml and ~/.config/akt/akt.toml")
+                .long(CONFIG_ARGUMENT)
+                .value_parser(value_parser!(PathBuf))
+                .required(false)
+        )
+
         .subcommand(
             Command::new(SHOW_KEYS_COMMAND)
                 .about("Show keys which used older than days")
                 .arg(
                     Arg::new(OLDER_THAN_DAYS_OPTION)
-                        .help("set days")
+                        .help("set days. without value `older_than_days` from config is used (default: 31), \
+                               candidates are also shown without option if it's set in config")
                         .value_parser(value_parser!(usize))
                         .long(OLDER_THAN_DAYS_OPTION)
+                        .num_args(0..=1)
                         .required(false)
                 )
                 .arg(
                     Arg::new(AUTH_LOG_PATH_OPTION)
-                        .help("set path to auth logs")
+                        .help("set path to auth logs. default: /var/log")
                         .value_parser(value_parser!(PathBuf))
                         .long(AUTH_LOG_PATH_OPTION)
-                        .default_value(DEFAULT_AUTH_LOG_PATH)
                         .required(false)
                 )
                 .arg(
@@ -66,26 +180,628 @@ pub fn get_cli_app() -> ArgMatches {
                         .long(FILE_OPTION)
                         .required(false)
                 )
+                .arg(
+                    Arg::new(PROTECTED_KEYS_FILE_OPTION)
+                        .help("set path to protected keys file (fingerprints or key id patterns), \
+                               such keys are never shown as candidates for removal. \
+                               default: /etc/akt/protected-keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(PROTECTED_KEYS_FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FINGERPRINT_OPTION)
+                        .help("prefix keys with fingerprints. Possible values: sha256, md5, both")
+                        .long(FINGERPRINT_OPTION)
+                        .value_parser(value_parser!(FingerprintFormat))
+                        .required(false)
+                )
+
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv, markdown, html")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(REPORT_OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FAIL_ON_OPTION)
+                        .help("set findings which cause non-zero exit code, comma separated: \
+                               candidates (exit code 2), coverage (4, auth logs don't cover threshold days up to now), none. \
+                               default: candidates")
+                        .long(FAIL_ON_OPTION)
+                        .value_parser(PossibleValuesParser::new(SHOW_KEYS_FAIL_ON_VALUES).map(|value| FailOn::from(value.as_str())))
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(AUDIT_COMMAND)
+                .about("Check keys against policy rules from config")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(USER_OPTION)
+                        .help("set user for policy rules (required options). default: current user")
+                        .long(USER_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(SHARED_KEYS_OPTION)
+                        .help("report keys shared by several people: keys in authorized_keys files of many users \
+                               (from /etc/passwd), keys used from many addresses or in concurrent sessions")
+                        .long(SHARED_KEYS_OPTION)
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs for shared keys report. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv, sarif")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(SARIF_OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FAIL_ON_OPTION)
+                        .help("set findings which cause non-zero exit code, comma separated: violations (exit code 3), none. \
+                               default: violations")
+                        .long(FAIL_ON_OPTION)
+                        .value_parser(PossibleValuesParser::new(VIOLATIONS_FAIL_ON_VALUES).map(|value| FailOn::from(value.as_str())))
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(SYNC_COMMAND)
+                .about("Reconcile authorized_keys file with desired keys file")
+                .arg(
+                    Arg::new(FROM_OPTION)
+                        .help("set path to desired keys file")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FROM_OPTION)
+                        .required(true)
+                )
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(PROTECTED_KEYS_FILE_OPTION)
+                        .help("set path to protected keys file, such keys are never removed. \
+                               default: /etc/akt/protected-keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(PROTECTED_KEYS_FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(DRY_RUN_OPTION)
+                        .help("show plan without changing authorized_keys file")
+                        .long(DRY_RUN_OPTION)
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
 
+        .subcommand(
+            Command::new(LINT_COMMAND)
+                .about("Report malformed lines of authorized_keys file, exit with non-zero code if any found")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
                 .arg(
                     Arg::new(FORMAT_OPTION)
-                        .help("set output format. Possible values: default, json")
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv, sarif")
                         .long(FORMAT_OPTION)
-                        .value_parser(value_parser!(OutputFormat))
+                        .value_parser(PossibleValuesParser::new(SARIF_OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FAIL_ON_OPTION)
+                        .help("set findings which cause non-zero exit code, comma separated: violations (exit code 3), none. \
+                               default: violations")
+                        .long(FAIL_ON_OPTION)
+                        .value_parser(PossibleValuesParser::new(VIOLATIONS_FAIL_ON_VALUES).map(|value| FailOn::from(value.as_str())))
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
                         .required(false)
                 )
         )
 
-        .get_matches()
+        .subcommand(
+            Command::new(CHECK_PERMS_COMMAND)
+                .about("Check modes and owners of authorized_keys file and its directories like sshd StrictModes does")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file. default: ~/.ssh/authorized_keys of user")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(USER_OPTION)
+                        .help("set user which logs in with keys from file. default: current user")
+                        .long(USER_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FIX_OPTION)
+                        .help("fix bad modes and owners inside user's home")
+                        .long(FIX_OPTION)
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FAIL_ON_OPTION)
+                        .help("set findings which cause non-zero exit code, comma separated: violations (exit code 3), none. \
+                               default: violations")
+                        .long(FAIL_ON_OPTION)
+                        .value_parser(PossibleValuesParser::new(VIOLATIONS_FAIL_ON_VALUES).map(|value| FailOn::from(value.as_str())))
+                        .value_delimiter(',')
+                        .action(ArgAction::Append)
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(FIND_COMMAND)
+                .about("Find key by fingerprint, key id (comment) substring or public key file")
+                .arg(
+                    Arg::new(QUERY_ARGUMENT)
+                        .help("SHA256:... or MD5:... fingerprint, path to .pub file or key id substring")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("search authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs for last seen info. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(REVOKE_COMMAND)
+                .about("Remove key from authorized_keys files of all users and add it to revoked keys file")
+                .arg(
+                    Arg::new(QUERY_ARGUMENT)
+                        .help("SHA256:... or MD5:... fingerprint or path to .pub file")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("remove key only from this authorized_keys file. \
+                               default: files of all users from /etc/passwd")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(REVOKED_KEYS_FILE_OPTION)
+                        .help("set path to revoked keys file (plain or KRL). default: RevokedKeys from sshd config")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(REVOKED_KEYS_FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(DRY_RUN_OPTION)
+                        .help("show changes without changing files")
+                        .long(DRY_RUN_OPTION)
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(CERTS_COMMAND)
+                .about("Show cert-authority entries and certificates (key id, serial) used to login")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file. default: ~/.ssh/authorized_keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("show entries from authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(KEY_USAGE_COMMAND)
+                .about("Show logins with each key from auth logs: count, first and last seen")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file. default: ~/.ssh/authorized_keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("show keys from authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(IPS_OPTION)
+                        .help("show distinct source addresses for each key")
+                        .long(IPS_OPTION)
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(KEY_FAILURES_COMMAND)
+                .about("Show failed and unfinished public key attempts from auth logs by key")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file to get key ids. default: ~/.ssh/authorized_keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("get key ids from authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(STATS_COMMAND)
+                .about("Show session counts and durations from auth logs by key or by user")
+                .arg(
+                    Arg::new(BY_OPTION)
+                        .help("group sessions by key or by user. Possible values: key, user. default: key")
+                        .long(BY_OPTION)
+                        .value_parser(value_parser!(StatsGroup))
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file to get key ids. default: ~/.ssh/authorized_keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("get key ids from authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(METRICS_COMMAND)
+                .about("Write key metrics in Prometheus text format for node_exporter textfile collector")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file. default: ~/.ssh/authorized_keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("check authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(OLDER_THAN_DAYS_OPTION)
+                        .help("set days for stale keys. default: `older_than_days` from config (31)")
+                        .value_parser(value_parser!(usize))
+                        .long(OLDER_THAN_DAYS_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(PROTECTED_KEYS_FILE_OPTION)
+                        .help("set path to protected keys file, such keys are never counted as stale. \
+                               default: /etc/akt/protected-keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(PROTECTED_KEYS_FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(OUTPUT_OPTION)
+                        .help("write metrics to file instead of stdout, \
+                               i.e. /var/lib/node_exporter/textfile_collector/akt.prom")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(OUTPUT_OPTION)
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(SUGGEST_FROM_COMMAND)
+                .about("Suggest from=\"...\" option for each key by source addresses of its logins")
+                .arg(
+                    Arg::new(FILE_OPTION)
+                        .help("set path to authorized_keys file. default: ~/.ssh/authorized_keys")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(FILE_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(ALL_USERS_OPTION)
+                        .help("suggest for authorized_keys files of all users from /etc/passwd, \
+                               file locations are taken from sshd config (AuthorizedKeysFile)")
+                        .long(ALL_USERS_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(FILE_OPTION)
+                )
+                .arg(
+                    Arg::new(IPV4_PREFIX_OPTION)
+                        .help("network prefix length for IPv4 addresses, i.e. 24 for 10.0.0.0/24")
+                        .long(IPV4_PREFIX_OPTION)
+                        .value_parser(value_parser!(u8).range(0..=32))
+                        .default_value("32")
+                )
+                .arg(
+                    Arg::new(IPV6_PREFIX_OPTION)
+                        .help("network prefix length for IPv6 addresses, i.e. 64 for 2001:db8::/64")
+                        .long(IPV6_PREFIX_OPTION)
+                        .value_parser(value_parser!(u8).range(0..=128))
+                        .default_value("128")
+                )
+                .arg(
+                    Arg::new(APPLY_OPTION)
+                        .help("add suggested option to entries without from option")
+                        .long(APPLY_OPTION)
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(DRY_RUN_OPTION)
+                        .help("show what would be changed without writing files")
+                        .long(DRY_RUN_OPTION)
+                        .action(ArgAction::SetTrue)
+                        .requires(APPLY_OPTION)
+                )
+                .arg(
+                    Arg::new(AUTH_LOG_PATH_OPTION)
+                        .help("set path to auth logs. default: /var/log")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(AUTH_LOG_PATH_OPTION)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .required(false)
+                )
+        )
+
+        .subcommand(
+            Command::new(KRL_COMMAND)
+                .about("Manage revoked keys file (RevokedKeys), binary KRL or plain")
+                .subcommand_required(true)
+                .arg(
+                    Arg::new(REVOKED_KEYS_FILE_OPTION)
+                        .help("set path to revoked keys file. default: RevokedKeys from sshd config")
+                        .value_parser(value_parser!(PathBuf))
+                        .long(REVOKED_KEYS_FILE_OPTION)
+                        .global(true)
+                        .required(false)
+                )
+                .arg(
+                    Arg::new(FORMAT_OPTION)
+                        .help("set output format. Possible values: default, json, ndjson, csv, tsv")
+                        .long(FORMAT_OPTION)
+                        .value_parser(PossibleValuesParser::new(OUTPUT_FORMAT_VALUES)
+                            .map(|value| OutputFormat::from(value.as_str())))
+                        .global(true)
+                        .required(false)
+                )
+                .subcommand(
+                    Command::new(KRL_LIST_COMMAND)
+                        .about("List revoked keys and fingerprints")
+                )
+                .subcommand(
+                    Command::new(KRL_ADD_COMMAND)
+                        .about("Add key to KRL file without changing authorized_keys files, KRL file is created if it doesn't exist")
+                        .arg(
+                            Arg::new(QUERY_ARGUMENT)
+                                .help("SHA256:... fingerprint or path to .pub file")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new(DRY_RUN_OPTION)
+                                .help("show changes without changing files")
+                                .long(DRY_RUN_OPTION)
+                                .action(ArgAction::SetTrue)
+                        )
+                )
+                .subcommand(
+                    Command::new(KRL_CHECK_COMMAND)
+                        .about("Show keys from authorized_keys which are revoked. Exit code is 3 if any key was found")
+                        .arg(
+                            Arg::new(FILE_OPTION)
+                                .help("set path to authorized_keys file. default: ~/.ssh/authorized_keys")
+                                .value_parser(value_parser!(PathBuf))
+                                .long(FILE_OPTION)
+                                .required(false)
+                        )
+                        .arg(
+                            Arg::new(ALL_USERS_OPTION)
+                                .help("check authorized_keys files of all users from /etc/passwd, \
+                                       file locations are taken from sshd config (AuthorizedKeysFile)")
+                                .long(ALL_USERS_OPTION)
+                                .action(ArgAction::SetTrue)
+                                .conflicts_with(FILE_OPTION)
+                        )
+                        .arg(
+                            Arg::new(FAIL_ON_OPTION)
+                                .help("set findings which cause non-zero exit code, comma separated: violations (exit code 3), none. \
+                                       default: violations")
+                                .long(FAIL_ON_OPTION)
+                                .value_parser(PossibleValuesParser::new(VIOLATIONS_FAIL_ON_VALUES).map(|value| FailOn::from(value.as_str())))
+                                .value_delimiter(',')
+                                .action(ArgAction::Append)
+                                .required(false)
+                        )
+                )
+        )
 }
 
 pub fn init_logging(matches: &ArgMatches) {
-    let log_level: &str;
-
-    match matches.get_one::<String>(LOG_LEVEL_ARGUMENT) {
-        Some(log_level_arg) => log_level = log_level_arg,
-        None => log_level = LOG_LEVEL_DEFAULT_VALUE
-    }
+    let log_level: &str = match matches.get_one::<String>(LOG_LEVEL_ARGUMENT) {
+        Some(log_level_arg) => log_level_arg,
+        None => LOG_LEVEL_DEFAULT_VALUE
+    };
 
     let logging_config = get_logging_config(log_level);
     log4rs::init_config(logging_config).expect("unable to init logging module");
@@ -93,8 +809,45 @@ pub fn init_logging(matches: &ArgMatches) {
 
 pub fn get_default_authorized_keys_file_path() -> PathBuf {
     let home_var = env::var_os(USER_HOME_VAR)
-        .expect(&format!("unexpected error: ${USER_HOME_VAR} variable isn't defined"));
-    let home_var_str = home_var.into_string().expect(&format!("unsupported value in ${USER_HOME_VAR} variable"));
+        .unwrap_or_else(|| panic!("unexpected error: ${USER_HOME_VAR} variable isn't defined"));
+    let home_var_str = home_var.into_string()
+        .unwrap_or_else(|_| panic!("unsupported value in ${USER_HOME_VAR} variable"));
 
     Path::new(&home_var_str).join(".ssh").join("authorized_keys")
-}
\ No newline at end of file
+}
+
+pub fn get_current_username() -> String {
+    env::var(USER_VAR).unwrap_or_default()
+}
+
+pub fn get_hostname() -> String {
+    match fs::read_to_string(HOSTNAME_FILE_PATH) {
+        Ok(hostname) => hostname.trim().to_string(),
+        Err(_) => env::var(HOSTNAME_VAR).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use crate::cli::get_cli_command;
+
+    #[test]
+    fn sarif_format_should_be_accepted_by_audit_and_lint_only() {
+        for command in ["audit", "lint"] {
+            assert!(get_cli_command().try_get_matches_from(["akt", command, "--format", "sarif"]).is_ok());
+        }
+
+        assert!(get_cli_command().try_get_matches_from(["akt", "revoke", "SHA256:abc", "--format", "sarif"]).is_err());
+        assert!(get_cli_command().try_get_matches_from(["akt", "suggest-from", "--apply", "--format", "sarif"]).is_err());
+        assert!(get_cli_command().try_get_matches_from(["akt", "show-keys", "--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn fail_on_should_accept_findings_of_command_only() {
+        assert!(get_cli_command().try_get_matches_from(["akt", "show-keys", "--fail-on", "candidates,coverage"]).is_ok());
+        assert!(get_cli_command().try_get_matches_from(["akt", "show-keys", "--fail-on", "violations"]).is_err());
+        assert!(get_cli_command().try_get_matches_from(["akt", "audit", "--fail-on", "violations"]).is_ok());
+        assert!(get_cli_command().try_get_matches_from(["akt", "audit", "--fail-on", "candidates"]).is_err());
+        assert!(get_cli_command().try_get_matches_from(["akt", "lint", "--fail-on", "coverage"]).is_err());
+    }
+}
@@ -1,13 +1,28 @@
 use std::fmt::{Display, Formatter};
 use std::process::exit;
 
+use chrono::{Local, SecondsFormat};
 use serde::Serialize;
+use serde_json::Value;
 
+use crate::authlog::LogCoverage;
+use crate::cli::columns::TableColumns;
+use crate::cli::get_hostname;
 use crate::EXIT_CODE_ERROR;
 
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+pub const OUTPUT_FORMAT_VALUES: [&str; 5] = ["default", "json", "ndjson", "csv", "tsv"];
+
+/// Markdown and html report is produced by show-keys command only
+pub const REPORT_OUTPUT_FORMAT_VALUES: [&str; 7] = ["default", "json", "ndjson", "csv", "tsv", "markdown", "html"];
+
+/// Sarif is supported by audit and lint commands only
+pub const SARIF_OUTPUT_FORMAT_VALUES: [&str; 6] = ["default", "json", "ndjson", "csv", "tsv", "sarif"];
+
 #[derive(Clone)]
 pub enum OutputFormat {
-    Default, Json
+    Default, Json, Ndjson, Csv, Tsv, Markdown, Html, Sarif
 }
 
 impl From<&str> for OutputFormat {
@@ -16,6 +31,12 @@ impl From<&str> for OutputFormat {
 
         match lowercase_value.as_str() {
             "json" => OutputFormat::Json,
+            "ndjson" => OutputFormat::Ndjson,
+            "csv" => OutputFormat::Csv,
+            "tsv" => OutputFormat::Tsv,
+            "markdown" => OutputFormat::Markdown,
+            "html" => OutputFormat::Html,
+            "sarif" => OutputFormat::Sarif,
             _ => OutputFormat::Default
         }
     }
@@ -25,28 +46,316 @@ impl Display for OutputFormat {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match &self {
             OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Html => write!(f, "html"),
+            OutputFormat::Sarif => write!(f, "sarif"),
             _ => write!(f, "default")
         }
     }
 }
 
-pub fn print_results(results: &mut Vec<(impl Display + Serialize)>, format: OutputFormat) {
+pub fn print_results<T: Display + Serialize + TableColumns>(results: &mut Vec<T>, format: OutputFormat) {
     match format {
         OutputFormat::Json => print_as_json(results),
+        OutputFormat::Ndjson => results.iter().for_each(print_as_ndjson_record),
+        OutputFormat::Csv => print_as_csv(results, b','),
+        OutputFormat::Tsv => print_as_csv(results, b'\t'),
+        OutputFormat::Markdown | OutputFormat::Html => {
+            eprintln!("markdown and html formats are supported by show-keys command only");
+            exit(EXIT_CODE_ERROR)
+        }
+        OutputFormat::Sarif => {
+            eprintln!("sarif format is supported by audit and lint commands only");
+            exit(EXIT_CODE_ERROR)
+        }
         _ => print_as_is(results)
     }
 }
 
-pub fn print_as_is(input: &Vec<(impl Display + Serialize)>) {
+pub fn print_as_is(input: &[impl Display + Serialize]) {
     input.iter().for_each(|i| println!("{}", i))
 }
 
-pub fn print_as_json(input: &mut Vec<(impl Display + Serialize)>) {
+pub fn print_as_json(input: &mut Vec<impl Display + Serialize>) {
     match serde_json::to_string(&input) {
-        Ok(json) => print!("{json}"),
+        Ok(json) => println!("{json}"),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+/// Record as a single line of JSON, so output can be processed line by line.
+pub fn print_as_ndjson_record(record: &impl Serialize) {
+    match get_ndjson_line(record) {
+        Ok(line) => print!("{line}"),
         Err(e) => {
             eprintln!("{}", e);
             exit(EXIT_CODE_ERROR)
         }
     }
-}
\ No newline at end of file
+}
+
+/// Compact JSON is a single line, strings escape line breaks.
+pub fn get_ndjson_line(record: &impl Serialize) -> anyhow::Result<String> {
+    Ok(format!("{}\n", serde_json::to_string(record)?))
+}
+
+/// JSON output with metadata, structure is described in `schema/` directory.
+///
+/// `schema_version` is increased on incompatible changes only.
+#[derive(Serialize)]
+pub struct JsonEnvelope<'a, P: Serialize, T: Serialize> {
+    pub schema_version: u32,
+
+    pub host: String,
+
+    /// RFC 3339
+    pub generated_at: String,
+
+    /// Command options which affect results
+    pub parameters: P,
+
+    pub log_coverage: Option<LogCoverage>,
+
+    pub results: &'a [T],
+}
+
+impl<'a, P: Serialize, T: Serialize> JsonEnvelope<'a, P, T> {
+    pub fn new(parameters: P, log_coverage: Option<LogCoverage>, results: &'a [T]) -> JsonEnvelope<'a, P, T> {
+        JsonEnvelope {
+            schema_version: JSON_SCHEMA_VERSION,
+            host: get_hostname(),
+            generated_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            parameters,
+            log_coverage,
+            results,
+        }
+    }
+}
+
+pub fn print_as_json_envelope(envelope: &JsonEnvelope<impl Serialize, impl Serialize>) {
+    match serde_json::to_string(envelope) {
+        Ok(json) => println!("{json}"),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+/// Header is taken from columns of the type and is written for empty results too, nested values are written as JSON.
+/// Values with delimiter, quotes or line breaks are quoted.
+pub fn print_as_csv<T: Serialize + TableColumns>(input: &[T], delimiter: u8) {
+    match get_table(input).and_then(|(header, rows)| get_csv(&header, &rows, delimiter)) {
+        Ok(csv) => print!("{csv}"),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+/// Header from columns of the type and rows of values, empty values for nulls and skipped fields.
+fn get_table<T: Serialize + TableColumns>(input: &[T]) -> anyhow::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let header: Vec<String> = T::get_columns().iter().map(|column| column.to_string()).collect();
+    let mut rows: Vec<Vec<String>> = vec![];
+
+    for item in input {
+        let fields = match serde_json::to_value(item)? {
+            Value::Object(fields) => fields,
+            value => anyhow::bail!("unable to write '{value}' as table row")
+        };
+
+        rows.push(header.iter().map(|name| match fields.get(name) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(value)) => value.to_string(),
+            Some(value) => value.to_string()
+        }).collect());
+    }
+
+    Ok((header, rows))
+}
+
+fn get_csv(header: &[String], rows: &[Vec<String>], delimiter: u8) -> anyhow::Result<String> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(vec![]);
+
+    writer.write_record(header)?;
+
+    for row in rows {
+        writer.write_record(row)?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Pipes are escaped and line breaks are replaced with spaces, so values don't break the table.
+/// Empty string for table without columns, markdown table requires at least one.
+pub fn get_markdown_table(header: &[impl AsRef<str>], rows: &[Vec<String>]) -> String {
+    if header.is_empty() {
+        return String::new()
+    }
+
+    let escape = |value: &str| value.replace('|', "\\|").replace(['\r', '\n'], " ");
+
+    let mut table = format!("| {} |\n", header.iter().map(|name| escape(name.as_ref())).collect::<Vec<String>>().join(" | "));
+    table.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+
+    for row in rows {
+        table.push_str(&format!("| {} |\n", row.iter().map(|value| escape(value)).collect::<Vec<String>>().join(" | ")));
+    }
+
+    table
+}
+
+pub fn get_html_table(header: &[impl AsRef<str>], rows: &[Vec<String>]) -> String {
+    let mut table = String::from("<table>\n<thead>\n<tr>");
+
+    for name in header {
+        table.push_str(&format!("<th>{}</th>", escape_html(name.as_ref())));
+    }
+
+    table.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    for row in rows {
+        table.push_str("<tr>");
+
+        for value in row {
+            table.push_str(&format!("<td>{}</td>", escape_html(value)));
+        }
+
+        table.push_str("</tr>\n");
+    }
+
+    table.push_str("</tbody>\n</table>\n");
+
+    table
+}
+
+pub fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+         .replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod output_tests {
+    use std::collections::HashMap;
+
+    use authorized_keys::authorizedkeys::AuthorizedKey;
+    use serde::Serialize;
+    use serde_json::Value;
+
+    use crate::cli::columns::TableColumns;
+    use crate::cli::output::{get_csv, get_html_table, get_markdown_table, get_ndjson_line, get_table, JsonEnvelope};
+    use crate::usecases::fingerprints::get_fingerprinted_keys;
+    use crate::usecases::oldkeys::{RemovalCandidate, RemovalReason};
+
+    #[derive(Serialize)]
+    struct Record {
+        name: String,
+        count: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        comment: Option<String>,
+        tags: Vec<String>,
+    }
+
+    impl TableColumns for Record {
+        fn get_columns() -> Vec<&'static str> {
+            vec!["name", "count", "comment", "tags"]
+        }
+    }
+
+    #[test]
+    fn values_with_commas_and_quotes_should_be_quoted() {
+        let records = vec![
+            Record { name: "a@b.com".to_string(), count: 1, comment: None, tags: vec![] },
+            Record { name: "John \"JD\" Doe, ops".to_string(), count: 2, comment: Some("x".to_string()),
+                     tags: vec!["a".to_string()] },
+        ];
+
+        let (header, rows) = get_table(&records).unwrap();
+
+        assert_eq!(get_csv(&header, &rows, b',').unwrap(),
+                   "name,count,comment,tags\na@b.com,1,,[]\n\"John \"\"JD\"\" Doe, ops\",2,x,\"[\"\"a\"\"]\"\n");
+
+        assert_eq!(get_csv(&header, &rows, b'\t').unwrap(),
+                   "name\tcount\tcomment\ttags\na@b.com\t1\t\t[]\n\"John \"\"JD\"\" Doe, ops\"\t2\tx\t\"[\"\"a\"\"]\"\n");
+
+        let (header, rows) = get_table::<Record>(&[]).unwrap();
+
+        assert_eq!(get_csv(&header, &rows, b',').unwrap(), "name,count,comment,tags\n");
+    }
+
+    #[test]
+    fn table_values_should_be_escaped() {
+        let header = vec!["name", "comment"];
+        let rows = vec![vec!["a|b".to_string(), "<script>\"x\" & 'y'</script>".to_string()]];
+
+        assert_eq!(get_markdown_table(&header, &rows),
+                   "| name | comment |\n|---|---|\n| a\\|b | <script>\"x\" & 'y'</script> |\n");
+
+        assert!(get_html_table(&header, &rows)
+            .contains("<td>a|b</td><td>&lt;script&gt;&quot;x&quot; &amp; &#39;y&#39;&lt;/script&gt;</td>"));
+
+        assert_eq!(get_markdown_table(&Vec::<&str>::new(), &[]), "");
+    }
+
+    #[test]
+    fn each_ndjson_record_should_be_a_single_line() {
+        let records = [
+            Record { name: "a@b.com".to_string(), count: 1, comment: None, tags: vec![] },
+            Record { name: "c@d.com".to_string(), count: 2, comment: Some("line\nbreak".to_string()), tags: vec!["x".to_string()] },
+        ];
+
+        let output: String = records.iter().map(|record| get_ndjson_line(record).unwrap()).collect();
+
+        let lines: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["name"], "a@b.com");
+        assert!(lines[0].get("comment").is_none());
+        assert_eq!(lines[1]["comment"], "line\nbreak");
+        assert_eq!(lines[1]["tags"][0], "x");
+    }
+
+    #[test]
+    fn envelope_should_contain_fields_required_by_schema() {
+        let schema: Value = serde_json::from_str(include_str!("../../schema/show-keys.v1.schema.json")).unwrap();
+
+        let key = AuthorizedKey {
+            key_type: "ssh-ed25519".to_string(),
+            key: "AAAAC3NzaC1lZDI1NTE5AAAAIDOGSbgN43gI+oP5CebK7JsGWsMT69uymML4YHWUPI2G".to_string(),
+            id: "a@b.com".to_string(),
+            row_index: 0,
+        };
+
+        let candidate = RemovalCandidate { key: key.clone(), reason: RemovalReason::NotSeen, last_seen: None, days_since: None };
+
+        let keys = get_fingerprinted_keys(vec![key], None);
+        let candidates = get_fingerprinted_keys(vec![candidate], None);
+
+        let envelopes = vec![
+            serde_json::to_value(JsonEnvelope::new(HashMap::from([("file", "authorized_keys")]), None, &keys)).unwrap(),
+            serde_json::to_value(JsonEnvelope::new(HashMap::from([("file", "authorized_keys")]), None, &candidates)).unwrap(),
+        ];
+
+        for envelope in &envelopes {
+            assert_eq!(envelope["schema_version"], schema["properties"]["schema_version"]["const"]);
+
+            for field in schema["required"].as_array().unwrap() {
+                assert!(envelope.get(field.as_str().unwrap()).is_some(), "field {field} is missing");
+            }
+
+            for field in schema["$defs"]["key"]["required"].as_array().unwrap() {
+                assert!(envelope["results"][0].get(field.as_str().unwrap()).is_some(), "key field {field} is missing");
+            }
+        }
+
+        assert!(schema["$defs"]["key"]["properties"]["reason"]["enum"].as_array().unwrap()
+            .contains(&envelopes[1]["results"][0]["reason"]));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::cli::output::{escape_html, get_html_table, get_markdown_table, OutputFormat};
+use crate::usecases::audit::AuditFinding;
+use crate::usecases::records::KeyRecord;
+use crate::usecases::report::KeysReport;
+
+const REPORT_TITLE: &str = "authorized_keys report";
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; } \
+                          table { border-collapse: collapse; margin-bottom: 1em; } \
+                          th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; } \
+                          th { background: #f0f0f0; }";
+
+/// Table with title, rows are empty if there is nothing to report
+struct ReportSection {
+    title: &'static str,
+
+    header: Vec<&'static str>,
+
+    rows: Vec<Vec<String>>,
+}
+
+/// Self-contained report, html doesn't require external resources.
+pub fn print_report(report: &KeysReport, format: OutputFormat) {
+    match format {
+        OutputFormat::Html => print!("{}", get_html_report(report)),
+        _ => print!("{}", get_markdown_report(report))
+    }
+}
+
+fn get_markdown_report(report: &KeysReport) -> String {
+    let mut text = format!("# {REPORT_TITLE}\n\n");
+
+    for (name, value) in get_properties(report) {
+        text.push_str(&format!("- {name}: {value}\n"));
+    }
+
+    for section in get_sections(report) {
+        text.push_str(&format!("\n## {}\n\n", section.title));
+
+        if section.rows.is_empty() {
+            text.push_str("None.\n");
+
+        } else {
+            text.push_str(&get_markdown_table(&section.header, &section.rows));
+        }
+    }
+
+    text
+}
+
+fn get_html_report(report: &KeysReport) -> String {
+    let mut html = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{REPORT_TITLE}</title>\n\
+                            <style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{REPORT_TITLE}</h1>\n<ul>\n");
+
+    for (name, value) in get_properties(report) {
+        html.push_str(&format!("<li>{name}: {}</li>\n", escape_html(&value)));
+    }
+
+    html.push_str("</ul>\n");
+
+    for section in get_sections(report) {
+        html.push_str(&format!("<h2>{}</h2>\n", section.title));
+
+        if section.rows.is_empty() {
+            html.push_str("<p>None.</p>\n");
+
+        } else {
+            html.push_str(&get_html_table(&section.header, &section.rows));
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+
+    html
+}
+
+fn get_properties(report: &KeysReport) -> Vec<(&'static str, String)> {
+    vec![
+        ("Host", report.host.to_string()),
+        ("File", report.file.display().to_string()),
+        ("Generated at", report.generated_at.to_string()),
+        ("Stale threshold", format!("{} days", report.older_than_days)),
+        ("Log coverage", match &report.log_coverage {
+            Some(log_coverage) => log_coverage.to_string(),
+            None => "auth logs are unavailable".to_string()
+        }),
+    ]
+}
+
+fn get_sections(report: &KeysReport) -> Vec<ReportSection> {
+    let summary = &report.summary;
+
+    vec![
+        ReportSection {
+            title: "Summary",
+            header: vec!["Keys", "Stale", "Never seen", "Protected", "Weak", "Duplicates"],
+            rows: vec![
+                [summary.keys, summary.stale, summary.not_seen, summary.protected, summary.weak, summary.duplicates]
+                    .iter().map(|count| count.to_string()).collect()
+            ],
+        },
+        ReportSection {
+            title: "Stale keys",
+            header: vec!["Row", "Type", "Bits", "Fingerprint", "Comment", "Last seen", "Days since"],
+            rows: report.stale.iter().map(|record| {
+                let mut row = get_key_row(record);
+                row.push(record.last_seen.clone().unwrap_or_default());
+                row.push(record.days_since.map(|days| days.to_string()).unwrap_or_default());
+                row
+            }).collect(),
+        },
+        ReportSection {
+            title: "Never seen keys",
+            header: vec!["Row", "Type", "Bits", "Fingerprint", "Comment"],
+            rows: report.not_seen.iter().map(get_key_row).collect(),
+        },
+        ReportSection {
+            title: "Weak keys",
+            header: vec!["Line", "Fingerprint", "Key id", "Message"],
+            rows: report.weak.iter().map(get_finding_row).collect(),
+        },
+        ReportSection {
+            title: "Duplicates",
+            header: vec!["Line", "Fingerprint", "Key id", "Message"],
+            rows: report.duplicates.iter().map(get_finding_row).collect(),
+        },
+    ]
+}
+
+fn get_key_row(record: &KeyRecord) -> Vec<String> {
+    vec![
+        record.row.to_string(),
+        record.key_type.to_string(),
+        record.bits.map(|bits| bits.to_string()).unwrap_or_default(),
+        record.fingerprint.clone().unwrap_or_default(),
+        record.comment.to_string(),
+    ]
+}
+
+fn get_finding_row(finding: &AuditFinding) -> Vec<String> {
+    vec![
+        finding.line.to_string(),
+        finding.fingerprint.clone().unwrap_or_default(),
+        finding.key_id.to_string(),
+        finding.message.to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod report_output_tests {
+    use std::path::PathBuf;
+
+    use crate::cli::report::{get_html_report, get_markdown_report};
+    use crate::usecases::report::{KeysReport, ReportSummary};
+
+    #[test]
+    fn report_should_contain_summary_and_empty_sections() {
+        let report = KeysReport {
+            host: "web-1".to_string(),
+            file: PathBuf::from("/home/alice/.ssh/authorized_keys"),
+            generated_at: "2023-04-01 10:00:00".to_string(),
+            older_than_days: 31,
+            log_coverage: None,
+            summary: ReportSummary { keys: 2, stale: 0, not_seen: 0, protected: 0, weak: 0, duplicates: 0 },
+            stale: vec![],
+            not_seen: vec![],
+            weak: vec![],
+            duplicates: vec![],
+        };
+
+        let markdown = get_markdown_report(&report);
+
+        assert!(markdown.starts_with("# authorized_keys report\n\n- Host: web-1\n"));
+        assert!(markdown.contains("- Log coverage: auth logs are unavailable\n"));
+        assert!(markdown.contains("| Keys | Stale | Never seen | Protected | Weak | Duplicates |\n|---|---|---|---|---|---|\n| 2 | 0 | 0 | 0 | 0 | 0 |\n"));
+        assert!(markdown.contains("## Stale keys\n\nNone.\n"));
+
+        let html = get_html_report(&report);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Duplicates</h2>\n<p>None.</p>\n"));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
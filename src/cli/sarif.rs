@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::cli::VERSION;
+use crate::EXIT_CODE_ERROR;
+use crate::usecases::audit::{AuditFinding, AuditRule};
+use crate::usecases::lint::{LintIssue, LintRule};
+
+const SARIF_VERSION: &str = "2.1.0";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+const TOOL_NAME: &str = "akt";
+
+/// Finding for SARIF log, `line` starts from 1, `0` for findings about the whole file
+pub struct SarifResult {
+    pub rule_id: String,
+
+    /// `error`, `warning` or `note`
+    pub level: &'static str,
+
+    pub message: String,
+
+    pub file: PathBuf,
+
+    pub line: usize,
+}
+
+/// Findings without `file` belong to `file_path`.
+pub fn get_audit_sarif_results(findings: &[AuditFinding], file_path: &Path) -> Vec<SarifResult> {
+    findings.iter().map(|finding| SarifResult {
+        rule_id: finding.rule.to_string(),
+        level: get_audit_level(&finding.rule),
+        message: finding.message.to_string(),
+        file: finding.file.clone().unwrap_or_else(|| file_path.to_path_buf()),
+        line: finding.line,
+    }).collect()
+}
+
+pub fn get_lint_sarif_results(issues: &[LintIssue], file_path: &Path) -> Vec<SarifResult> {
+    issues.iter().map(|issue| SarifResult {
+        rule_id: issue.rule.to_string(),
+        level: get_lint_level(&issue.rule),
+        message: format!("{}. fix: {}", issue.message, issue.suggestion),
+        file: file_path.to_path_buf(),
+        line: issue.line,
+    }).collect()
+}
+
+pub fn print_as_sarif(results: &[SarifResult]) {
+    match serde_json::to_string(&get_sarif_log(results)) {
+        Ok(json) => println!("{json}"),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+fn get_sarif_log(results: &[SarifResult]) -> Value {
+    let mut rule_ids: Vec<&str> = vec![];
+
+    for result in results {
+        if !rule_ids.contains(&result.rule_id.as_str()) {
+            rule_ids.push(&result.rule_id);
+        }
+    }
+
+    let rules: Vec<Value> = rule_ids.iter().map(|rule_id| {
+        let level = results.iter().find(|result| &result.rule_id == rule_id).map(|result| result.level);
+        json!({ "id": rule_id, "defaultConfiguration": { "level": level } })
+    }).collect();
+
+    let results: Vec<Value> = results.iter().map(|result| {
+        let mut physical_location = json!({ "artifactLocation": { "uri": get_artifact_uri(&result.file) } });
+
+        if result.line > 0 {
+            physical_location["region"] = json!({ "startLine": result.line });
+        }
+
+        json!({
+            "ruleId": result.rule_id,
+            "ruleIndex": rule_ids.iter().position(|rule_id| rule_id == &result.rule_id),
+            "level": result.level,
+            "message": { "text": result.message },
+            "locations": [{ "physicalLocation": physical_location }]
+        })
+    }).collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": { "driver": { "name": TOOL_NAME, "version": VERSION, "rules": rules } },
+            "results": results
+        }]
+    })
+}
+
+/// Relative paths stay relative, so code scanning can match them with repository files.
+fn get_artifact_uri(path: &Path) -> String {
+    if path.is_absolute() {
+        return match Url::from_file_path(path) {
+            Ok(url) => url.to_string(),
+            Err(_) => path.display().to_string()
+        }
+    }
+
+    // relative path is encoded as absolute one, then root is cut off
+    match Url::from_file_path(Path::new("/").join(path)) {
+        Ok(url) => url.path().trim_start_matches('/').to_string(),
+        Err(_) => path.display().to_string()
+    }
+}
+
+fn get_audit_level(rule: &AuditRule) -> &'static str {
+    match rule {
+        AuditRule::UnparsableKey | AuditRule::KeyTypeNotAllowed | AuditRule::WeakKey |
+        AuditRule::InsecurePermissions => "error",
+        AuditRule::SecurityKeyOptionMisuse => "note",
+        _ => "warning"
+    }
+}
+
+fn get_lint_level(rule: &LintRule) -> &'static str {
+    match rule {
+        LintRule::WindowsLineEnding | LintRule::TrailingGarbage => "warning",
+        _ => "error"
+    }
+}
+
+#[cfg(test)]
+mod sarif_tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::cli::sarif::{get_artifact_uri, get_audit_sarif_results, get_sarif_log};
+    use crate::usecases::audit::{AuditFinding, AuditRule};
+
+    #[test]
+    fn findings_should_have_rules_levels_and_locations() {
+        let findings = vec![
+            get_finding(AuditRule::WeakKey, None, 2),
+            get_finding(AuditRule::InsecurePermissions, None, 0),
+            get_finding(AuditRule::SharedKey, Some(PathBuf::from("/home/bob/.ssh/authorized_keys")), 1),
+        ];
+
+        let log = get_sarif_log(&get_audit_sarif_results(&findings, Path::new("keys/alice authorized_keys")));
+
+        assert_eq!(log["version"], "2.1.0");
+
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 3);
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "weak-key");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results[0]["ruleId"], "weak-key");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "keys/alice%20authorized_keys");
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["region"]["startLine"], 2);
+        assert!(results[1]["locations"][0]["physicalLocation"].get("region").is_none());
+        assert_eq!(results[2]["level"], "warning");
+        assert_eq!(results[2]["ruleIndex"], 2);
+        assert_eq!(results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "file:///home/bob/.ssh/authorized_keys");
+    }
+
+    #[test]
+    fn artifact_uri_should_be_percent_encoded() {
+        assert_eq!(get_artifact_uri(Path::new("/home/bob/keys #1 100%?")), "file:///home/bob/keys%20%231%20100%25%3F");
+        assert_eq!(get_artifact_uri(Path::new("keys/ключи")), "keys/%D0%BA%D0%BB%D1%8E%D1%87%D0%B8");
+    }
+
+    fn get_finding(rule: AuditRule, file: Option<PathBuf>, line: usize) -> AuditFinding {
+        AuditFinding {
+            rule,
+            file,
+            line,
+            fingerprint: None,
+            key_id: "alice@laptop".to_string(),
+            message: "message".to_string(),
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::usecases::audit::DEFAULT_MIN_RSA_BITS;
+
+pub const SYSTEM_CONFIG_FILE_PATH: &str = "/etc/akt/akt.toml";
+
+const USER_CONFIG_FILE_PATH: &str = ".config/akt/akt.toml";
+
+const USER_HOME_VAR: &str = "HOME";
+
+const OLDER_THAN_DAYS_DEFAULT_VALUE: usize = 31;
+
+const DEFAULT_AUTH_LOG_PATH: &str = "/var/log";
+
+const DEFAULT_PROTECTED_KEYS_FILE_PATH: &str = "/etc/akt/protected-keys";
+
+const SHARED_KEY_MAX_USERS_DEFAULT_VALUE: usize = 1;
+
+const SHARED_KEY_MAX_ADDRESSES_DEFAULT_VALUE: usize = 10;
+
+const SHARED_KEY_MAX_NETWORKS_DEFAULT_VALUE: usize = 3;
+
+const SHARED_KEY_MAX_SESSIONS_DEFAULT_VALUE: usize = 3;
+
+/// Key hygiene policy, loaded from `akt.toml`.
+///
+/// All values are optional, defaults are applied by getters. CLI options override values from file.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AktConfig {
+    pub older_than_days: Option<usize>,
+
+    /// Directory with `auth.log`, `auth.log.X`, `auth.log.X.gz` files
+    pub auth_log_path: Option<PathBuf>,
+
+    pub protected_keys_file: Option<PathBuf>,
+
+    /// Fingerprints (`SHA256:...`) or key id patterns, same format as protected keys file
+    pub protected_keys: Vec<String>,
+
+    /// Key types allowed by policy, i.e. `ssh-ed25519`. All types are allowed if not set.
+    pub allowed_key_types: Option<Vec<String>>,
+
+    /// RSA keys shorter than this are weak, default is 2048. DSA keys are always weak.
+    pub min_rsa_bits: Option<usize>,
+
+    /// Regular expression for key id (comment)
+    pub comment_format: Option<String>,
+
+    /// Options required for keys by user, i.e. `root = ["from"]`
+    pub required_options: HashMap<String, Vec<String>>,
+
+    /// Security keys (FIDO) must require touch, `no-touch-required` option isn't allowed
+    pub require_touch: Option<bool>,
+
+    /// Security keys (FIDO) must have `verify-required` option (PIN or biometrics)
+    pub require_verify: Option<bool>,
+
+    /// Key in authorized_keys files of more users is reported as shared
+    pub shared_key_max_users: Option<usize>,
+
+    /// Key used from more distinct addresses is reported as shared
+    pub shared_key_max_addresses: Option<usize>,
+
+    /// Key used from more distinct networks (/24 for IPv4, /64 for IPv6) is reported as shared
+    pub shared_key_max_networks: Option<usize>,
+
+    /// Key with more concurrent sessions is reported as shared
+    pub shared_key_max_sessions: Option<usize>,
+}
+
+impl AktConfig {
+    pub fn get_older_than_days(&self) -> usize {
+        self.older_than_days.unwrap_or(OLDER_THAN_DAYS_DEFAULT_VALUE)
+    }
+
+    pub fn get_auth_log_path(&self) -> PathBuf {
+        self.auth_log_path.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_AUTH_LOG_PATH))
+    }
+
+    pub fn get_protected_keys_file(&self) -> PathBuf {
+        self.protected_keys_file.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_PROTECTED_KEYS_FILE_PATH))
+    }
+
+    pub fn get_required_options(&self, username: &str) -> Vec<String> {
+        self.required_options.get(username).cloned().unwrap_or_default()
+    }
+
+    pub fn get_shared_key_max_users(&self) -> usize {
+        self.shared_key_max_users.unwrap_or(SHARED_KEY_MAX_USERS_DEFAULT_VALUE)
+    }
+
+    pub fn get_shared_key_max_addresses(&self) -> usize {
+        self.shared_key_max_addresses.unwrap_or(SHARED_KEY_MAX_ADDRESSES_DEFAULT_VALUE)
+    }
+
+    pub fn get_shared_key_max_networks(&self) -> usize {
+        self.shared_key_max_networks.unwrap_or(SHARED_KEY_MAX_NETWORKS_DEFAULT_VALUE)
+    }
+
+    pub fn get_shared_key_max_sessions(&self) -> usize {
+        self.shared_key_max_sessions.unwrap_or(SHARED_KEY_MAX_SESSIONS_DEFAULT_VALUE)
+    }
+
+    /// Merges user config (`other`) into system config.
+    ///
+    /// User values take precedence, but audit policy can only be tightened: allowed key types are
+    /// intersected, user `min_rsa_bits` can't be less than system or default value, required options
+    /// are combined and `require_touch`/`require_verify` enabled in any file stay enabled.
+    /// Protected keys are combined.
+    fn merge(self, other: AktConfig) -> AktConfig {
+        let mut protected_keys = self.protected_keys;
+        protected_keys.extend(other.protected_keys);
+
+        let mut required_options = self.required_options;
+
+        for (username, options) in other.required_options {
+            let user_options = required_options.entry(username).or_default();
+
+            for option in options {
+                if !user_options.contains(&option) {
+                    user_options.push(option);
+                }
+            }
+        }
+
+        let allowed_key_types = match (self.allowed_key_types, other.allowed_key_types) {
+            (Some(system_types), Some(user_types)) => Some(
+                system_types.into_iter().filter(|key_type| user_types.contains(key_type)).collect()),
+            (system_types, user_types) => user_types.or(system_types)
+        };
+
+        AktConfig {
+            older_than_days: other.older_than_days.or(self.older_than_days),
+            auth_log_path: other.auth_log_path.or(self.auth_log_path),
+            protected_keys_file: other.protected_keys_file.or(self.protected_keys_file),
+            protected_keys,
+            allowed_key_types,
+            min_rsa_bits: other.min_rsa_bits
+                .map(|bits| bits.max(self.min_rsa_bits.unwrap_or(DEFAULT_MIN_RSA_BITS)))
+                .or(self.min_rsa_bits),
+            comment_format: other.comment_format.or(self.comment_format),
+            required_options,
+            require_touch: get_strictest_flag(self.require_touch, other.require_touch),
+            require_verify: get_strictest_flag(self.require_verify, other.require_verify),
+            shared_key_max_users: other.shared_key_max_users.or(self.shared_key_max_users),
+            shared_key_max_addresses: other.shared_key_max_addresses.or(self.shared_key_max_addresses),
+            shared_key_max_networks: other.shared_key_max_networks.or(self.shared_key_max_networks),
+            shared_key_max_sessions: other.shared_key_max_sessions.or(self.shared_key_max_sessions),
+        }
+    }
+}
+
+fn get_strictest_flag(system_value: Option<bool>, user_value: Option<bool>) -> Option<bool> {
+    match (system_value, user_value) {
+        (Some(system_value), Some(user_value)) => Some(system_value || user_value),
+        _ => user_value.or(system_value)
+    }
+}
+
+/// Loads config from `config_file_path` if specified.
+///
+/// Otherwise loads system config (`/etc/akt/akt.toml`) and user config (`~/.config/akt/akt.toml`),
+/// see `AktConfig::merge`. Missing files are skipped.
+pub fn load_config(config_file_path: Option<&PathBuf>) -> anyhow::Result<AktConfig> {
+    let user_home = env::var_os(USER_HOME_VAR).map(PathBuf::from);
+
+    load_config_for_user_home(config_file_path, user_home.as_deref())
+}
+
+/// Same as `load_config`, but user config is taken from `user_home` instead of `$HOME`,
+/// i.e. for the audited user. Without `user_home` only system config is loaded.
+pub fn load_config_for_user_home(config_file_path: Option<&PathBuf>,
+                                 user_home: Option<&Path>) -> anyhow::Result<AktConfig> {
+    match config_file_path {
+        Some(path) => get_config_from_file(path),
+        None => {
+            let mut config = AktConfig::default();
+
+            let mut config_paths = vec![PathBuf::from(SYSTEM_CONFIG_FILE_PATH)];
+
+            if let Some(home_path) = user_home {
+                config_paths.push(home_path.join(USER_CONFIG_FILE_PATH));
+            }
+
+            for config_path in config_paths {
+                if config_path.exists() {
+                    config = config.merge(get_config_from_file(&config_path)?);
+
+                } else {
+                    debug!("config file '{}' wasn't found, skip", config_path.display());
+                }
+            }
+
+            Ok(config)
+        }
+    }
+}
+
+fn get_config_from_file(file_path: &Path) -> anyhow::Result<AktConfig> {
+    info!("loading config from '{}'", file_path.display());
+
+    let content = fs::read_to_string(file_path)
+        .map_err(|e| anyhow::anyhow!("unable to read config file '{}': {}", file_path.display(), e))?;
+
+    get_config_from_str(&content)
+        .map_err(|e| anyhow::anyhow!("invalid config file '{}': {}", file_path.display(), e))
+}
+
+fn get_config_from_str(content: &str) -> anyhow::Result<AktConfig> {
+    let config: AktConfig = toml::from_str(content)?;
+    debug!("config: {:?}", config);
+    Ok(config)
+}
+
+#[cfg(test)]
+mod config_tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::config::{AktConfig, get_config_from_str, load_config_for_user_home};
+    use crate::tests_common::get_temp_dir;
+
+    #[test]
+    fn empty_config_should_use_defaults() {
+        let config = get_config_from_str("").unwrap();
+
+        assert_eq!(config, AktConfig::default());
+        assert_eq!(config.get_older_than_days(), 31);
+        assert_eq!(config.get_auth_log_path(), PathBuf::from("/var/log"));
+    }
+
+    #[test]
+    fn parse_all_values() {
+        let content = r#"
+            older_than_days = 90
+            auth_log_path = "/srv/logs"
+            protected_keys = ["SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8", "test@ansible"]
+            allowed_key_types = ["ssh-ed25519", "ssh-rsa"]
+            min_rsa_bits = 3072
+            comment_format = '^[a-z.]+@company\.com$'
+
+            [required_options]
+            root = ["from"]
+        "#;
+
+        let config = get_config_from_str(content).unwrap();
+
+        assert_eq!(config.get_older_than_days(), 90);
+        assert_eq!(config.get_auth_log_path(), PathBuf::from("/srv/logs"));
+        assert_eq!(config.protected_keys.len(), 2);
+        assert_eq!(config.allowed_key_types, Some(vec!["ssh-ed25519".to_string(), "ssh-rsa".to_string()]));
+        assert_eq!(config.min_rsa_bits, Some(3072));
+        assert_eq!(config.comment_format, Some("^[a-z.]+@company\\.com$".to_string()));
+        assert_eq!(config.get_required_options("root"), vec!["from".to_string()]);
+        assert!(config.get_required_options("deployer").is_empty());
+    }
+
+    #[test]
+    fn unknown_fields_should_be_rejected() {
+        assert!(get_config_from_str("older_than = 5").is_err());
+    }
+
+    #[test]
+    fn user_config_values_should_take_precedence() {
+        let system_config = get_config_from_str(r#"
+            older_than_days = 90
+            min_rsa_bits = 3072
+            protected_keys = ["break-glass@company.com"]
+        "#).unwrap();
+
+        let user_config = get_config_from_str(r#"
+            older_than_days = 30
+            protected_keys = ["test@ansible"]
+        "#).unwrap();
+
+        let config = system_config.merge(user_config);
+
+        assert_eq!(config.get_older_than_days(), 30);
+        assert_eq!(config.min_rsa_bits, Some(3072));
+        assert_eq!(config.protected_keys.len(), 2);
+    }
+
+    #[test]
+    fn user_config_should_not_loosen_policy() {
+        let system_config = get_config_from_str(r#"
+            allowed_key_types = ["ssh-ed25519", "ssh-rsa"]
+            min_rsa_bits = 3072
+            require_touch = true
+
+            [required_options]
+            root = ["from"]
+        "#).unwrap();
+
+        let user_config = get_config_from_str(r#"
+            allowed_key_types = ["ssh-rsa", "ssh-dss"]
+            min_rsa_bits = 1024
+            require_touch = false
+            require_verify = true
+
+            [required_options]
+            root = ["no-pty"]
+        "#).unwrap();
+
+        let config = system_config.merge(user_config);
+
+        assert_eq!(config.allowed_key_types, Some(vec!["ssh-rsa".to_string()]));
+        assert_eq!(config.min_rsa_bits, Some(3072));
+        assert_eq!(config.require_touch, Some(true));
+        assert_eq!(config.require_verify, Some(true));
+        assert_eq!(config.get_required_options("root"), vec!["from".to_string(), "no-pty".to_string()]);
+
+        let config = AktConfig::default().merge(get_config_from_str("min_rsa_bits = 4096").unwrap());
+        assert_eq!(config.min_rsa_bits, Some(4096));
+
+        let config = AktConfig::default().merge(get_config_from_str("min_rsa_bits = 1024").unwrap());
+        assert_eq!(config.min_rsa_bits, Some(2048));
+    }
+
+    #[test]
+    fn user_config_should_be_loaded_from_user_home() {
+        let dir = get_temp_dir();
+        let config_dir = dir.path().join(".config/akt");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("akt.toml"), "older_than_days = 7\n").unwrap();
+
+        let config = load_config_for_user_home(None, Some(dir.path())).unwrap();
+
+        assert_eq!(config.get_older_than_days(), 7);
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::fs::{File, Permissions};
+use std::io::Write;
+use std::os::unix::fs::{chown, MetadataExt, PermissionsExt};
+use std::path::Path;
+
+use log::{debug, info};
+use tempfile::{Builder, NamedTempFile};
+
+const TEMP_FILE_SUFFIX: &str = ".akt-tmp";
+
+/// Writes content to temp file in the same directory and then renames it to `file_path`,
+/// so readers (sshd) see either old or new file content.
+///
+/// Permissions and owner of existing file are preserved, new file is created with `0600` mode.
+pub fn write_file_atomically(file_path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    info!("write file '{}'", file_path.display());
+
+    let metadata = fs::metadata(file_path).ok();
+
+    write_temp_file_and_rename(file_path, content, |temp_file| {
+        if let Some(metadata) = &metadata {
+            temp_file.as_file().set_permissions(Permissions::from_mode(metadata.mode()))?;
+
+            let temp_metadata = temp_file.as_file().metadata()?;
+
+            if temp_metadata.uid() != metadata.uid() || temp_metadata.gid() != metadata.gid() {
+                chown(temp_file.path(), Some(metadata.uid()), Some(metadata.gid()))?;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Same as `write_file_atomically`, but file gets `mode` permissions before rename,
+/// so readers never see file with other permissions.
+pub fn write_file_atomically_with_mode(file_path: &Path, content: &[u8], mode: u32) -> anyhow::Result<()> {
+    info!("write file '{}' with mode {:o}", file_path.display(), mode);
+
+    write_temp_file_and_rename(file_path, content, |temp_file| {
+        temp_file.as_file().set_permissions(Permissions::from_mode(mode))
+    })
+}
+
+/// Temp file has unique name, so files left after crash don't break later writes.
+/// It's removed on error, parent directory is synced after rename to persist the new entry.
+fn write_temp_file_and_rename(file_path: &Path,
+                              content: &[u8],
+                              prepare: impl FnOnce(&NamedTempFile) -> std::io::Result<()>) -> anyhow::Result<()> {
+    let directory = match file_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new(".")
+    };
+
+    let mut file_name = file_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+
+    let result = Builder::new()
+        .prefix(&file_name)
+        .suffix(TEMP_FILE_SUFFIX)
+        .tempfile_in(directory)
+        .and_then(|mut temp_file| {
+            debug!("temp file '{}'", temp_file.path().display());
+
+            temp_file.write_all(content)?;
+            temp_file.as_file().sync_all()?;
+            prepare(&temp_file)?;
+
+            temp_file.persist(file_path).map_err(|e| e.error)?;
+
+            File::open(directory)?.sync_all()
+        });
+
+    result.map_err(|e| anyhow::anyhow!("unable to write file '{}': {}", file_path.display(), e))
+}
+
+#[cfg(test)]
+mod files_tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::files::{write_file_atomically, write_file_atomically_with_mode};
+    use crate::tests_common::get_temp_dir;
+
+    #[test]
+    fn leftover_temp_file_should_not_break_write() {
+        let dir = get_temp_dir();
+        let path = dir.path().join("authorized_keys");
+
+        fs::write(dir.path().join("authorized_keys.akt-tmp"), "leftover").unwrap();
+
+        write_file_atomically(&path, b"ssh-ed25519 AAAA\n").unwrap();
+        write_file_atomically(&path, b"ssh-ed25519 BBBB\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ssh-ed25519 BBBB\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn file_should_be_written_with_mode() {
+        let dir = get_temp_dir();
+        let path = dir.path().join("akt.prom");
+
+        write_file_atomically_with_mode(&path, b"akt_keys 1\n", 0o644).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "akt_keys 1\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o644);
+    }
+}
@@ -0,0 +1,472 @@
+use std::fmt::{Display, Formatter};
+
+use md5::Md5;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub const KEY_TYPES: [&str; 8] = [
+    "ssh-rsa",
+    "ssh-dss",
+    "ssh-ed25519",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+    "sk-ssh-ed25519@openssh.com",
+];
+
+const CERT_KEY_TYPE_SUFFIX: &str = "-cert-v01@openssh.com";
+
+const OPENSSH_SUFFIX: &str = "@openssh.com";
+
+/// Entry with this option trusts certificates signed by the key
+pub const CERT_AUTHORITY_OPTION: &str = "cert-authority";
+
+/// Key is accepted only from listed addresses, patterns or networks (CIDR)
+pub const SOURCE_ADDRESSES_OPTION: &str = "from";
+
+/// Security key (FIDO) signatures without user presence (touch) are accepted
+pub const NO_TOUCH_REQUIRED_OPTION: &str = "no-touch-required";
+
+/// Security key (FIDO) signatures must be verified by user, i.e. with PIN
+pub const VERIFY_REQUIRED_OPTION: &str = "verify-required";
+
+const SECURITY_KEY_TYPE_PREFIX: &str = "sk-";
+
+const RSA_KEY_TYPE: &str = "ssh-rsa";
+
+const DSA_KEY_TYPE: &str = "ssh-dss";
+
+const ED25519_BITS: usize = 256;
+
+const ECDSA_CURVE_PREFIX: &str = "nistp";
+
+/// Base64 key data starts with length of key type name (`00 00 00 xx`)
+const KEY_DATA_PREFIX: &str = "AAAA";
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParseError {
+    MissingKeyType,
+    UnknownKeyType(String),
+    MissingKeyData,
+    InvalidBase64,
+    KeyTypeMismatch(String),
+    UnterminatedQuote,
+    EmptyOption,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            ParseError::MissingKeyType => write!(f, "key type is missing"),
+            ParseError::UnknownKeyType(key_type) => write!(f, "unknown key type '{key_type}'"),
+            ParseError::MissingKeyData => write!(f, "key data is missing"),
+            ParseError::InvalidBase64 => write!(f, "key data isn't valid base64"),
+            ParseError::KeyTypeMismatch(blob_key_type) =>
+                write!(f, "key type doesn't match key data, key data has type '{blob_key_type}'"),
+            ParseError::UnterminatedQuote => write!(f, "options have unterminated quote"),
+            ParseError::EmptyOption => write!(f, "options have empty option"),
+        }
+    }
+}
+
+/// Key option, i.e. `no-pty` or `from="10.0.0.1"`. Value is stored unescaped.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct KeyOption {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl Display for KeyOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}=\"{}\"", self.name, value.replace('"', "\\\"")),
+            None => write!(f, "{}", self.name)
+        }
+    }
+}
+
+/// Key line of authorized_keys file: `[options] key-type base64-key-data [comment]`.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct KeyEntry {
+    pub options: Vec<KeyOption>,
+
+    pub key_type: String,
+
+    /// Base64 encoded key blob
+    pub key_data: String,
+
+    pub comment: Option<String>,
+}
+
+impl KeyEntry {
+    /// Parses key line like sshd does: line starts either with key type or with options.
+    pub fn parse(text: &str) -> Result<KeyEntry, ParseError> {
+        let text = text.trim_start();
+
+        let (first_token, rest) = split_token(text)?;
+
+        if first_token.is_empty() {
+            return Err(ParseError::MissingKeyType)
+        }
+
+        let (options, key_type, rest) = if is_known_key_type(first_token) {
+            (vec![], first_token, rest)
+
+        } else {
+            let (key_type, rest) = split_token(rest.trim_start())?;
+
+            if key_type.is_empty() {
+                return Err(ParseError::UnknownKeyType(first_token.to_string()))
+            }
+
+            if !is_known_key_type(key_type) {
+                // `ssh-foo AAAA...`: line starts with unknown key type, not with options
+                let unknown_key_type = if key_type.starts_with(KEY_DATA_PREFIX) { first_token } else { key_type };
+
+                return Err(ParseError::UnknownKeyType(unknown_key_type.to_string()))
+            }
+
+            (parse_options(first_token)?, key_type, rest)
+        };
+
+        let (key_data, rest) = split_token(rest.trim_start())?;
+
+        if key_data.is_empty() {
+            return Err(ParseError::MissingKeyData)
+        }
+
+        let blob = base64::decode(key_data).map_err(|_| ParseError::InvalidBase64)?;
+
+        let blob_key_type = get_blob_key_type(&blob).ok_or(ParseError::InvalidBase64)?;
+
+        if blob_key_type != key_type {
+            return Err(ParseError::KeyTypeMismatch(blob_key_type))
+        }
+
+        let comment = rest.trim();
+
+        Ok(KeyEntry {
+            options,
+            key_type: key_type.to_string(),
+            key_data: key_data.to_string(),
+            comment: if comment.is_empty() { None } else { Some(comment.to_string()) },
+        })
+    }
+
+    pub fn get_option(&self, name: &str) -> Option<&KeyOption> {
+        self.options.iter().find(|o| o.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn has_option(&self, name: &str) -> bool {
+        self.get_option(name).is_some()
+    }
+
+    pub fn is_cert_authority(&self) -> bool {
+        self.has_option(CERT_AUTHORITY_OPTION)
+    }
+
+    /// Hardware-backed FIDO key, i.e. `sk-ssh-ed25519@openssh.com`
+    pub fn is_security_key(&self) -> bool {
+        self.key_type.starts_with(SECURITY_KEY_TYPE_PREFIX)
+    }
+
+    pub fn get_blob(&self) -> Vec<u8> {
+        base64::decode(&self.key_data).unwrap_or_default()
+    }
+
+    /// SHA256 fingerprint without prefix, same as in auth logs after `SHA256:`
+    pub fn get_fingerprint(&self) -> String {
+        get_fingerprint_from_blob(&self.get_blob())
+    }
+
+    /// MD5 fingerprint without prefix, i.e. `6e:f1:81:...`
+    pub fn get_md5_fingerprint(&self) -> String {
+        Md5::digest(self.get_blob()).iter().map(|b| format!("{b:02x}")).collect::<Vec<String>>().join(":")
+    }
+
+    /// Key type without certificate suffix, i.e. `ssh-rsa` for `ssh-rsa-cert-v01@openssh.com`
+    pub fn get_plain_key_type(&self) -> &str {
+        self.key_type.strip_suffix(CERT_KEY_TYPE_SUFFIX).unwrap_or(&self.key_type)
+    }
+
+    /// Key size in bits as `ssh-keygen -l` shows it, size of certified key for certificates,
+    /// `None` for malformed keys.
+    pub fn get_bits(&self) -> Option<usize> {
+        let blob = self.get_blob();
+        let strings = get_blob_strings(&blob);
+
+        // certificate has nonce before key fields
+        let offset = if self.get_plain_key_type() == self.key_type { 0 } else { 1 };
+
+        let key_type = self.get_plain_key_type();
+        let key_type = key_type.strip_prefix(SECURITY_KEY_TYPE_PREFIX).unwrap_or(key_type);
+
+        match key_type {
+            RSA_KEY_TYPE => strings.get(2 + offset).map(|modulus| get_integer_bits(modulus)),
+            DSA_KEY_TYPE => strings.get(1 + offset).map(|p| get_integer_bits(p)),
+            _ if key_type.starts_with("ssh-ed25519") => Some(ED25519_BITS),
+            _ if key_type.starts_with("ecdsa-sha2-") => strings.get(1 + offset)
+                .and_then(|curve| std::str::from_utf8(curve).ok())
+                .and_then(|curve| curve.strip_prefix(ECDSA_CURVE_PREFIX))
+                .and_then(|bits| bits.parse().ok()),
+            _ => None
+        }
+    }
+
+    pub fn get_comment(&self) -> &str {
+        self.comment.as_deref().unwrap_or_default()
+    }
+
+    /// Key without options and comment: `key-type base64-key-data`
+    pub fn get_key(&self) -> String {
+        format!("{} {}", self.key_type, self.key_data)
+    }
+}
+
+impl Display for KeyEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.options.is_empty() {
+            let options: Vec<String> = self.options.iter().map(|o| o.to_string()).collect();
+            write!(f, "{} ", options.join(","))?;
+        }
+
+        write!(f, "{}", self.get_key())?;
+
+        if let Some(comment) = &self.comment {
+            write!(f, " {comment}")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn is_known_key_type(key_type: &str) -> bool {
+    match key_type.strip_suffix(CERT_KEY_TYPE_SUFFIX) {
+        Some(cert_key_type) => KEY_TYPES.iter()
+            .any(|t| *t == cert_key_type || t.strip_suffix(OPENSSH_SUFFIX) == Some(cert_key_type)),
+        None => KEY_TYPES.contains(&key_type)
+    }
+}
+
+/// Splits text by the first whitespace outside of double quotes.
+///
+/// Like sshd, only `\"` is skipped as escape, other backslashes are regular characters.
+fn split_token(text: &str) -> Result<(&str, &str), ParseError> {
+    let mut in_quotes = false;
+
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' && chars.peek().is_some_and(|(_, next)| *next == '"') {
+            chars.next();
+
+        } else if c == '"' {
+            in_quotes = !in_quotes;
+
+        } else if c.is_whitespace() && !in_quotes {
+            return Ok((&text[..index], &text[index..]))
+        }
+    }
+
+    if in_quotes {
+        return Err(ParseError::UnterminatedQuote)
+    }
+
+    Ok((text, ""))
+}
+
+/// Option values are unescaped like sshd does: `\"` becomes `"`, other backslashes are kept.
+fn parse_options(options: &str) -> Result<Vec<KeyOption>, ParseError> {
+    let mut result: Vec<KeyOption> = vec![];
+
+    let mut name = String::new();
+    let mut value: Option<String> = None;
+    let mut in_quotes = false;
+
+    let mut push_option = |name: &mut String, value: &mut Option<String>| {
+        if name.is_empty() {
+            return Err(ParseError::EmptyOption)
+        }
+
+        result.push(KeyOption { name: name.to_string(), value: value.take() });
+        name.clear();
+        Ok(())
+    };
+
+    let mut chars = options.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match &mut value {
+            Some(option_value) if in_quotes => {
+                if c == '\\' && chars.peek() == Some(&'"') {
+                    option_value.push('"');
+                    chars.next();
+
+                } else if c == '"' {
+                    in_quotes = false;
+
+                } else {
+                    option_value.push(c);
+                }
+            }
+            Some(_) if c == '"' => in_quotes = true,
+            _ if c == ',' => push_option(&mut name, &mut value)?,
+            Some(option_value) => option_value.push(c),
+            None if c == '=' => value = Some(String::new()),
+            None => name.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(ParseError::UnterminatedQuote)
+    }
+
+    push_option(&mut name, &mut value)?;
+
+    Ok(result)
+}
+
+fn get_blob_key_type(blob: &[u8]) -> Option<String> {
+    let length_bytes: [u8; 4] = blob.get(0..4)?.try_into().ok()?;
+    let length = u32::from_be_bytes(length_bytes) as usize;
+    let key_type = blob.get(4..4usize.checked_add(length)?)?;
+    String::from_utf8(key_type.to_vec()).ok()
+}
+
+/// SHA256 fingerprint of public key blob without prefix
+pub fn get_fingerprint_from_blob(blob: &[u8]) -> String {
+    base64::encode_config(Sha256::digest(blob), base64::STANDARD_NO_PAD)
+}
+
+/// Public key blob is a sequence of strings: key type and key parameters.
+fn get_blob_strings(blob: &[u8]) -> Vec<&[u8]> {
+    let mut strings: Vec<&[u8]> = vec![];
+
+    let mut rest = blob;
+
+    while let Some(length_bytes) = rest.get(0..4) {
+        let length = u32::from_be_bytes([length_bytes[0], length_bytes[1], length_bytes[2], length_bytes[3]]) as usize;
+
+        let end = match 4usize.checked_add(length) {
+            Some(end) if end <= rest.len() => end,
+            _ => break
+        };
+
+        strings.push(&rest[4..end]);
+
+        rest = &rest[end..];
+    }
+
+    strings
+}
+
+/// Bit length of big-endian integer (ssh mpint)
+fn get_integer_bits(integer: &[u8]) -> usize {
+    let bytes: Vec<&u8> = integer.iter().skip_while(|b| **b == 0).collect();
+
+    match bytes.first() {
+        Some(first) => (bytes.len() - 1) * 8 + (8 - first.leading_zeros() as usize),
+        None => 0
+    }
+}
+
+#[cfg(test)]
+mod key_entry_tests {
+    use crate::keysfile::entry::{KeyEntry, KeyOption, ParseError};
+    use crate::tests_common::KEY1 as ED25519_KEY;
+
+    #[test]
+    fn parse_key_without_options() {
+        let entry = KeyEntry::parse(&format!("  {ED25519_KEY}   a@b.com  more words ")).unwrap();
+
+        assert!(entry.options.is_empty());
+        assert_eq!(entry.key_type, "ssh-ed25519");
+        assert_eq!(entry.comment, Some("a@b.com  more words".to_string()));
+        assert_eq!(entry.get_fingerprint(), "FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE");
+    }
+
+    #[test]
+    fn parse_options_with_quotes_and_escapes() {
+        let line = format!(r#"from="10.0.0.1,10.0.0.2",command="echo \"a b\" \\n",NO-PTY {ED25519_KEY}"#);
+
+        let entry = KeyEntry::parse(&line).unwrap();
+
+        assert_eq!(entry.options, vec![
+            KeyOption { name: "from".to_string(), value: Some("10.0.0.1,10.0.0.2".to_string()) },
+            KeyOption { name: "command".to_string(), value: Some(r#"echo "a b" \\n"#.to_string()) },
+            KeyOption { name: "NO-PTY".to_string(), value: None },
+        ]);
+        assert!(entry.has_option("no-pty"));
+        assert_eq!(entry.comment, None);
+
+        assert_eq!(entry.to_string(), line);
+
+        let line = format!(r#"command="a\\"b\" c" {ED25519_KEY}"#);
+
+        let entry = KeyEntry::parse(&line).unwrap();
+
+        assert_eq!(entry.options[0].value, Some(r#"a\"b" c"#.to_string()));
+        assert_eq!(entry.to_string(), line);
+    }
+
+    #[test]
+    fn return_errors_for_invalid_entries() {
+        assert_eq!(KeyEntry::parse("ssh-ed25519"), Err(ParseError::MissingKeyData));
+        assert_eq!(KeyEntry::parse("ssh-ed25519 AAAA%%%"), Err(ParseError::InvalidBase64));
+        assert_eq!(KeyEntry::parse("ssh-foo AAAA"), Err(ParseError::UnknownKeyType("ssh-foo".to_string())));
+        assert_eq!(KeyEntry::parse("ssh-foo AAAAC3NzaC1lZDI1NTE5 a@b.com"),
+                   Err(ParseError::UnknownKeyType("ssh-foo".to_string())));
+        assert_eq!(KeyEntry::parse("no-pty ssh-foo AAAA"), Err(ParseError::UnknownKeyType("ssh-foo".to_string())));
+        assert_eq!(KeyEntry::parse(&format!("command=\"ls {ED25519_KEY}")), Err(ParseError::UnterminatedQuote));
+        assert_eq!(KeyEntry::parse(&format!("no-pty,,no-X11-forwarding {ED25519_KEY}")), Err(ParseError::EmptyOption));
+
+        assert_eq!(KeyEntry::parse("ssh-ed25519 /////w=="), Err(ParseError::InvalidBase64));
+
+        let mismatch = ED25519_KEY.replace("ssh-ed25519", "ssh-rsa");
+        assert_eq!(KeyEntry::parse(&mismatch), Err(ParseError::KeyTypeMismatch("ssh-ed25519".to_string())));
+    }
+
+    #[test]
+    fn security_keys_should_have_fingerprints_and_size() {
+        let entry = KeyEntry::parse("verify-required sk-ssh-ed25519@openssh.com AAAAGnNrLXNzaC1lZDI1NTE5QG9wZW5zc2guY29tAAAAICkcIlkxK1/eMTYrK9kb3GTtuCSjQXbFGjB6ZgtwLIxkAAAABHNzaDo= yubikey@corp").unwrap();
+
+        assert!(entry.is_security_key());
+        assert!(entry.has_option("verify-required"));
+        assert_eq!(entry.get_fingerprint(), "MryUqhqbZlGzNCR80bpxG4YwOv1mOIKNWSwOcxXTGvM");
+        assert_eq!(entry.get_md5_fingerprint(), "8d:9c:e3:80:e4:54:af:9e:65:5a:42:ef:44:91:37:bd");
+        assert_eq!(entry.get_bits(), Some(256));
+
+        let entry = KeyEntry::parse("sk-ecdsa-sha2-nistp256@openssh.com AAAAInNrLWVjZHNhLXNoYTItbmlzdHAyNTZAb3BlbnNzaC5jb20AAAAIbmlzdHAyNTYAAABBBPtr83a0BcV8/oTkaLbBT4qCTGGcKUXgYGrefM/Spp31aaAfdLKdg2yQexFve4nBh4Lzed6Sxql4vECB43bSLuAAAAAEc3NoOg==").unwrap();
+
+        assert!(entry.is_security_key());
+        assert_eq!(entry.get_fingerprint(), "my4sMarsrNIPz2gSh1aP5szAjMRM3MjmLlbUe+16IRw");
+        assert_eq!(entry.get_bits(), Some(256));
+
+        let entry = KeyEntry::parse(ED25519_KEY).unwrap();
+
+        assert!(!entry.is_security_key());
+        assert_eq!(entry.get_md5_fingerprint(), "6e:f1:81:2f:36:aa:49:34:f4:7d:68:3a:36:7b:d6:52");
+    }
+
+    #[test]
+    fn certificate_size_should_be_size_of_certified_key() {
+        let cert_key_type = "ssh-rsa-cert-v01@openssh.com";
+
+        let mut modulus = vec![0u8];
+        modulus.extend([0xffu8; 128]);
+
+        let mut blob: Vec<u8> = vec![];
+
+        for string in [cert_key_type.as_bytes(), &[1u8; 32], &[1, 0, 1], &modulus] {
+            blob.extend((string.len() as u32).to_be_bytes());
+            blob.extend(string);
+        }
+
+        let entry = KeyEntry::parse(&format!("{cert_key_type} {}", base64::encode(&blob))).unwrap();
+
+        assert_eq!(entry.get_plain_key_type(), "ssh-rsa");
+        assert_eq!(entry.get_bits(), Some(1024));
+    }
+}
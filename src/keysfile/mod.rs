@@ -0,0 +1,366 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use crate::keysfile::entry::{KeyEntry, ParseError};
+
+pub mod entry;
+
+/// akt-driven commands edit only entries inside managed block, if file has one
+pub const MANAGED_BLOCK_BEGIN: &str = "# BEGIN akt managed";
+pub const MANAGED_BLOCK_END: &str = "# END akt managed";
+
+/// Entries inside unmanaged block are never changed by akt
+pub const UNMANAGED_BLOCK_BEGIN: &str = "# BEGIN akt unmanaged";
+pub const UNMANAGED_BLOCK_END: &str = "# END akt unmanaged";
+
+const COMMENT_PREFIX: &str = "#";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LineKind {
+    Blank,
+    Comment,
+    Key(KeyEntry),
+    Invalid(ParseError),
+}
+
+/// Line of authorized_keys file, keeps original text, so file can be written back byte-for-byte.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    /// Line text without line ending
+    pub text: String,
+
+    /// `\n`, `\r\n` or empty for the last line without line ending
+    pub line_ending: String,
+
+    pub kind: LineKind,
+}
+
+impl Line {
+    pub fn parse(raw_line: &str) -> Line {
+        let (text, line_ending) = match raw_line.strip_suffix("\r\n") {
+            Some(text) => (text, "\r\n"),
+            None => match raw_line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (raw_line, "")
+            }
+        };
+
+        let trimmed_text = text.trim();
+
+        let kind = if trimmed_text.is_empty() {
+            LineKind::Blank
+
+        } else if trimmed_text.starts_with(COMMENT_PREFIX) {
+            LineKind::Comment
+
+        } else {
+            match KeyEntry::parse(trimmed_text) {
+                Ok(entry) => LineKind::Key(entry),
+                Err(e) => LineKind::Invalid(e)
+            }
+        };
+
+        Line { text: text.to_string(), line_ending: line_ending.to_string(), kind }
+    }
+
+    pub fn get_entry(&self) -> Option<&KeyEntry> {
+        match &self.kind {
+            LineKind::Key(entry) => Some(entry),
+            _ => None
+        }
+    }
+
+    fn is_marker(&self, marker: &str) -> bool {
+        self.kind == LineKind::Comment && self.text.trim() == marker
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.text, self.line_ending)
+    }
+}
+
+/// authorized_keys file as list of lines, including comments, blank and invalid lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeysFile {
+    pub lines: Vec<Line>,
+}
+
+impl KeysFile {
+    pub fn parse(content: &str) -> KeysFile {
+        let lines = content.split_inclusive('\n').map(Line::parse).collect();
+        KeysFile { lines }
+    }
+
+    /// Indexes of lines between managed block markers (markers are excluded).
+    pub fn get_managed_block(&self) -> anyhow::Result<Option<Range<usize>>> {
+        let blocks = self.get_blocks(MANAGED_BLOCK_BEGIN, MANAGED_BLOCK_END)?;
+
+        if blocks.len() > 1 {
+            anyhow::bail!("more than one managed block found")
+        }
+
+        Ok(blocks.into_iter().next())
+    }
+
+    /// Indexes of lines which akt is allowed to edit: lines inside managed block if file has one,
+    /// otherwise all lines outside unmanaged blocks.
+    pub fn get_editable_lines(&self) -> anyhow::Result<Vec<usize>> {
+        match self.get_managed_block()? {
+            Some(managed_block) => Ok(managed_block.collect()),
+            None => {
+                let unmanaged_blocks = self.get_blocks(UNMANAGED_BLOCK_BEGIN, UNMANAGED_BLOCK_END)?;
+
+                // markers are excluded as well
+                Ok((0..self.lines.len())
+                    .filter(|index| !unmanaged_blocks.iter().any(|block| (block.start - 1..=block.end).contains(index)))
+                    .collect())
+            }
+        }
+    }
+
+    /// Index to insert new lines: before managed block end marker or at the end of file.
+    pub fn get_insert_index(&self) -> anyhow::Result<usize> {
+        match self.get_managed_block()? {
+            Some(managed_block) => Ok(managed_block.end),
+            None => Ok(self.lines.len())
+        }
+    }
+
+    /// Inserts line, adds line ending to previous line if it doesn't have one.
+    pub fn insert_line(&mut self, index: usize, mut line: Line) {
+        let line_ending = self.get_line_ending().to_string();
+
+        if index > 0 {
+            if let Some(previous_line) = self.lines.get_mut(index - 1) {
+                if previous_line.line_ending.is_empty() {
+                    previous_line.line_ending = line_ending.clone();
+                }
+            }
+        }
+
+        if line.line_ending.is_empty() && index < self.lines.len() {
+            line.line_ending = line_ending;
+        }
+
+        self.lines.insert(index, line);
+    }
+
+    /// Line ending used in file, `\n` by default.
+    pub fn get_line_ending(&self) -> &str {
+        self.lines.iter()
+            .map(|l| l.line_ending.as_str())
+            .find(|e| !e.is_empty())
+            .unwrap_or("\n")
+    }
+
+    fn get_blocks(&self, begin_marker: &str, end_marker: &str) -> anyhow::Result<Vec<Range<usize>>> {
+        let mut blocks: Vec<Range<usize>> = vec![];
+
+        let mut block_start: Option<usize> = None;
+
+        for (index, line) in self.lines.iter().enumerate() {
+            if line.is_marker(begin_marker) {
+                if block_start.is_some() {
+                    anyhow::bail!("line {}: nested '{begin_marker}' marker", index + 1)
+                }
+
+                block_start = Some(index + 1);
+
+            } else if line.is_marker(end_marker) {
+                match block_start {
+                    Some(start) => blocks.push(start..index),
+                    None => anyhow::bail!("line {}: '{end_marker}' marker without '{begin_marker}'", index + 1)
+                }
+
+                block_start = None;
+            }
+        }
+
+        if block_start.is_some() {
+            anyhow::bail!("'{begin_marker}' marker without '{end_marker}'")
+        }
+
+        Ok(blocks)
+    }
+}
+
+impl Display for KeysFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.lines.iter().try_for_each(|line| write!(f, "{line}"))
+    }
+}
+
+#[cfg(test)]
+mod keys_file_tests {
+    use crate::keysfile::{KeysFile, Line, LineKind};
+    use crate::tests_common::KEY1;
+
+    #[test]
+    fn keep_content_byte_for_byte() {
+        let content = format!("# comment  \r\n\n  \n{KEY1} a@b.com\ninvalid line\n{KEY1}");
+
+        let keys_file = KeysFile::parse(&content);
+
+        assert_eq!(keys_file.lines.len(), 6);
+        assert_eq!(keys_file.lines[0].kind, LineKind::Comment);
+        assert_eq!(keys_file.lines[0].line_ending, "\r\n");
+        assert_eq!(keys_file.lines[1].kind, LineKind::Blank);
+        assert_eq!(keys_file.lines[2].kind, LineKind::Blank);
+        assert!(keys_file.lines[3].get_entry().is_some());
+        assert!(matches!(keys_file.lines[4].kind, LineKind::Invalid(_)));
+        assert_eq!(keys_file.lines[5].line_ending, "");
+
+        assert_eq!(keys_file.to_string(), content);
+    }
+
+    #[test]
+    fn editable_lines_should_be_inside_managed_block() {
+        let content = format!("{KEY1} a\n# BEGIN akt managed\n{KEY1} b\n\n# END akt managed\n{KEY1} c\n");
+
+        let keys_file = KeysFile::parse(&content);
+
+        assert_eq!(keys_file.get_managed_block().unwrap(), Some(2..4));
+        assert_eq!(keys_file.get_editable_lines().unwrap(), vec![2, 3]);
+        assert_eq!(keys_file.get_insert_index().unwrap(), 4);
+    }
+
+    #[test]
+    fn editable_lines_should_be_outside_unmanaged_blocks_without_managed_block() {
+        let content = format!("{KEY1} a\n# BEGIN akt unmanaged\n{KEY1} b\n# END akt unmanaged\n{KEY1} c\n");
+
+        let keys_file = KeysFile::parse(&content);
+
+        assert_eq!(keys_file.get_managed_block().unwrap(), None);
+        assert_eq!(keys_file.get_editable_lines().unwrap(), vec![0, 4]);
+        assert_eq!(keys_file.get_insert_index().unwrap(), 5);
+    }
+
+    #[test]
+    fn broken_markers_should_be_rejected() {
+        let unclosed = KeysFile::parse("# BEGIN akt managed\n");
+        let unopened = KeysFile::parse("# END akt managed\n");
+        let nested = KeysFile::parse("# BEGIN akt managed\n# BEGIN akt managed\n# END akt managed\n");
+        let two_blocks = KeysFile::parse("# BEGIN akt managed\n# END akt managed\n# BEGIN akt managed\n# END akt managed\n");
+
+        assert!(unclosed.get_managed_block().is_err());
+        assert!(unopened.get_managed_block().is_err());
+        assert!(nested.get_managed_block().is_err());
+        assert!(two_blocks.get_managed_block().is_err());
+    }
+
+    #[test]
+    fn insert_line_should_add_missing_line_ending() {
+        let mut keys_file = KeysFile::parse(&format!("{KEY1} a"));
+
+        keys_file.insert_line(1, Line::parse(&format!("{KEY1} b\n")));
+
+        assert_eq!(keys_file.to_string(), format!("{KEY1} a\n{KEY1} b\n"));
+    }
+}
+
+#[cfg(test)]
+mod keys_file_roundtrip_tests {
+    use proptest::prelude::*;
+
+    use crate::keysfile::entry::{is_known_key_type, KeyEntry, KeyOption};
+    use crate::keysfile::{KeysFile, LineKind};
+
+    const TEST_KEYS: &str = include_str!("../../test-data/authorized_keys");
+
+    /// Generated line with expected key (`key-type base64-key-data`) for key lines
+    type TestLine = (String, Option<String>);
+
+    fn key_option() -> impl Strategy<Value = KeyOption> {
+        let name = "[a-zA-Z]{1,8}(-[a-zA-Z0-9]{1,8})?".prop_filter("key type", |name| !is_known_key_type(name));
+
+        (name, proptest::option::of("[a-zA-Z0-9 ,.:=/*\"-]{0,20}"))
+            .prop_map(|(name, value)| KeyOption { name, value })
+    }
+
+    fn key_line() -> impl Strategy<Value = TestLine> {
+        let test_lines: Vec<&str> = TEST_KEYS.lines().collect();
+
+        (prop::sample::select(test_lines), prop::collection::vec(key_option(), 0..4), "[ \t]{0,3}", "[ \t]{0,3}")
+            .prop_map(|(test_line, options, indent, trailing_whitespace)| {
+                let entry = KeyEntry::parse(test_line).unwrap();
+
+                let options: Vec<String> = options.iter().map(|o| o.to_string()).collect();
+
+                let text = if options.is_empty() {
+                    format!("{indent}{test_line}{trailing_whitespace}")
+                } else {
+                    format!("{indent}{} {test_line}{trailing_whitespace}", options.join(","))
+                };
+
+                (text, Some(entry.get_key()))
+            })
+    }
+
+    fn any_line() -> impl Strategy<Value = TestLine> {
+        prop_oneof![
+            4 => key_line(),
+            1 => "[ \t]{0,3}".prop_map(|text| (text, None)),
+            1 => "[ \t]{0,2}#[^\r\n]{0,30}".prop_map(|text| (text, None)),
+            1 => "[a-z]{1,10}( [a-zA-Z0-9+/=\"]{0,20}){0,2}".prop_map(|text| (text, None)),
+        ]
+    }
+
+    fn content() -> impl Strategy<Value = (String, Vec<TestLine>)> {
+        (prop::collection::vec((any_line(), prop::sample::select(vec!["\n", "\r\n"])), 0..20), any::<bool>())
+            .prop_map(|(lines, last_line_ending)| {
+                let mut content = String::new();
+
+                let line_count = lines.len();
+
+                for (index, ((text, _), line_ending)) in lines.iter().enumerate() {
+                    content.push_str(text);
+
+                    // empty last line exists only with line ending
+                    if index + 1 < line_count || last_line_ending || text.is_empty() {
+                        content.push_str(line_ending);
+                    }
+                }
+
+                (content, lines.into_iter().map(|(line, _)| line).collect())
+            })
+    }
+
+    #[test]
+    fn all_test_keys_should_be_parsed() {
+        let keys_file = KeysFile::parse(TEST_KEYS);
+
+        assert!(keys_file.lines.iter().all(|line| line.get_entry().is_some()));
+        assert_eq!(keys_file.to_string(), TEST_KEYS);
+    }
+
+    proptest! {
+        #[test]
+        fn file_should_be_written_back_byte_for_byte((content, test_lines) in content()) {
+            let keys_file = KeysFile::parse(&content);
+
+            prop_assert_eq!(keys_file.to_string(), content);
+            prop_assert_eq!(keys_file.lines.len(), test_lines.len());
+
+            for (line, (_, expected_key)) in keys_file.lines.iter().zip(test_lines) {
+                match expected_key {
+                    Some(expected_key) => {
+                        let entry = line.get_entry().unwrap();
+                        prop_assert_eq!(entry.get_key(), expected_key);
+                    }
+                    None => prop_assert!(!matches!(line.kind, LineKind::Key(_)))
+                }
+            }
+        }
+
+        #[test]
+        fn serialized_entry_should_be_parsed_to_same_entry((text, _) in key_line()) {
+            let entry = KeyEntry::parse(&text).unwrap();
+
+            let serialized_entry = entry.to_string();
+
+            prop_assert_eq!(KeyEntry::parse(&serialized_entry), Ok(entry));
+        }
+    }
+}
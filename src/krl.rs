@@ -0,0 +1,275 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// OpenSSH key revocation list, see PROTOCOL.krl in OpenSSH sources.
+pub const KRL_MAGIC: &[u8] = b"SSHKRL\n\0";
+
+const KRL_FORMAT_VERSION: u32 = 1;
+
+const SECTION_CERTIFICATES: u8 = 1;
+const SECTION_EXPLICIT_KEY: u8 = 2;
+const SECTION_FINGERPRINT_SHA1: u8 = 3;
+const SECTION_SIGNATURE: u8 = 4;
+const SECTION_FINGERPRINT_SHA256: u8 = 5;
+
+const SHA1_LENGTH: usize = 20;
+const SHA256_LENGTH: usize = 32;
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Krl {
+    pub version: u64,
+
+    /// Unix timestamp
+    pub generated_date: u64,
+
+    pub flags: u64,
+
+    pub reserved: Vec<u8>,
+
+    pub comment: String,
+
+    /// Certificate sections are kept as is
+    pub certificate_sections: Vec<Vec<u8>>,
+
+    /// Public key blobs
+    pub explicit_keys: Vec<Vec<u8>>,
+
+    /// SHA1 hashes of public key blobs
+    pub sha1_fingerprints: Vec<Vec<u8>>,
+
+    /// SHA256 hashes of public key blobs
+    pub sha256_fingerprints: Vec<Vec<u8>>,
+}
+
+impl Krl {
+    pub fn is_krl(content: &[u8]) -> bool {
+        content.starts_with(KRL_MAGIC)
+    }
+
+    pub fn parse(content: &[u8]) -> anyhow::Result<Krl> {
+        let mut reader = Reader { data: content };
+
+        if reader.get_bytes(KRL_MAGIC.len())? != KRL_MAGIC {
+            anyhow::bail!("file isn't KRL")
+        }
+
+        let format_version = reader.get_u32()?;
+
+        if format_version != KRL_FORMAT_VERSION {
+            anyhow::bail!("unsupported KRL format version {format_version}")
+        }
+
+        let mut krl = Krl {
+            version: reader.get_u64()?,
+            generated_date: reader.get_u64()?,
+            flags: reader.get_u64()?,
+            reserved: reader.get_string()?.to_vec(),
+            comment: String::from_utf8_lossy(reader.get_string()?).to_string(),
+            ..Krl::default()
+        };
+
+        while !reader.data.is_empty() {
+            let section_type = reader.get_u8()?;
+            let section_data = reader.get_string()?;
+
+            match section_type {
+                SECTION_CERTIFICATES => krl.certificate_sections.push(section_data.to_vec()),
+                SECTION_EXPLICIT_KEY => krl.explicit_keys.extend(get_strings(section_data)?),
+                SECTION_FINGERPRINT_SHA1 => krl.sha1_fingerprints.extend(get_hashes(section_data, SHA1_LENGTH)?),
+                SECTION_FINGERPRINT_SHA256 => krl.sha256_fingerprints.extend(get_hashes(section_data, SHA256_LENGTH)?),
+                SECTION_SIGNATURE => anyhow::bail!("signed KRL isn't supported"),
+                _ => anyhow::bail!("unsupported KRL section type {section_type}")
+            }
+        }
+
+        Ok(krl)
+    }
+
+    /// Serializes KRL, keys and hashes are sorted like `ssh-keygen` does.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+
+        writer.data.extend_from_slice(KRL_MAGIC);
+        writer.put_u32(KRL_FORMAT_VERSION);
+        writer.put_u64(self.version);
+        writer.put_u64(self.generated_date);
+        writer.put_u64(self.flags);
+        writer.put_string(&self.reserved);
+        writer.put_string(self.comment.as_bytes());
+
+        for section in &self.certificate_sections {
+            writer.put_section(SECTION_CERTIFICATES, section);
+        }
+
+        for (section_type, values) in [(SECTION_EXPLICIT_KEY, &self.explicit_keys),
+                                       (SECTION_FINGERPRINT_SHA1, &self.sha1_fingerprints),
+                                       (SECTION_FINGERPRINT_SHA256, &self.sha256_fingerprints)] {
+            if values.is_empty() {
+                continue
+            }
+
+            let mut values = values.clone();
+            values.sort();
+            values.dedup();
+
+            let mut section = Writer::default();
+            values.iter().for_each(|value| section.put_string(value));
+
+            writer.put_section(section_type, &section.data);
+        }
+
+        writer.data
+    }
+
+    pub fn is_revoked(&self, key_blob: &[u8]) -> bool {
+        self.explicit_keys.iter().any(|k| k == key_blob) ||
+            self.sha1_fingerprints.iter().any(|f| f.as_slice() == Sha1::digest(key_blob).as_slice()) ||
+            self.sha256_fingerprints.iter().any(|f| f.as_slice() == Sha256::digest(key_blob).as_slice())
+    }
+
+    pub fn revoke_key(&mut self, key_blob: &[u8]) {
+        if !self.is_revoked(key_blob) {
+            self.explicit_keys.push(key_blob.to_vec());
+            self.touch();
+        }
+    }
+
+    /// `sha256_fingerprint` is decoded fingerprint, 32 bytes.
+    pub fn revoke_sha256_fingerprint(&mut self, sha256_fingerprint: &[u8]) {
+        if !self.sha256_fingerprints.iter().any(|f| f == sha256_fingerprint) {
+            self.sha256_fingerprints.push(sha256_fingerprint.to_vec());
+            self.touch();
+        }
+    }
+
+    fn touch(&mut self) {
+        self.generated_date = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn get_bytes(&mut self, length: usize) -> anyhow::Result<&'a [u8]> {
+        if self.data.len() < length {
+            anyhow::bail!("unexpected end of KRL data")
+        }
+
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn get_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.get_bytes(1)?[0])
+    }
+
+    fn get_u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_be_bytes(self.get_bytes(4)?.try_into()?))
+    }
+
+    fn get_u64(&mut self) -> anyhow::Result<u64> {
+        Ok(u64::from_be_bytes(self.get_bytes(8)?.try_into()?))
+    }
+
+    fn get_string(&mut self) -> anyhow::Result<&'a [u8]> {
+        let length = self.get_u32()? as usize;
+        self.get_bytes(length)
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn put_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_string(&mut self, value: &[u8]) {
+        self.put_u32(value.len() as u32);
+        self.data.extend_from_slice(value);
+    }
+
+    fn put_section(&mut self, section_type: u8, section_data: &[u8]) {
+        self.data.push(section_type);
+        self.put_string(section_data);
+    }
+}
+
+fn get_strings(data: &[u8]) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut reader = Reader { data };
+
+    let mut strings: Vec<Vec<u8>> = vec![];
+
+    while !reader.data.is_empty() {
+        strings.push(reader.get_string()?.to_vec());
+    }
+
+    Ok(strings)
+}
+
+fn get_hashes(data: &[u8], hash_length: usize) -> anyhow::Result<Vec<Vec<u8>>> {
+    let hashes = get_strings(data)?;
+
+    if hashes.iter().any(|h| h.len() != hash_length) {
+        anyhow::bail!("invalid hash length in KRL")
+    }
+
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod krl_tests {
+    use crate::keysfile::entry::KeyEntry;
+    use crate::krl::Krl;
+    use crate::tests_common::{KEY1, KEY2};
+
+    /// Generated with `ssh-keygen -k -f krl -z 7 key1.pub`, key1.pub contains KEY1
+    const KEY1_KRL: &str = "U1NIS1JMCgAAAAABAAAAAAAAAAcAAAAAatTCNAAAAAAAAAAAAAAAAAAAAAACAAAANwAAADMAAAALc3NoLWVkMjU1MTkAAAAgM4ZJuA3jeAj6g/kJ5srsmwZawxPr27KYwvhgdZQ8jYY=";
+
+    #[test]
+    fn parse_krl_generated_by_ssh_keygen() {
+        let krl = Krl::parse(&base64::decode(KEY1_KRL).unwrap()).unwrap();
+
+        assert_eq!(krl.version, 7);
+        assert!(krl.is_revoked(&get_blob(KEY1)));
+        assert!(!krl.is_revoked(&get_blob(KEY2)));
+
+        assert_eq!(base64::encode(krl.to_bytes()), KEY1_KRL);
+    }
+
+    #[test]
+    fn revoked_keys_should_be_kept_after_serialization() {
+        let mut krl = Krl::default();
+
+        krl.revoke_sha256_fingerprint(&base64::decode("FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE").unwrap());
+        krl.revoke_key(&get_blob(KEY2));
+
+        let krl = Krl::parse(&krl.to_bytes()).unwrap();
+
+        assert!(krl.is_revoked(&get_blob(KEY1)));
+        assert!(krl.is_revoked(&get_blob(KEY2)));
+        assert_eq!(krl.explicit_keys.len(), 1);
+    }
+
+    #[test]
+    fn broken_krl_should_be_rejected() {
+        assert!(Krl::parse(b"ssh-ed25519 AAAA").is_err());
+        assert!(Krl::parse(&base64::decode(KEY1_KRL).unwrap()[..40]).is_err());
+    }
+
+    fn get_blob(key: &str) -> Vec<u8> {
+        KeyEntry::parse(key).unwrap().get_blob()
+    }
+}
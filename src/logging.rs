@@ -11,14 +11,14 @@ const ROLLING_APPENDER_NAME: &str = "rolling";
 pub const LOG_FILE_PATH: &str = "akt.log";
 
 fn get_logging_level_from_string(level: &str) -> LevelFilter {
-    return match level {
+    match level {
         "debug" => LevelFilter::Debug,
         "error" => LevelFilter::Error,
         "warn" => LevelFilter::Warn,
         "trace" => LevelFilter::Trace,
         "off" => LevelFilter::Off,
         _ => LevelFilter::Info
-    };
+    }
 }
 
 pub fn get_logging_config(logging_level: &str) -> Config {
@@ -31,7 +31,7 @@ pub fn get_logging_config(logging_level: &str) -> Config {
             Root::builder()
                 .appender(ROLLING_APPENDER_NAME)
                 .build(level)
-        ).expect(&format!("unable to create log file '{}'", LOG_FILE_PATH))
+        ).unwrap_or_else(|_| panic!("unable to create log file '{}'", LOG_FILE_PATH))
 }
 
 fn get_rolling_appender() -> Appender {
@@ -1,96 +1,935 @@
-use std::path::PathBuf;
+use std::fs;
+use std::io::{IsTerminal, stdout};
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
-use authorized_keys::authorizedkeys::get_authorized_keys_from_file;
-use log::info;
-use ssh_auth_log::provider::AuthLogFileProvider;
+use clap::ArgMatches;
+use log::{info, warn};
 
-use crate::cli::{AUTH_LOG_PATH_OPTION, DEFAULT_AUTH_LOG_PATH, FILE_OPTION, FORMAT_OPTION, get_cli_app, get_default_authorized_keys_file_path, init_logging, OLDER_THAN_DAYS_DEFAULT_VALUE, OLDER_THAN_DAYS_OPTION};
-use crate::cli::output::{OutputFormat, print_results};
-use crate::usecases::oldkeys::get_keys_older_than;
+use crate::authlog::{AuthLog, LogCoverage, read_auth_log, read_latest_logins};
+use crate::cli::{ALL_USERS_OPTION, APPLY_OPTION, AUDIT_COMMAND, AUTH_LOG_PATH_OPTION, BY_OPTION, CERTS_COMMAND, CHECK_PERMS_COMMAND, CONFIG_ARGUMENT, DRY_RUN_OPTION, FAIL_ON_OPTION, FILE_OPTION, FIND_COMMAND, FINGERPRINT_OPTION, FIX_OPTION, FORMAT_OPTION, FROM_OPTION, get_cli_app, get_current_username, get_default_authorized_keys_file_path, get_hostname, init_logging, IPS_OPTION, IPV4_PREFIX_OPTION, IPV6_PREFIX_OPTION, KEY_FAILURES_COMMAND, KEY_USAGE_COMMAND, KRL_ADD_COMMAND, KRL_CHECK_COMMAND, KRL_COMMAND, KRL_LIST_COMMAND, LINT_COMMAND, METRICS_COMMAND, OLDER_THAN_DAYS_OPTION, OUTPUT_OPTION, PROTECTED_KEYS_FILE_OPTION, QUERY_ARGUMENT, REVOKE_COMMAND, REVOKED_KEYS_FILE_OPTION, SHARED_KEYS_OPTION, SHOW_KEYS_COMMAND, ShowKeysParameters, STATS_COMMAND, SUGGEST_FROM_COMMAND, SYNC_COMMAND, USER_OPTION};
+use crate::cli::exitcode::{check_fail_on, FailOn, get_default_fail_on, get_exit_code};
+use crate::cli::output::{JsonEnvelope, OutputFormat, print_as_json_envelope, print_as_ndjson_record, print_results};
+use crate::cli::report::print_report;
+use crate::cli::sarif::{get_audit_sarif_results, get_lint_sarif_results, print_as_sarif};
+use crate::config::{AktConfig, load_config, load_config_for_user_home};
+use crate::keysfile::KeysFile;
+use crate::usecases::audit::{audit_authorized_keys, audit_authorized_keys_file};
+use crate::usecases::certs::get_certificate_authorities;
+use crate::usecases::failures::get_key_failures;
+use crate::usecases::find::{find_keys, KeyQuery};
+use crate::usecases::fingerprints::{FingerprintedKey, FingerprintFormat, get_fingerprinted_keys};
+use crate::usecases::lint::lint_authorized_keys_file;
+use crate::usecases::metrics::{get_metrics, write_metrics_file};
+use crate::passwd::{get_passwd_entry, PASSWD_FILE_PATH};
+use crate::usecases::oldkeys::{for_each_entry_older_than, for_each_key_older_than, get_keys_older_than, read_authorized_keys,
+                               RemovalCandidate};
+use crate::usecases::perms::check_permissions;
+use crate::usecases::protected::{get_protected_keys_from_file, get_protected_keys_from_str, ProtectedKeys};
+use crate::usecases::records::{get_candidate_records, get_key_records};
+use crate::usecases::report::{get_keys_report, KeysReport};
+use crate::usecases::revoke::{add_to_krl_file, revoke_key};
+use crate::usecases::revokedkeys::{get_revoked_entries, RevokedKeysFile};
+use crate::usecases::sharedkeys::find_shared_keys;
+use crate::usecases::stats::{get_session_stats, StatsGroup};
+use crate::usecases::suggestfrom::{suggest_from, SuggestOptions};
+use crate::usecases::sync::sync_authorized_keys_file;
+use crate::usecases::usage::{get_key_ids, get_keys_usage};
+use crate::sshdconfig::{get_revoked_keys_file_path, SSHD_CONFIG_FILE_PATH};
+use crate::usecases::users::{get_file_owner, get_sshd_config, get_users_authorized_keys_files, UserKeysFile};
 
+mod authlog;
+mod cidr;
 mod cli;
 
 mod usecases;
 mod logging;
+mod config;
+mod files;
+mod keysfile;
+mod krl;
+mod passwd;
+mod sshdconfig;
 
 #[cfg(test)]
 mod tests_common;
 
 const EXIT_CODE_ERROR: i32 = 1;
 
+const EXIT_CODE_CANDIDATES_FOUND: i32 = 2;
+
+const EXIT_CODE_ISSUES_FOUND: i32 = 3;
+
+const EXIT_CODE_LOG_COVERAGE_INSUFFICIENT: i32 = 4;
+
 fn main() {
     let matches = get_cli_app();
 
     init_logging(&matches);
 
+    if let Err(e) = check_fail_on_option(&matches) {
+        eprintln!("{}", e);
+        exit(EXIT_CODE_ERROR)
+    }
+
+    let config = match load_config(matches.get_one::<PathBuf>(CONFIG_ARGUMENT)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_CODE_ERROR)
+        }
+    };
+
     match matches.subcommand() {
         Some((SHOW_KEYS_COMMAND, cmd_matches)) => {
             info!("command: show public keys");
 
-            let mut auth_log_path = PathBuf::from(DEFAULT_AUTH_LOG_PATH);
+            let auth_log_path = get_auth_log_path(cmd_matches, &config);
+
+            let file_path = get_authorized_keys_file_path(cmd_matches);
+
+            info!("path to authorized_keys file '{}'", file_path.display());
+
+            let output_format = get_output_format(cmd_matches);
+
+            let fingerprint_format = cmd_matches.get_one::<FingerprintFormat>(FINGERPRINT_OPTION).cloned();
+
+            let is_report = matches!(output_format, OutputFormat::Markdown | OutputFormat::Html);
+
+            // `older_than_days` from config and report formats enable candidates search without the option
+            if cmd_matches.contains_id(OLDER_THAN_DAYS_OPTION) || config.older_than_days.is_some() || is_report {
+                let older_than_days = match cmd_matches.get_one::<usize>(OLDER_THAN_DAYS_OPTION) {
+                    Some(days_value) => *days_value,
+                    None => config.get_older_than_days()
+                };
+
+                info!("older than days {older_than_days}");
+
+                let protected_keys = match get_protected_keys(cmd_matches, &config) {
+                    Ok(protected_keys) => protected_keys,
+                    Err(e) => {
+                        eprintln!("unable to load protected keys: {}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+                };
+
+                // candidates depend on last use of keys only, so logs of any size are scanned with bounded memory
+                let auth_log = match read_latest_logins(&auth_log_path) {
+                    Ok(auth_log) => auth_log,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+                };
+
+                let authorized_keys_file_path_str = format!("{}", file_path.display());
+
+                let mut has_candidates = false;
+
+                if matches!(output_format, OutputFormat::Ndjson) {
+                    let result = for_each_key_older_than(&auth_log.logins,
+                                                         older_than_days,
+                                                         &authorized_keys_file_path_str,
+                                                         &protected_keys, |candidate| {
+                        if !candidate.is_protected() {
+                            has_candidates = true;
+                            print_as_ndjson_record(&FingerprintedKey::new(candidate, fingerprint_format.clone()));
+                        }
+                    });
+
+                    if let Err(e) = result {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+
+                    exit(get_show_keys_exit_code(cmd_matches, has_candidates, auth_log.coverage.as_ref(), older_than_days))
+                }
+
+                if is_report {
+                    match get_report(&file_path, older_than_days, &auth_log, &protected_keys, &config) {
+                        Ok(report) => {
+                            has_candidates = report.summary.stale + report.summary.not_seen > 0;
+                            print_report(&report, output_format)
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(EXIT_CODE_ERROR)
+                        }
+                    }
 
-            if cmd_matches.contains_id(AUTH_LOG_PATH_OPTION) {
-                match cmd_matches.get_one::<PathBuf>(AUTH_LOG_PATH_OPTION) {
-                    Some(path_value) => auth_log_path = path_value.clone(),
-                    None => {}
+                    exit(get_show_keys_exit_code(cmd_matches, has_candidates, auth_log.coverage.as_ref(), older_than_days))
                 }
+
+                match get_keys_older_than(&auth_log.logins,
+                                          older_than_days,
+                                          &authorized_keys_file_path_str,
+                                          &protected_keys) {
+                    Ok(candidates) => {
+                        let candidates = candidates.into_iter()
+                            .filter(|candidate| !candidate.is_protected())
+                            .collect::<Vec<RemovalCandidate>>();
+
+                        has_candidates = !candidates.is_empty();
+
+                        let mut keys = get_fingerprinted_keys(candidates, fingerprint_format);
+
+                        match output_format {
+                            OutputFormat::Csv | OutputFormat::Tsv => {
+                                let username = get_file_owner_or_exit(&file_path);
+
+                                print_results(&mut get_candidate_records(&username, &file_path, &keys), output_format)
+                            }
+                            OutputFormat::Json => {
+                                let parameters = ShowKeysParameters {
+                                    file: file_path.clone(),
+                                    older_than_days: Some(older_than_days),
+                                    auth_log_path: Some(auth_log_path.clone()),
+                                };
+
+                                print_as_json_envelope(
+                                    &JsonEnvelope::new(parameters, auth_log.coverage.clone(), &keys))
+                            }
+                            _ => {
+                                if stdout().is_terminal() {
+                                    println!("keys for removal:");
+                                }
+
+                                print_results(&mut keys, output_format)
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+                }
+
+                exit(get_show_keys_exit_code(cmd_matches, has_candidates, auth_log.coverage.as_ref(), older_than_days))
             }
 
-            let mut file_path = get_default_authorized_keys_file_path();
+            match read_authorized_keys(&file_path) {
+                Ok(keys) => {
+                    let mut keys = get_fingerprinted_keys(keys, fingerprint_format);
 
-            if cmd_matches.contains_id(FILE_OPTION) {
-                match cmd_matches.get_one::<PathBuf>(FILE_OPTION) {
-                    Some(file_path_value) => file_path = file_path_value.clone(),
-                    None => {}
+                    match output_format {
+                        OutputFormat::Csv | OutputFormat::Tsv => {
+                            let username = get_file_owner_or_exit(&file_path);
+
+                            print_results(&mut get_key_records(&username, &file_path, &keys), output_format)
+                        }
+                        OutputFormat::Json => {
+                            let parameters = ShowKeysParameters {
+                                file: file_path.clone(),
+                                older_than_days: None,
+                                auth_log_path: None,
+                            };
+
+                            print_as_json_envelope(&JsonEnvelope::new(parameters, None, &keys))
+                        }
+                        _ => print_results(&mut keys, output_format)
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
                 }
             }
 
+        }
+        Some((AUDIT_COMMAND, cmd_matches)) => {
+            info!("command: audit");
+
+            let file_path = get_authorized_keys_file_path(cmd_matches);
+
             info!("path to authorized_keys file '{}'", file_path.display());
 
-            let output_format = match cmd_matches.get_one::<OutputFormat>(FORMAT_OPTION) {
-                Some(value) => value.clone(),
-                None => OutputFormat::Default
+            let username = match cmd_matches.get_one::<String>(USER_OPTION) {
+                Some(username) => username.to_string(),
+                None => get_current_username()
             };
 
-            if cmd_matches.contains_id(OLDER_THAN_DAYS_OPTION) {
-                let older_than_days = match cmd_matches.get_one::<usize>(OLDER_THAN_DAYS_OPTION) {
-                    Some(days_value) => days_value.clone(),
-                    None => OLDER_THAN_DAYS_DEFAULT_VALUE
+            let config = match cmd_matches.get_one::<String>(USER_OPTION) {
+                Some(_) => match load_audited_user_config(&matches, &username) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+                },
+                None => config
+            };
+
+            let output_format = get_output_format(cmd_matches);
+
+            let mut findings = match audit_authorized_keys_file(&file_path, &username, &config) {
+                Ok(findings) => findings,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            if cmd_matches.get_flag(SHARED_KEYS_OPTION) {
+                let mut files = match get_user_keys_files(cmd_matches, true) {
+                    Ok(files) => files,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
                 };
 
-                info!("older than days {older_than_days}");
+                if !files.iter().any(|file| file.path == file_path) {
+                    files.push(UserKeysFile { username: username.to_string(), path: file_path.clone() });
+                }
 
-                let auth_log_file_provider = AuthLogFileProvider::new(auth_log_path.as_path());
-                let authorized_keys_file_path_str = format!("{}", file_path.display());
+                let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                    Ok(auth_log) => auth_log,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+                };
 
-                match get_keys_older_than(&auth_log_file_provider,
-                                          older_than_days,
-                                          &authorized_keys_file_path_str) {
-                    Ok(mut keys) => {
-                        println!("keys for removal:");
-                        print_results(&mut keys, output_format)
+                // keys are compared across all users, but only audited file or user files are reported
+                let reported_files: Option<Vec<PathBuf>> = if cmd_matches.get_one::<PathBuf>(FILE_OPTION).is_some() {
+                    Some(vec![file_path.clone()])
+
+                } else if cmd_matches.get_one::<String>(USER_OPTION).is_some() {
+                    Some(files.iter().filter(|file| file.username == username).map(|file| file.path.clone()).collect())
+
+                } else {
+                    None
+                };
+
+                findings.extend(find_shared_keys(&files, reported_files.as_deref(), &auth_log.logins, &config));
+            }
+
+            let has_findings = !findings.is_empty();
+
+            match output_format {
+                OutputFormat::Sarif => print_as_sarif(&get_audit_sarif_results(&findings, &file_path)),
+                _ => print_results(&mut findings, output_format)
+            }
+
+            if has_findings {
+                exit(get_exit_code(&get_fail_on(cmd_matches), &[FailOn::Violations]))
+            }
+        }
+        Some((SYNC_COMMAND, cmd_matches)) => {
+            info!("command: sync");
+
+            let file_path = get_authorized_keys_file_path(cmd_matches);
+
+            info!("path to authorized_keys file '{}'", file_path.display());
+
+            let desired_keys_file_path = cmd_matches.get_one::<PathBuf>(FROM_OPTION)
+                                                    .expect("--from option is required");
+
+            let dry_run = cmd_matches.get_flag(DRY_RUN_OPTION);
+
+            let output_format = get_output_format(cmd_matches);
+
+            let protected_keys = match get_protected_keys(cmd_matches, &config) {
+                Ok(protected_keys) => protected_keys,
+                Err(e) => {
+                    eprintln!("unable to load protected keys: {}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            match sync_authorized_keys_file(&file_path, desired_keys_file_path, &protected_keys, dry_run) {
+                Ok(mut actions) => print_results(&mut actions, output_format),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            }
+        }
+        Some((LINT_COMMAND, cmd_matches)) => {
+            info!("command: lint");
+
+            let file_path = get_authorized_keys_file_path(cmd_matches);
+
+            info!("path to authorized_keys file '{}'", file_path.display());
+
+            let output_format = get_output_format(cmd_matches);
+
+            match lint_authorized_keys_file(&file_path) {
+                Ok(mut issues) => {
+                    let has_issues = !issues.is_empty();
+
+                    match output_format {
+                        OutputFormat::Sarif => print_as_sarif(&get_lint_sarif_results(&issues, &file_path)),
+                        _ => print_results(&mut issues, output_format)
+                    }
+
+                    if has_issues {
+                        exit(get_exit_code(&get_fail_on(cmd_matches), &[FailOn::Violations]))
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            }
+        }
+        Some((CHECK_PERMS_COMMAND, cmd_matches)) => {
+            info!("command: check permissions");
+
+            let username = match cmd_matches.get_one::<String>(USER_OPTION) {
+                Some(username) => username.to_string(),
+                None => get_current_username()
+            };
+
+            let user = match get_passwd_entry(&username) {
+                Ok(user) => user,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let file_path = match cmd_matches.get_one::<PathBuf>(FILE_OPTION) {
+                Some(file_path_value) => file_path_value.clone(),
+                None => user.home.join(".ssh").join("authorized_keys")
+            };
+
+            info!("path to authorized_keys file '{}'", file_path.display());
+
+            let fix = cmd_matches.get_flag(FIX_OPTION);
+
+            let output_format = get_output_format(cmd_matches);
+
+            match check_permissions(&file_path, &user, fix) {
+                Ok(mut issues) => {
+                    let has_unfixed_issues = issues.iter().any(|issue| !issue.fixed);
+
+                    print_results(&mut issues, output_format);
+
+                    if has_unfixed_issues {
+                        exit(get_exit_code(&get_fail_on(cmd_matches), &[FailOn::Violations]))
                     }
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            }
+        }
+        Some((FIND_COMMAND, cmd_matches)) => {
+            info!("command: find");
+
+            let query_value = cmd_matches.get_one::<String>(QUERY_ARGUMENT).expect("query is required");
+
+            let query = match KeyQuery::parse(query_value) {
+                Ok(query) => query,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let all_users = cmd_matches.get_flag(ALL_USERS_OPTION);
+
+            let files = match get_user_keys_files(cmd_matches, all_users) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let output_format = get_output_format(cmd_matches);
+
+            let mut found_keys = find_keys(&query, &files, all_users, &auth_log.logins);
+
+            print_results(&mut found_keys, output_format);
+        }
+        Some((REVOKE_COMMAND, cmd_matches)) => {
+            info!("command: revoke");
+
+            let query_value = cmd_matches.get_one::<String>(QUERY_ARGUMENT).expect("query is required");
+
+            let query = match KeyQuery::parse(query_value) {
+                Ok(query) => query,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let files = match cmd_matches.get_one::<PathBuf>(FILE_OPTION) {
+                Some(file_path) => vec![UserKeysFile { username: get_file_owner_or_exit(file_path), path: file_path.clone() }],
+                None => match get_users_authorized_keys_files(Path::new(PASSWD_FILE_PATH), Path::new(SSHD_CONFIG_FILE_PATH)) {
+                    Ok(files) => files,
                     Err(e) => {
                         eprintln!("{}", e);
                         exit(EXIT_CODE_ERROR)
                     }
                 }
+            };
+
+            let revoked_keys_file_path = match get_revoked_keys_file_option(cmd_matches) {
+                Ok(path) => path,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let dry_run = cmd_matches.get_flag(DRY_RUN_OPTION);
 
-                exit(0)
+            let output_format = get_output_format(cmd_matches);
+
+            match revoke_key(&query, &files, revoked_keys_file_path.as_deref(), dry_run) {
+                Ok(mut records) => print_results(&mut records, output_format),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            }
+        }
+        Some((CERTS_COMMAND, cmd_matches)) => {
+            info!("command: certs");
+
+            let all_users = cmd_matches.get_flag(ALL_USERS_OPTION);
+
+            let files = match get_user_keys_files(cmd_matches, all_users) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let mut authorities = get_certificate_authorities(&files, all_users, &auth_log.logins);
+
+            print_results(&mut authorities, get_output_format(cmd_matches))
+        }
+        Some((KEY_USAGE_COMMAND, cmd_matches)) => {
+            info!("command: key-usage");
+
+            let all_users = cmd_matches.get_flag(ALL_USERS_OPTION);
+
+            let files = match get_user_keys_files(cmd_matches, all_users) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let mut usages = get_keys_usage(&files, all_users, &auth_log.logins, cmd_matches.get_flag(IPS_OPTION));
+
+            print_results(&mut usages, get_output_format(cmd_matches))
+        }
+        Some((KEY_FAILURES_COMMAND, cmd_matches)) => {
+            info!("command: key-failures");
+
+            let files = match get_user_keys_files(cmd_matches, cmd_matches.get_flag(ALL_USERS_OPTION)) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            if auth_log.skipped_postponed_attempts > 0 {
+                eprintln!("warning: {} postponed line(s) without key, unfinished postponed attempts are skipped. \
+                           sshd logs key of postponed attempt with LogLevel DEBUG", auth_log.skipped_postponed_attempts);
+
+            } else if auth_log.failures.is_empty() {
+                eprintln!("warning: failed or postponed attempts weren't found in auth logs, sshd logs them with LogLevel VERBOSE");
+            }
+
+            let mut key_failures = get_key_failures(&auth_log.failures, &files);
+
+            print_results(&mut key_failures, get_output_format(cmd_matches))
+        }
+        Some((STATS_COMMAND, cmd_matches)) => {
+            info!("command: stats");
+
+            let files = match get_user_keys_files(cmd_matches, cmd_matches.get_flag(ALL_USERS_OPTION)) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let group = cmd_matches.get_one::<StatsGroup>(BY_OPTION).cloned().unwrap_or(StatsGroup::Key);
+
+            let mut stats = get_session_stats(&auth_log.logins, &group, &get_key_ids(&files));
+
+            print_results(&mut stats, get_output_format(cmd_matches))
+        }
+        Some((METRICS_COMMAND, cmd_matches)) => {
+            info!("command: metrics");
+
+            let all_users = cmd_matches.get_flag(ALL_USERS_OPTION);
+
+            let files = match get_user_keys_files(cmd_matches, all_users) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let older_than_days = match cmd_matches.get_one::<usize>(OLDER_THAN_DAYS_OPTION) {
+                Some(days_value) => *days_value,
+                None => config.get_older_than_days()
+            };
+
+            let protected_keys = match get_protected_keys(cmd_matches, &config) {
+                Ok(protected_keys) => protected_keys,
+                Err(e) => {
+                    eprintln!("unable to load protected keys: {}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let metrics = match get_metrics(&files, all_users, &auth_log.logins, auth_log.coverage.as_ref(), older_than_days,
+                                            &protected_keys, &config) {
+                Ok(metrics) => metrics,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            match cmd_matches.get_one::<PathBuf>(OUTPUT_OPTION) {
+                Some(path) => {
+                    if let Err(e) = write_metrics_file(path, &metrics) {
+                        eprintln!("{}", e);
+                        exit(EXIT_CODE_ERROR)
+                    }
+                }
+                None => print!("{metrics}")
             }
+        }
+        Some((SUGGEST_FROM_COMMAND, cmd_matches)) => {
+            info!("command: suggest-from");
 
-            match get_authorized_keys_from_file(&file_path) {
-                Ok(mut keys) => print_results(&mut keys, output_format),
+            let all_users = cmd_matches.get_flag(ALL_USERS_OPTION);
+
+            let files = match get_user_keys_files(cmd_matches, all_users) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let auth_log = match read_auth_log(&get_auth_log_path(cmd_matches, &config)) {
+                Ok(auth_log) => auth_log,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            let options = SuggestOptions {
+                ipv4_prefix_length: *cmd_matches.get_one::<u8>(IPV4_PREFIX_OPTION).expect("default value is expected"),
+                ipv6_prefix_length: *cmd_matches.get_one::<u8>(IPV6_PREFIX_OPTION).expect("default value is expected"),
+                apply: cmd_matches.get_flag(APPLY_OPTION),
+                dry_run: cmd_matches.get_flag(DRY_RUN_OPTION),
+            };
+
+            match suggest_from(&files, all_users, &auth_log.logins, &options) {
+                Ok(mut suggestions) => print_results(&mut suggestions, get_output_format(cmd_matches)),
                 Err(e) => {
                     eprintln!("{}", e);
                     exit(EXIT_CODE_ERROR)
                 }
             }
+        }
+        Some((KRL_COMMAND, cmd_matches)) => {
+            info!("command: krl");
 
+            let revoked_keys_file_path = match get_revoked_keys_file_option(cmd_matches) {
+                Ok(Some(path)) => path,
+                Ok(None) => {
+                    eprintln!("RevokedKeys isn't set in sshd config, use --{REVOKED_KEYS_FILE_OPTION}");
+                    exit(EXIT_CODE_ERROR)
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_CODE_ERROR)
+                }
+            };
+
+            match cmd_matches.subcommand() {
+                Some((KRL_LIST_COMMAND, sub_matches)) => {
+                    match RevokedKeysFile::load(&revoked_keys_file_path) {
+                        Ok(revoked_keys_file) => {
+                            print_results(&mut revoked_keys_file.get_revoked_keys(), get_output_format(sub_matches))
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(EXIT_CODE_ERROR)
+                        }
+                    }
+                }
+                Some((KRL_ADD_COMMAND, sub_matches)) => {
+                    let query_value = sub_matches.get_one::<String>(QUERY_ARGUMENT).expect("query is required");
+
+                    let dry_run = sub_matches.get_flag(DRY_RUN_OPTION);
+
+                    match KeyQuery::parse(query_value)
+                        .and_then(|query| add_to_krl_file(&revoked_keys_file_path, &query, dry_run)) {
+                        Ok(record) => print_results(&mut vec![record], get_output_format(sub_matches)),
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(EXIT_CODE_ERROR)
+                        }
+                    }
+                }
+                Some((KRL_CHECK_COMMAND, sub_matches)) => {
+                    let all_users = sub_matches.get_flag(ALL_USERS_OPTION);
+
+                    let revoked_entries = get_user_keys_files(sub_matches, all_users)
+                        .and_then(|files| {
+                            let revoked_keys_file = RevokedKeysFile::load(&revoked_keys_file_path)?;
+                            Ok(get_revoked_entries(&files, all_users, &revoked_keys_file))
+                        });
+
+                    let mut revoked_entries = match revoked_entries {
+                        Ok(revoked_entries) => revoked_entries,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            exit(EXIT_CODE_ERROR)
+                        }
+                    };
+
+                    print_results(&mut revoked_entries, get_output_format(sub_matches));
+
+                    if !revoked_entries.is_empty() {
+                        exit(get_exit_code(&get_fail_on(sub_matches), &[FailOn::Violations]))
+                    }
+                }
+                _ => {}
+            }
         }
         _ => {}
     }
 }
 
+fn get_authorized_keys_file_path(cmd_matches: &ArgMatches) -> PathBuf {
+    match cmd_matches.get_one::<PathBuf>(FILE_OPTION) {
+        Some(file_path_value) => file_path_value.clone(),
+        None => get_default_authorized_keys_file_path()
+    }
+}
+
+/// authorized_keys files of all users or file from `--file-path` option (default is current user's file),
+/// user of single file is its owner.
+fn get_user_keys_files(cmd_matches: &ArgMatches, all_users: bool) -> anyhow::Result<Vec<UserKeysFile>> {
+    if all_users {
+        return get_users_authorized_keys_files(Path::new(PASSWD_FILE_PATH), Path::new(SSHD_CONFIG_FILE_PATH))
+    }
+
+    let file_path = get_authorized_keys_file_path(cmd_matches);
+
+    if !file_path.is_file() {
+        anyhow::bail!("authorized_keys file '{}' wasn't found", file_path.display())
+    }
+
+    let username = get_file_owner(&file_path, Path::new(PASSWD_FILE_PATH))?;
+
+    Ok(vec![UserKeysFile { username, path: file_path }])
+}
+
+/// Config with per-user file of audited user instead of current one, only system config is used
+/// if user isn't found in passwd file.
+fn load_audited_user_config(matches: &ArgMatches, username: &str) -> anyhow::Result<AktConfig> {
+    let user_home = match get_passwd_entry(username) {
+        Ok(user) => Some(user.home),
+        Err(e) => {
+            warn!("user config wasn't loaded: {}", e);
+            None
+        }
+    };
+
+    load_config_for_user_home(matches.get_one::<PathBuf>(CONFIG_ARGUMENT), user_home.as_deref())
+}
+
+/// Owner of authorized_keys file, it differs from current user for `--file-path` option or sudo.
+fn get_file_owner_or_exit(file_path: &Path) -> String {
+    match get_file_owner(file_path, Path::new(PASSWD_FILE_PATH)) {
+        Ok(username) => username,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_CODE_ERROR)
+        }
+    }
+}
+
+/// Auth logs directory or file from option or config.
+fn get_auth_log_path(cmd_matches: &ArgMatches, config: &AktConfig) -> PathBuf {
+    match cmd_matches.get_one::<PathBuf>(AUTH_LOG_PATH_OPTION) {
+        Some(path_value) => path_value.clone(),
+        None => config.get_auth_log_path()
+    }
+}
+
+/// Revoked keys file from option or `RevokedKeys` from sshd config.
+fn get_revoked_keys_file_option(cmd_matches: &ArgMatches) -> anyhow::Result<Option<PathBuf>> {
+    match cmd_matches.get_one::<PathBuf>(REVOKED_KEYS_FILE_OPTION) {
+        Some(path) => Ok(Some(path.clone())),
+        None => Ok(get_revoked_keys_file_path(&get_sshd_config(Path::new(SSHD_CONFIG_FILE_PATH))?))
+    }
+}
+
+fn get_output_format(cmd_matches: &ArgMatches) -> OutputFormat {
+    match cmd_matches.get_one::<OutputFormat>(FORMAT_OPTION) {
+        Some(value) => value.clone(),
+        None => OutputFormat::Default
+    }
+}
+
+/// Findings from `--fail-on` option, see `get_default_fail_on` without it.
+/// Checks `--fail-on` of command and its subcommands before anything is done
+fn check_fail_on_option(matches: &ArgMatches) -> anyhow::Result<()> {
+    if let Ok(Some(values)) = matches.try_get_many::<FailOn>(FAIL_ON_OPTION) {
+        check_fail_on(&values.cloned().collect::<Vec<FailOn>>())?;
+    }
+
+    match matches.subcommand() {
+        Some((_, sub_matches)) => check_fail_on_option(sub_matches),
+        None => Ok(())
+    }
+}
+
+fn get_fail_on(cmd_matches: &ArgMatches) -> Vec<FailOn> {
+    match cmd_matches.get_many::<FailOn>(FAIL_ON_OPTION) {
+        Some(values) => values.cloned().collect(),
+        None => get_default_fail_on()
+    }
+}
+
+/// Log coverage is insufficient if auth logs have no records, cover less days than threshold
+/// or have no records for more than a day.
+fn get_show_keys_exit_code(cmd_matches: &ArgMatches, has_candidates: bool, log_coverage: Option<&LogCoverage>,
+                           older_than_days: usize) -> i32 {
+    let fail_on = get_fail_on(cmd_matches);
+
+    let mut found: Vec<FailOn> = vec![];
+
+    if has_candidates {
+        found.push(FailOn::Candidates);
+    }
 
+    if fail_on.contains(&FailOn::Coverage) {
+        let is_coverage_insufficient = match log_coverage {
+            Some(log_coverage) => !log_coverage.covers_last_days(older_than_days),
+            None => true
+        };
+
+        if is_coverage_insufficient {
+            found.push(FailOn::Coverage);
+        }
+    }
+
+    get_exit_code(&fail_on, &found)
+}
+
+/// Report for authorized_keys file, audit policy is taken for file owner.
+///
+/// File is read once, candidates (including protected keys) and audit findings are taken from the same content.
+fn get_report(file_path: &Path, older_than_days: usize, auth_log: &AuthLog, protected_keys: &ProtectedKeys,
+              config: &AktConfig) -> anyhow::Result<KeysReport> {
+    let username = get_file_owner(file_path, Path::new(PASSWD_FILE_PATH))?;
+
+    let content = fs::read_to_string(file_path)?;
+
+    let keys_file = KeysFile::parse(&content);
+
+    let mut candidates: Vec<RemovalCandidate> = vec![];
+
+    for_each_entry_older_than(&auth_log.logins, older_than_days, &keys_file, protected_keys,
+                              |candidate| candidates.push(candidate));
+
+    let keys = keys_file.lines.iter().filter(|line| line.get_entry().is_some()).count();
+
+    let findings = audit_authorized_keys(&content, &username, config)?;
+
+    let records = get_candidate_records(&username, file_path, &get_fingerprinted_keys(candidates, None));
+
+    Ok(get_keys_report(&get_hostname(), file_path, older_than_days, auth_log.coverage.clone(), keys, &records, &findings))
+}
+
+/// Protected keys from config and protected keys file.
+///
+/// Default protected keys file is optional, file set with CLI option or config must exist.
+fn get_protected_keys(cmd_matches: &ArgMatches, config: &AktConfig) -> anyhow::Result<ProtectedKeys> {
+    let mut protected_keys = get_protected_keys_from_str(&config.protected_keys.join("\n"))?;
+
+    let protected_keys_file_path = match cmd_matches.get_one::<PathBuf>(PROTECTED_KEYS_FILE_OPTION) {
+        Some(path) => Some(path.clone()),
+        None => {
+            let path = config.get_protected_keys_file();
+
+            if config.protected_keys_file.is_some() || path.exists() {
+                Some(path)
+
+            } else {
+                None
+            }
+        }
+    };
+
+    if let Some(path) = protected_keys_file_path {
+        protected_keys.extend(get_protected_keys_from_file(&path)?);
+    }
+
+    Ok(protected_keys)
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+
+pub const PASSWD_FILE_PATH: &str = "/etc/passwd";
+
+const COMMENT_PREFIX: &str = "#";
+
+/// User account from `/etc/passwd`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswdEntry {
+    pub username: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: PathBuf,
+    pub shell: String,
+}
+
+pub fn get_passwd_entries_from_file(file_path: &Path) -> anyhow::Result<Vec<PasswdEntry>> {
+    info!("loading users from '{}'", file_path.display());
+
+    let content = fs::read_to_string(file_path)?;
+
+    Ok(get_passwd_entries_from_str(&content))
+}
+
+/// Malformed lines are skipped.
+pub fn get_passwd_entries_from_str(content: &str) -> Vec<PasswdEntry> {
+    let mut entries: Vec<PasswdEntry> = vec![];
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+            continue
+        }
+
+        match get_passwd_entry_from_line(line) {
+            Some(entry) => {
+                debug!("user '{}' uid {} home '{}'", entry.username, entry.uid, entry.home.display());
+                entries.push(entry)
+            }
+            None => warn!("unsupported passwd line: '{line}'")
+        }
+    }
+
+    entries
+}
+
+pub fn get_passwd_entry(username: &str) -> anyhow::Result<PasswdEntry> {
+    get_passwd_entries_from_file(Path::new(PASSWD_FILE_PATH))?
+        .into_iter()
+        .find(|e| e.username == username)
+        .ok_or_else(|| anyhow::anyhow!("user '{username}' wasn't found in {PASSWD_FILE_PATH}"))
+}
+
+/// `name:password:uid:gid:gecos:home:shell`
+fn get_passwd_entry_from_line(line: &str) -> Option<PasswdEntry> {
+    let fields: Vec<&str> = line.split(':').collect();
+
+    if fields.len() != 7 {
+        return None
+    }
+
+    Some(PasswdEntry {
+        username: fields[0].to_string(),
+        uid: fields[2].parse().ok()?,
+        gid: fields[3].parse().ok()?,
+        home: PathBuf::from(fields[5]),
+        shell: fields[6].to_string(),
+    })
+}
+
+#[cfg(test)]
+mod passwd_tests {
+    use std::path::PathBuf;
+
+    use crate::passwd::get_passwd_entries_from_str;
+
+    #[test]
+    fn skip_comments_and_malformed_lines() {
+        let content = "# users\nroot:x:0:0:root:/root:/bin/bash\nbroken:x:abc:0::/:/bin/sh\n\n\
+                       alice:x:1000:1000:Alice,,,:/home/alice:/bin/zsh\n";
+
+        let entries = get_passwd_entries_from_str(content);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].username, "root");
+        assert_eq!(entries[0].uid, 0);
+        assert_eq!(entries[1].username, "alice");
+        assert_eq!(entries[1].gid, 1000);
+        assert_eq!(entries[1].home, PathBuf::from("/home/alice"));
+        assert_eq!(entries[1].shell, "/bin/zsh");
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+pub const SSHD_CONFIG_FILE_PATH: &str = "/etc/ssh/sshd_config";
+
+const AUTHORIZED_KEYS_FILE_DIRECTIVE: &str = "authorizedkeysfile";
+
+const REVOKED_KEYS_DIRECTIVE: &str = "revokedkeys";
+
+const MATCH_DIRECTIVE: &str = "match";
+
+/// sshd default for `AuthorizedKeysFile`
+const DEFAULT_AUTHORIZED_KEYS_FILES: [&str; 2] = [".ssh/authorized_keys", ".ssh/authorized_keys2"];
+
+const NONE_VALUE: &str = "none";
+
+/// Values of global `AuthorizedKeysFile` directive, sshd defaults if directive isn't set.
+pub fn get_authorized_keys_file_patterns(sshd_config: &str) -> Vec<String> {
+    match get_directive_values(sshd_config, AUTHORIZED_KEYS_FILE_DIRECTIVE) {
+        Some(values) => values.into_iter().filter(|v| !v.eq_ignore_ascii_case(NONE_VALUE)).collect(),
+        None => DEFAULT_AUTHORIZED_KEYS_FILES.iter().map(|p| p.to_string()).collect()
+    }
+}
+
+/// Path from global `RevokedKeys` directive.
+pub fn get_revoked_keys_file_path(sshd_config: &str) -> Option<PathBuf> {
+    get_directive_values(sshd_config, REVOKED_KEYS_DIRECTIVE)?
+        .into_iter()
+        .find(|v| !v.eq_ignore_ascii_case(NONE_VALUE))
+        .map(PathBuf::from)
+}
+
+/// Values of the first directive with `keyword` before any `Match` block, like sshd does.
+fn get_directive_values(sshd_config: &str, keyword: &str) -> Option<Vec<String>> {
+    for line in sshd_config.lines() {
+        let mut tokens = line.split_whitespace();
+
+        let line_keyword = match tokens.next() {
+            Some(line_keyword) => line_keyword.to_lowercase(),
+            None => continue
+        };
+
+        if line_keyword == MATCH_DIRECTIVE {
+            break
+        }
+
+        if line_keyword == keyword {
+            return Some(tokens.map(|t| t.to_string()).collect())
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod sshd_config_tests {
+    use std::path::PathBuf;
+
+    use crate::sshdconfig::{get_authorized_keys_file_patterns, get_revoked_keys_file_path};
+
+    #[test]
+    fn use_first_directive_outside_of_match_blocks() {
+        let sshd_config = "# comment\nPort 22\nauthorizedKeysFile  .ssh/authorized_keys /etc/ssh/keys/%u\n\
+                           AuthorizedKeysFile .ssh/other\nRevokedKeys /etc/ssh/revoked_keys\n\
+                           Match User git\n  AuthorizedKeysFile none\n";
+
+        assert_eq!(get_authorized_keys_file_patterns(sshd_config),
+                   vec![".ssh/authorized_keys".to_string(), "/etc/ssh/keys/%u".to_string()]);
+        assert_eq!(get_revoked_keys_file_path(sshd_config), Some(PathBuf::from("/etc/ssh/revoked_keys")));
+
+        assert_eq!(get_authorized_keys_file_patterns("Match User git\n  AuthorizedKeysFile none\n"),
+                   vec![".ssh/authorized_keys".to_string(), ".ssh/authorized_keys2".to_string()]);
+        assert_eq!(get_revoked_keys_file_path("Match User git\n  RevokedKeys /etc/ssh/revoked_keys\n"), None);
+
+        assert!(get_authorized_keys_file_patterns("AuthorizedKeysFile none\n").is_empty());
+    }
+}
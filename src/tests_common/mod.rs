@@ -1,10 +1,25 @@
 pub mod time;
 
-use chrono::NaiveDateTime;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Duration, NaiveDateTime};
 use fake::{Fake, Faker};
 use log::LevelFilter;
 use ssh_auth_log::KeyLoginAttempt;
-use ssh_fingerprint_rs::PublicKeyFingerprint;
+use tempfile::TempDir;
+
+use crate::authlog::{CertificateLogin, PublicKeyLogin};
+use crate::usecases::users::UserKeysFile;
+
+pub const KEY1: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIDOGSbgN43gI+oP5CebK7JsGWsMT69uymML4YHWUPI2G";
+pub const KEY1_FINGERPRINT: &str = "SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE";
+pub const KEY2: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIJRApVG9oMFm8Rf4UHe+L8NDluPrIT3Q9eB/o1PXR2Ld";
+pub const KEY2_FINGERPRINT: &str = "SHA256:6xMOdUUbH5Dsag46HR3gfuU/t1rdnWH4BpOrVrtE+HI";
+pub const KEY3: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAABAgMEBQYHCAkKCwwNDg8QERITFBUWFxgZGhscHR4f";
+
+/// Weak by default policy
+pub const RSA_1024_KEY: &str = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAgQC2DwS8wUEz/MM68PcCnaB4hE3zzPKtSl52ZhS399t7OuyvggDdJINhqWoJIOCZk387odt/8Mccs/LLdifvt7ftNq3l/01TcLrX96d1o4AgTpt0uu8beAp5TcEAIdK3CPcSA5BeZno0dgKs1DuiPEnIM5hlpyS1VO/Yw+cWVM9AOQ==";
 
 pub fn init_logging() {
     let _ = env_logger::builder().filter_level(LevelFilter::Debug)
@@ -15,9 +30,21 @@ pub fn get_random_string() -> String {
     Faker.fake::<String>()
 }
 
+/// Creates new empty directory in system temp directory, it's removed when returned value is dropped.
+pub fn get_temp_dir() -> TempDir {
+    tempfile::Builder::new().prefix("akt-test-").tempdir().unwrap()
+}
+
+/// Writes `content` to file named after `username` in `dir`.
+pub fn create_keys_file(dir: &Path, username: &str, content: &str) -> UserKeysFile {
+    let path = dir.join(username);
+    fs::write(&path, content).unwrap();
+    UserKeysFile { username: username.to_string(), path }
+}
+
 pub fn get_key_login_attempt(timestamp: &NaiveDateTime, fingerprint: &str) -> KeyLoginAttempt {
     KeyLoginAttempt {
-        timestamp: timestamp.clone(),
+        timestamp: *timestamp,
         key_type: "rsa".to_string(),
         fingerprint_type: "SHA256".to_string(),
         fingerprint: fingerprint.to_string(),
@@ -26,12 +53,48 @@ pub fn get_key_login_attempt(timestamp: &NaiveDateTime, fingerprint: &str) -> Ke
     }
 }
 
-pub fn get_public_key_fingerprint(fingerprint: &str) -> PublicKeyFingerprint {
-    PublicKeyFingerprint {
-        key_length: 2048,
-        fingerprint_type: "SHA256".to_string(),
+/// Login from `10.0.0.1` with ED25519 key, `fingerprint` should have `SHA256:` prefix.
+pub fn get_public_key_login(timestamp: &NaiveDateTime, username: &str, fingerprint: &str) -> PublicKeyLogin {
+    PublicKeyLogin {
+        timestamp: *timestamp,
+        pid: 1,
+        username: username.to_string(),
+        source_ip: "10.0.0.1".to_string(),
+        key_type: "ED25519".to_string(),
         fingerprint: fingerprint.to_string(),
-        key_id: "a@b.com".to_string(),
-        key_type: "RSA".to_string(),
+        certificate: None,
+        session_closed: None,
     }
-}
\ No newline at end of file
+}
+
+/// Login of `username` from `source_ip`, `fingerprint` should have `SHA256:` prefix.
+pub fn get_login_from(timestamp: &NaiveDateTime, username: &str, source_ip: &str, fingerprint: &str) -> PublicKeyLogin {
+    PublicKeyLogin {
+        source_ip: source_ip.to_string(),
+        ..get_public_key_login(timestamp, username, fingerprint)
+    }
+}
+
+/// Login from `source_ip` with session closed after `session_seconds`.
+pub fn get_closed_session_login(timestamp: &NaiveDateTime, username: &str, source_ip: &str, fingerprint: &str,
+                                session_seconds: i64) -> PublicKeyLogin {
+    PublicKeyLogin {
+        session_closed: Some(*timestamp + Duration::seconds(session_seconds)),
+        ..get_login_from(timestamp, username, source_ip, fingerprint)
+    }
+}
+
+/// Login with certificate of KEY2 signed by CA with `ca_fingerprint`.
+pub fn get_certificate_login(timestamp: &NaiveDateTime, username: &str, key_id: &str, serial: u64,
+                             ca_fingerprint: &str) -> PublicKeyLogin {
+    PublicKeyLogin {
+        key_type: "ED25519-CERT".to_string(),
+        certificate: Some(CertificateLogin {
+            key_id: key_id.to_string(),
+            serial,
+            ca_key_type: "ED25519".to_string(),
+            ca_fingerprint: ca_fingerprint.to_string(),
+        }),
+        ..get_public_key_login(timestamp, username, KEY2_FINGERPRINT)
+    }
+}
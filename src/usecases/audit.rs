@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::config::AktConfig;
+use crate::keysfile::entry::{NO_TOUCH_REQUIRED_OPTION, VERIFY_REQUIRED_OPTION};
+use crate::keysfile::{KeysFile, LineKind};
+use crate::passwd::get_passwd_entry;
+use crate::usecases::perms::check_permissions;
+
+const RSA_KEY_TYPE: &str = "ssh-rsa";
+
+const DSA_KEY_TYPE: &str = "ssh-dss";
+
+/// RSA keys shorter than this are weak if `min_rsa_bits` isn't set in config
+pub const DEFAULT_MIN_RSA_BITS: usize = 2048;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditRule {
+    UnparsableKey,
+    KeyTypeNotAllowed,
+    WeakKey,
+    InvalidComment,
+    MissingRequiredOption,
+    DuplicateKey,
+    InsecurePermissions,
+
+    /// Security key accepts signatures without touch, but policy requires touch
+    TouchNotRequired,
+
+    /// Security key doesn't require user verification, but policy requires it
+    VerifyNotRequired,
+
+    /// Security key option on regular key
+    SecurityKeyOptionMisuse,
+
+    /// Key is in authorized_keys files of several users
+    SharedKey,
+
+    /// Key is used from many addresses, networks or in concurrent sessions
+    SharedKeyUsage,
+}
+
+impl Display for AuditRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            AuditRule::UnparsableKey => write!(f, "unparsable-key"),
+            AuditRule::KeyTypeNotAllowed => write!(f, "key-type-not-allowed"),
+            AuditRule::WeakKey => write!(f, "weak-key"),
+            AuditRule::InvalidComment => write!(f, "invalid-comment"),
+            AuditRule::MissingRequiredOption => write!(f, "missing-required-option"),
+            AuditRule::DuplicateKey => write!(f, "duplicate-key"),
+            AuditRule::InsecurePermissions => write!(f, "insecure-permissions"),
+            AuditRule::TouchNotRequired => write!(f, "touch-not-required"),
+            AuditRule::VerifyNotRequired => write!(f, "verify-not-required"),
+            AuditRule::SecurityKeyOptionMisuse => write!(f, "security-key-option-misuse"),
+            AuditRule::SharedKey => write!(f, "shared-key"),
+            AuditRule::SharedKeyUsage => write!(f, "shared-key-usage"),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct AuditFinding {
+    pub rule: AuditRule,
+
+    /// Set for findings about several authorized_keys files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+
+    /// Line number in authorized_keys file, starts from 1. `0` for findings about the whole file.
+    pub line: usize,
+
+    pub fingerprint: Option<String>,
+
+    pub key_id: String,
+
+    pub message: String,
+}
+
+impl Display for AuditFinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+
+        write!(f, "{}: [{}] {}", self.line, self.rule, self.message)
+    }
+}
+
+/// Checks keys from authorized_keys file against policy rules from config.
+///
+/// `username` is used to get required options for user and to check file permissions
+/// like sshd `StrictModes` does.
+pub fn audit_authorized_keys_file(authorized_keys_file_path: &Path, username: &str,
+                                  config: &AktConfig) -> anyhow::Result<Vec<AuditFinding>> {
+    info!("audit authorized_keys file '{}' for user '{username}'", authorized_keys_file_path.display());
+
+    let content = fs::read_to_string(authorized_keys_file_path)?;
+
+    let mut findings = audit_authorized_keys(&content, username, config)?;
+
+    match get_passwd_entry(username) {
+        Ok(user) => {
+            let issues = check_permissions(authorized_keys_file_path, &user, false)?;
+
+            findings.extend(issues.iter().map(|issue| AuditFinding {
+                rule: AuditRule::InsecurePermissions,
+                file: None,
+                line: 0,
+                fingerprint: None,
+                key_id: String::new(),
+                message: issue.to_string(),
+            }));
+        }
+        Err(e) => warn!("permissions weren't checked: {}", e)
+    }
+
+    Ok(findings)
+}
+
+pub fn audit_authorized_keys(content: &str, username: &str,
+                             config: &AktConfig) -> anyhow::Result<Vec<AuditFinding>> {
+    let comment_regex = match &config.comment_format {
+        Some(comment_format) => Some(Regex::new(comment_format)?),
+        None => None
+    };
+
+    let required_options = config.get_required_options(username);
+
+    let mut findings: Vec<AuditFinding> = vec![];
+
+    let mut fingerprint_lines: HashMap<String, usize> = HashMap::new();
+
+    for (index, line) in KeysFile::parse(content).lines.iter().enumerate() {
+        let line_number = index + 1;
+
+        let entry = match &line.kind {
+            LineKind::Key(entry) => entry,
+            LineKind::Invalid(e) => {
+                findings.push(AuditFinding {
+                    rule: AuditRule::UnparsableKey,
+                    file: None,
+                    line: line_number,
+                    fingerprint: None,
+                    key_id: String::new(),
+                    message: format!("unable to parse key: {e}"),
+                });
+                continue
+            }
+            _ => continue
+        };
+
+        let fingerprint = format!("SHA256:{}", entry.get_fingerprint());
+        let key_id = entry.get_comment().to_string();
+        debug!("line {line_number}: key '{fingerprint}' id '{key_id}'");
+
+        let mut add_finding = |rule: AuditRule, message: String| {
+            findings.push(AuditFinding {
+                rule, file: None, line: line_number, fingerprint: Some(fingerprint.clone()), key_id: key_id.clone(), message
+            })
+        };
+
+        let key_type = entry.key_type.as_str();
+
+        if let Some(allowed_key_types) = &config.allowed_key_types {
+            if !allowed_key_types.iter().any(|t| t == key_type) {
+                add_finding(AuditRule::KeyTypeNotAllowed, format!("key type '{key_type}' isn't allowed by policy"));
+            }
+        }
+
+        match entry.get_plain_key_type() {
+            DSA_KEY_TYPE => add_finding(AuditRule::WeakKey, "dsa keys are weak and disabled in OpenSSH".to_string()),
+            RSA_KEY_TYPE => {
+                let min_rsa_bits = config.min_rsa_bits.unwrap_or(DEFAULT_MIN_RSA_BITS);
+
+                let key_size = entry.get_bits().unwrap_or_default();
+
+                if key_size < min_rsa_bits {
+                    add_finding(AuditRule::WeakKey,
+                                format!("rsa key size {key_size} bits is less than {min_rsa_bits}"));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(comment_regex) = &comment_regex {
+            if !comment_regex.is_match(&key_id) {
+                add_finding(AuditRule::InvalidComment,
+                            format!("key id '{key_id}' doesn't match format '{}'", comment_regex.as_str()));
+            }
+        }
+
+        for required_option in &required_options {
+            if !entry.has_option(required_option) {
+                add_finding(AuditRule::MissingRequiredOption,
+                            format!("option '{required_option}' is required for user '{username}'"));
+            }
+        }
+
+        if entry.is_security_key() {
+            if config.require_touch == Some(true) && entry.has_option(NO_TOUCH_REQUIRED_OPTION) {
+                add_finding(AuditRule::TouchNotRequired,
+                            format!("security key accepts signatures without touch ({NO_TOUCH_REQUIRED_OPTION})"));
+            }
+
+            if config.require_verify == Some(true) && !entry.has_option(VERIFY_REQUIRED_OPTION) {
+                add_finding(AuditRule::VerifyNotRequired,
+                            format!("security key doesn't require user verification ({VERIFY_REQUIRED_OPTION})"));
+            }
+
+        } else {
+            if entry.has_option(NO_TOUCH_REQUIRED_OPTION) {
+                add_finding(AuditRule::SecurityKeyOptionMisuse,
+                            format!("option '{NO_TOUCH_REQUIRED_OPTION}' has no effect for '{key_type}' key"));
+            }
+
+            if entry.has_option(VERIFY_REQUIRED_OPTION) {
+                add_finding(AuditRule::SecurityKeyOptionMisuse,
+                            format!("option '{VERIFY_REQUIRED_OPTION}' has no effect for '{key_type}' key"));
+            }
+        }
+
+        match fingerprint_lines.get(&fingerprint) {
+            Some(first_line) => add_finding(AuditRule::DuplicateKey,
+                                            format!("key is duplicate of line {first_line}")),
+            None => {
+                fingerprint_lines.insert(fingerprint.clone(), line_number);
+            }
+        }
+    }
+
+    info!("audit findings: {}", findings.len());
+
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use crate::config::AktConfig;
+    use crate::tests_common::{KEY1 as ED25519_KEY, RSA_1024_KEY};
+    use crate::usecases::audit::{audit_authorized_keys, AuditRule};
+
+    const RSA_2048_KEY: &str = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQCrP/oiI0zlj5YknAFODNxIlihsalWrO7amDHPC76DwvW1UQsGany0vInOqnQ8AbhNm0HBlYDCSKJLsAZ8ErJEL12zjJdzgWIEWpSRg5CzE/Db4pkaFC1NO75yobSYLbtOhr73VfvM2LlgaL5OiFxWXnH8zje445wSwkdaGzZAehxSaK4j8V4hoUOueQDnffvppDO4/WLgndiHRjXCDyaMnC/7DeAAY82YXoODKaRlNRpBwIt0myQ0NXF7+HzRoXFAQOwNJxQSZFj6wehZQ2bdTk9reSRZxBKvoY1sWzZphxQ9TDrLKsYa5Oz+VkUgScdgeatpxseHlRwnKliQfMgFx";
+
+    const DSA_KEY: &str = "ssh-dss AAAAB3NzaC1kc3MAAACBAIN4b1+av/4O9xniXB05PGToQSD4+pMT7FtAlkcxa0+XAsmmgucqqKTSXo5dL5mMCI2GSKDMsrLgOuxrh7ouyHa80VRRd8eRMIICkG49DLMfxVfW0P4ToS5e3/f4WB65tdFN4/fPGy/1dmpZ5let6cG3F5gioGawJX+YXCR/scYJAAAAFQDfOPSY2yK5DprkG5LmJv4VSinRdwAAAIBab+z05mR/GVACEoR7Njtv5PgrYdksO//C5piw4dET/Vxp6ygJj/qERClD5vF/cPozVc9SLTkj3tTvaTeYOumWNryQXGCMsVMM0FjmFLh3/IAyLgv3Kjm5NMeUaiNtSLcC8/Ojp8anPPEf5ym+CB3OWUmvVlLqCVMBj1AXE47CTwAAAIBpp1NliujfVPOTDbLFH7E/1jPHW+CUlEjsugayHPnkwn2FB8xj7wsmAHSlFZTQC9446HTaFzPrS0y5f9WUmekgjM14J0i/hJ1kyHabAJ0Ghm+GeGpD1wz4h2lMsTQGL29r4huTlAuvF9dWnYKDBOHmrxNh7kkeJ7tKD6lARubVaQ==";
+
+    #[test]
+    fn no_findings_without_policy() {
+        let content = format!("# comment\n\n{RSA_2048_KEY} a@b.com\n{ED25519_KEY} c@d.com\n");
+
+        let findings = audit_authorized_keys(&content, "root", &AktConfig::default()).unwrap();
+
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn report_unparsable_keys() {
+        let content = format!("{ED25519_KEY} a@b.com\nssh-rsa broken-key c@d.com\n");
+
+        let findings = audit_authorized_keys(&content, "root", &AktConfig::default()).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, AuditRule::UnparsableKey);
+        assert_eq!(findings[0].line, 2);
+    }
+
+    #[test]
+    fn dsa_and_short_rsa_keys_should_be_weak_by_default() {
+        let content = format!("{RSA_1024_KEY} a@b.com\n{DSA_KEY} c@d.com\n{RSA_2048_KEY} e@f.com\n");
+
+        let findings = audit_authorized_keys(&content, "root", &AktConfig::default()).unwrap();
+
+        let rules: Vec<(usize, AuditRule)> = findings.iter().map(|f| (f.line, f.rule.clone())).collect();
+
+        assert_eq!(rules, vec![(1, AuditRule::WeakKey), (2, AuditRule::WeakKey)]);
+        assert_eq!(findings[0].message, "rsa key size 1024 bits is less than 2048");
+    }
+
+    #[test]
+    fn report_policy_violations() {
+        let content = format!("{RSA_2048_KEY} a@company.com\n{ED25519_KEY} deployer\n");
+
+        let config = AktConfig {
+            allowed_key_types: Some(vec!["ssh-ed25519".to_string()]),
+            min_rsa_bits: Some(3072),
+            comment_format: Some("^.+@company\\.com$".to_string()),
+            ..AktConfig::default()
+        };
+
+        let findings = audit_authorized_keys(&content, "root", &config).unwrap();
+
+        let rules: Vec<(usize, AuditRule)> = findings.iter().map(|f| (f.line, f.rule.clone())).collect();
+
+        assert_eq!(rules, vec![
+            (1, AuditRule::KeyTypeNotAllowed),
+            (1, AuditRule::WeakKey),
+            (2, AuditRule::InvalidComment),
+        ]);
+    }
+
+    #[test]
+    fn report_missing_required_options_for_user() {
+        let content = format!("from=\"10.0.0.1,10.0.0.2\",no-pty {ED25519_KEY} a@b.com\nno-pty {RSA_2048_KEY} c@d.com\n");
+
+        let mut config = AktConfig::default();
+        config.required_options.insert("root".to_string(), vec!["from".to_string()]);
+
+        let findings = audit_authorized_keys(&content, "root", &config).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, AuditRule::MissingRequiredOption);
+        assert_eq!(findings[0].line, 2);
+
+        let findings = audit_authorized_keys(&content, "deployer", &config).unwrap();
+
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn report_duplicate_keys() {
+        let content = format!("{ED25519_KEY} a@b.com\n{RSA_2048_KEY} c@d.com\nno-pty {ED25519_KEY} e@f.com\n");
+
+        let findings = audit_authorized_keys(&content, "root", &AktConfig::default()).unwrap();
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, AuditRule::DuplicateKey);
+        assert_eq!(findings[0].line, 3);
+        assert_eq!(findings[0].message, "key is duplicate of line 1");
+    }
+
+    #[test]
+    fn report_security_key_options() {
+        let sk_key = "sk-ssh-ed25519@openssh.com AAAAGnNrLXNzaC1lZDI1NTE5QG9wZW5zc2guY29tAAAAICkcIlkxK1/eMTYrK9kb3GTtuCSjQXbFGjB6ZgtwLIxkAAAABHNzaDo=";
+
+        let content = format!("no-touch-required {sk_key} a@b.com\nverify-required {ED25519_KEY} c@d.com\n");
+
+        let findings = audit_authorized_keys(&content, "root", &AktConfig::default()).unwrap();
+
+        let rules: Vec<(usize, AuditRule)> = findings.iter().map(|f| (f.line, f.rule.clone())).collect();
+
+        assert_eq!(rules, vec![(2, AuditRule::SecurityKeyOptionMisuse)]);
+        assert_eq!(findings[0].message, "option 'verify-required' has no effect for 'ssh-ed25519' key");
+
+        let config = AktConfig { require_touch: Some(true), require_verify: Some(true), ..AktConfig::default() };
+
+        let findings = audit_authorized_keys(&content, "root", &config).unwrap();
+
+        let rules: Vec<(usize, AuditRule)> = findings.iter().map(|f| (f.line, f.rule.clone())).collect();
+
+        assert_eq!(rules, vec![
+            (1, AuditRule::TouchNotRequired),
+            (1, AuditRule::VerifyNotRequired),
+            (2, AuditRule::SecurityKeyOptionMisuse),
+        ]);
+    }
+}
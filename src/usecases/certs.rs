@@ -0,0 +1,180 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::authlog::PublicKeyLogin;
+use crate::keysfile::KeysFile;
+use crate::usecases::users::{read_user_keys_files, UserKeysFile};
+
+const LAST_SEEN_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Certificate signed by CA and used to login
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct CertificateUsage {
+    pub key_id: String,
+
+    pub serial: u64,
+
+    pub logins: usize,
+
+    pub last_seen: String,
+}
+
+impl Display for CertificateUsage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (serial {}) logins: {} last seen: {}", self.key_id, self.serial, self.logins, self.last_seen)
+    }
+}
+
+/// `cert-authority` entry of authorized_keys file with certificates seen in auth logs
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct CertificateAuthority {
+    /// Owner of authorized_keys file, empty if file was set explicitly
+    pub username: Option<String>,
+
+    pub file: PathBuf,
+
+    /// Line index in file, starts from 0
+    pub row_index: usize,
+
+    pub fingerprint: String,
+
+    pub key_type: String,
+
+    pub key_id: String,
+
+    pub certificates: Vec<CertificateUsage>,
+}
+
+impl Display for CertificateAuthority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(username) = &self.username {
+            write!(f, "{username} ")?;
+        }
+
+        write!(f, "{}:{} {} {} {} certificates: {}", self.file.display(), self.row_index, self.fingerprint,
+               self.key_type, self.key_id, self.certificates.len())?;
+
+        for certificate in &self.certificates {
+            write!(f, "\n  {certificate}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns `cert-authority` entries with certificates (key id, serial) used to login.
+///
+/// Logins are attributed to CA entry by CA fingerprint. For files with known user only user's logins are counted.
+pub fn get_certificate_authorities(files: &[UserKeysFile], with_usernames: bool,
+                                   logins: &[PublicKeyLogin]) -> Vec<CertificateAuthority> {
+    info!("get cert-authority entries");
+
+    let mut authorities: Vec<CertificateAuthority> = vec![];
+
+    for (file, content) in read_user_keys_files(files) {
+        for (index, line) in KeysFile::parse(&content).lines.iter().enumerate() {
+            let entry = match line.get_entry() {
+                Some(entry) if entry.is_cert_authority() => entry,
+                _ => continue
+            };
+
+            let fingerprint = format!("SHA256:{}", entry.get_fingerprint());
+            debug!("cert-authority '{fingerprint}' found in '{}' at row {index}", file.path.display());
+
+            let username = if with_usernames { Some(file.username.to_string()) } else { None };
+
+            let ca_logins: Vec<&PublicKeyLogin> = logins.iter()
+                .filter(|login| username.is_none() || username.as_deref() == Some(login.username.as_str()))
+                .filter(|login| login.certificate.as_ref()
+                    .is_some_and(|certificate| certificate.ca_fingerprint == fingerprint))
+                .collect();
+
+            authorities.push(CertificateAuthority {
+                username,
+                file: file.path.clone(),
+                row_index: index,
+                fingerprint,
+                key_type: entry.key_type.to_string(),
+                key_id: entry.get_comment().to_string(),
+                certificates: get_certificate_usages(&ca_logins),
+            });
+        }
+    }
+
+    info!("cert-authority entries found: {}", authorities.len());
+
+    authorities
+}
+
+fn get_certificate_usages(logins: &[&PublicKeyLogin]) -> Vec<CertificateUsage> {
+    let mut usages: Vec<(CertificateUsage, &PublicKeyLogin)> = vec![];
+
+    for login in logins {
+        let certificate = match &login.certificate {
+            Some(certificate) => certificate,
+            None => continue
+        };
+
+        match usages.iter_mut()
+            .find(|(usage, _)| usage.key_id == certificate.key_id && usage.serial == certificate.serial) {
+            Some((usage, latest_login)) => {
+                usage.logins += 1;
+
+                if latest_login.timestamp < login.timestamp {
+                    *latest_login = login;
+                }
+            }
+            None => usages.push((CertificateUsage {
+                key_id: certificate.key_id.to_string(),
+                serial: certificate.serial,
+                logins: 1,
+                last_seen: String::new(),
+            }, login))
+        }
+    }
+
+    usages.into_iter()
+        .map(|(usage, latest_login)| CertificateUsage {
+            last_seen: latest_login.timestamp.format(LAST_SEEN_FORMAT).to_string(),
+            ..usage
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod certs_tests {
+    use crate::tests_common::{create_keys_file, get_certificate_login, get_temp_dir, KEY1 as CA_KEY,
+                              KEY1_FINGERPRINT as CA_FINGERPRINT, KEY2};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::certs::get_certificate_authorities;
+
+    #[test]
+    fn certificate_logins_should_be_attributed_to_ca_entry() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice",
+                                          &format!("{KEY2} bob@desktop\ncert-authority,principals=\"alice\" {CA_KEY} corp-ca\n"))];
+
+        let logins = vec![
+            get_certificate_login(&get_datetime_from_now(3), "alice", "alice@corp", 42, CA_FINGERPRINT),
+            get_certificate_login(&get_datetime_from_now(1), "alice", "alice@corp", 42, CA_FINGERPRINT),
+            get_certificate_login(&get_datetime_from_now(5), "alice", "alice@laptop", 7, CA_FINGERPRINT),
+            get_certificate_login(&get_datetime_from_now(1), "bob", "bob@corp", 8, CA_FINGERPRINT),
+        ];
+
+        let authorities = get_certificate_authorities(&files, true, &logins);
+
+        assert_eq!(authorities.len(), 1);
+        assert_eq!(authorities[0].row_index, 1);
+        assert_eq!(authorities[0].fingerprint, CA_FINGERPRINT);
+
+        let certificates: Vec<(String, u64, usize)> = authorities[0].certificates.iter()
+            .map(|c| (c.key_id.to_string(), c.serial, c.logins)).collect();
+
+        assert_eq!(certificates, vec![("alice@corp".to_string(), 42, 2), ("alice@laptop".to_string(), 7, 1)]);
+        assert_eq!(authorities[0].certificates[0].last_seen,
+                   logins[1].timestamp.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+}
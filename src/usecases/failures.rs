@@ -0,0 +1,150 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use log::info;
+use serde::Serialize;
+
+use crate::authlog::{FailureKind, PublicKeyFailure};
+use crate::usecases::usage::{format_timestamp, get_key_ids, get_source_addresses, SourceAddress};
+use crate::usecases::users::UserKeysFile;
+
+/// Unsuccessful attempts to login with key from auth logs
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct KeyFailures {
+    pub fingerprint: String,
+
+    /// As logged by sshd, i.e. `RSA`
+    pub key_type: String,
+
+    /// Key id from authorized_keys files, empty for unknown keys
+    pub key_id: Option<String>,
+
+    pub failed: usize,
+
+    /// Key was accepted, but login wasn't finished
+    pub postponed: usize,
+
+    /// Users which key was tried for
+    pub usernames: Vec<String>,
+
+    pub first_seen: String,
+
+    pub last_seen: String,
+
+    pub source_addresses: Vec<SourceAddress>,
+}
+
+impl Display for KeyFailures {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {} failed: {} postponed: {} users: {} first seen: {} last seen: {}", self.fingerprint,
+               self.key_type, self.key_id.as_deref().unwrap_or("unknown"), self.failed, self.postponed,
+               self.usernames.join(","), self.first_seen, self.last_seen)?;
+
+        for source_address in &self.source_addresses {
+            write!(f, "\n  {source_address}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Groups failed and postponed attempts by key, keys with most attempts go first.
+///
+/// Key ids are taken from authorized_keys `files`, keys which aren't there are likely probed.
+pub fn get_key_failures(failures: &[PublicKeyFailure], files: &[UserKeysFile]) -> Vec<KeyFailures> {
+    info!("get failed attempts by key");
+
+    let key_ids = get_key_ids(files);
+
+    let mut fingerprints: Vec<&str> = vec![];
+    let mut key_attempts: HashMap<&str, Vec<&PublicKeyFailure>> = HashMap::new();
+
+    for failure in failures {
+        let attempts = key_attempts.entry(failure.fingerprint.as_str()).or_insert_with(|| {
+            fingerprints.push(failure.fingerprint.as_str());
+            vec![]
+        });
+
+        attempts.push(failure);
+    }
+
+    let mut key_failures: Vec<KeyFailures> = vec![];
+
+    for fingerprint in fingerprints {
+        let attempts = &key_attempts[fingerprint];
+
+        let mut usernames: Vec<String> = attempts.iter().map(|attempt| attempt.username.to_string()).collect();
+        usernames.sort();
+        usernames.dedup();
+
+        let timestamps = attempts.iter().map(|attempt| attempt.timestamp);
+
+        key_failures.push(KeyFailures {
+            fingerprint: fingerprint.to_string(),
+            key_type: attempts[0].key_type.to_string(),
+            key_id: key_ids.get(fingerprint).cloned(),
+            failed: attempts.iter().filter(|attempt| attempt.kind == FailureKind::Failed).count(),
+            postponed: attempts.iter().filter(|attempt| attempt.kind == FailureKind::Postponed).count(),
+            usernames,
+            first_seen: timestamps.clone().min().map(|t| format_timestamp(&t)).unwrap_or_default(),
+            last_seen: timestamps.max().map(|t| format_timestamp(&t)).unwrap_or_default(),
+            source_addresses: get_source_addresses(
+                attempts.iter().map(|attempt| (attempt.source_ip.as_str(), attempt.timestamp))),
+        });
+    }
+
+    key_failures.sort_by_key(|failures| Reverse(failures.failed + failures.postponed));
+
+    info!("keys with failed attempts: {}", key_failures.len());
+
+    key_failures
+}
+
+#[cfg(test)]
+mod failures_tests {
+    use crate::authlog::{FailureKind, PublicKeyFailure};
+    use crate::tests_common::{create_keys_file, get_temp_dir, KEY1, KEY1_FINGERPRINT};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::failures::get_key_failures;
+
+    const UNKNOWN_FINGERPRINT: &str = "SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8";
+
+    #[test]
+    fn group_attempts_by_key() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice", &format!("{KEY1} alice@laptop\n"))];
+
+        let failures = vec![
+            get_failure(FailureKind::Postponed, "alice", "10.0.0.1", KEY1_FINGERPRINT),
+            get_failure(FailureKind::Failed, "root", "10.0.0.9", UNKNOWN_FINGERPRINT),
+            get_failure(FailureKind::Failed, "admin", "10.0.0.9", UNKNOWN_FINGERPRINT),
+        ];
+
+        let key_failures = get_key_failures(&failures, &files);
+
+        assert_eq!(key_failures.len(), 2);
+
+        assert_eq!(key_failures[0].fingerprint, UNKNOWN_FINGERPRINT);
+        assert_eq!(key_failures[0].key_id, None);
+        assert_eq!(key_failures[0].failed, 2);
+        assert_eq!(key_failures[0].usernames, vec!["admin".to_string(), "root".to_string()]);
+        assert_eq!(key_failures[0].source_addresses.len(), 1);
+        assert_eq!(key_failures[0].source_addresses[0].logins, 2);
+
+        assert_eq!(key_failures[1].key_id, Some("alice@laptop".to_string()));
+        assert_eq!(key_failures[1].postponed, 1);
+    }
+
+    fn get_failure(kind: FailureKind, username: &str, source_ip: &str, fingerprint: &str) -> PublicKeyFailure {
+        PublicKeyFailure {
+            timestamp: get_datetime_from_now(1),
+            pid: 1,
+            kind,
+            username: username.to_string(),
+            source_ip: source_ip.to_string(),
+            key_type: "ED25519".to_string(),
+            fingerprint: fingerprint.to_string(),
+        }
+    }
+}
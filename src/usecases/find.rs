@@ -0,0 +1,203 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::authlog::PublicKeyLogin;
+use crate::keysfile::entry::KeyEntry;
+use crate::keysfile::KeysFile;
+use crate::usecases::usage::{get_key_logins, get_logins_by_key};
+use crate::usecases::users::{read_user_keys_files, UserKeysFile};
+
+const SHA256_PREFIX: &str = "SHA256:";
+
+const MD5_PREFIX: &str = "MD5:";
+
+const LAST_SEEN_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum KeyQuery {
+    /// Fingerprint without `SHA256:` prefix
+    Sha256(String),
+
+    /// Fingerprint without `MD5:` prefix, i.e. `6e:f1:...`
+    Md5(String),
+
+    /// Key from public key file
+    Key(KeyEntry),
+
+    /// Key id (comment) substring, case-insensitive
+    Comment(String),
+}
+
+impl KeyQuery {
+    /// Query is a fingerprint with `SHA256:` or `MD5:` prefix (as in auth logs), path to public key file
+    /// or key id (comment) substring.
+    pub fn parse(query: &str) -> anyhow::Result<KeyQuery> {
+        if let Some(fingerprint) = query.strip_prefix(SHA256_PREFIX) {
+            return Ok(KeyQuery::Sha256(fingerprint.to_string()))
+        }
+
+        if let Some(fingerprint) = query.strip_prefix(MD5_PREFIX) {
+            return Ok(KeyQuery::Md5(fingerprint.to_lowercase()))
+        }
+
+        let path = Path::new(query);
+
+        if path.is_file() {
+            info!("query is public key file '{}'", path.display());
+
+            let content = fs::read_to_string(path)?;
+
+            let entry = KeysFile::parse(&content).lines.iter()
+                .find_map(|line| line.get_entry().cloned())
+                .ok_or_else(|| anyhow::anyhow!("public key wasn't found in file '{}'", path.display()))?;
+
+            return Ok(KeyQuery::Key(entry))
+        }
+
+        Ok(KeyQuery::Comment(query.to_string()))
+    }
+
+    pub fn is_match(&self, entry: &KeyEntry) -> bool {
+        match self {
+            KeyQuery::Sha256(fingerprint) => &entry.get_fingerprint() == fingerprint,
+            KeyQuery::Key(key_entry) => entry.get_fingerprint() == key_entry.get_fingerprint(),
+            KeyQuery::Md5(fingerprint) => &entry.get_md5_fingerprint() == fingerprint,
+            KeyQuery::Comment(comment) => entry.get_comment().to_lowercase().contains(&comment.to_lowercase())
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct FoundKey {
+    /// Owner of authorized_keys file, empty if file was set explicitly
+    pub username: Option<String>,
+
+    pub file: PathBuf,
+
+    /// Line index in file, starts from 0
+    pub row_index: usize,
+
+    pub fingerprint: String,
+
+    pub key_type: String,
+
+    pub key_id: String,
+
+    /// Latest successful login with key, empty if key wasn't found in auth logs
+    pub last_seen: Option<String>,
+}
+
+impl Display for FoundKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(username) = &self.username {
+            write!(f, "{username} ")?;
+        }
+
+        write!(f, "{}:{} {} {} {} last seen: {}", self.file.display(), self.row_index, self.fingerprint,
+               self.key_type, self.key_id, self.last_seen.as_deref().unwrap_or("never"))
+    }
+}
+
+/// Searches authorized_keys files for keys matching the query.
+///
+/// Unreadable files are skipped. For files with known user last seen is taken from user's logins only,
+/// certificate logins are attributed to CA key.
+pub fn find_keys(query: &KeyQuery, files: &[UserKeysFile], with_usernames: bool,
+                 logins: &[PublicKeyLogin]) -> Vec<FoundKey> {
+    info!("find keys by query {:?}", query);
+
+    let mut found_keys: Vec<FoundKey> = vec![];
+
+    let logins_by_key = get_logins_by_key(logins);
+
+    for (file, content) in read_user_keys_files(files) {
+        for (index, line) in KeysFile::parse(&content).lines.iter().enumerate() {
+            let entry = match line.get_entry() {
+                Some(entry) if query.is_match(entry) => entry,
+                _ => continue
+            };
+
+            let fingerprint = entry.get_fingerprint();
+            debug!("key '{fingerprint}' found in '{}' at row {index}", file.path.display());
+
+            let username = if with_usernames { Some(file.username.to_string()) } else { None };
+
+            let last_seen: Option<NaiveDateTime> = get_key_logins(
+                &logins_by_key, username.as_deref(), &format!("{SHA256_PREFIX}{fingerprint}")).iter()
+                .map(|login| login.timestamp).max();
+
+            found_keys.push(FoundKey {
+                username,
+                file: file.path.clone(),
+                row_index: index,
+                fingerprint: format!("{SHA256_PREFIX}{fingerprint}"),
+                key_type: entry.key_type.to_string(),
+                key_id: entry.get_comment().to_string(),
+                last_seen: last_seen.map(|timestamp| timestamp.format(LAST_SEEN_FORMAT).to_string()),
+            });
+        }
+    }
+
+    info!("keys found: {}", found_keys.len());
+
+    found_keys
+}
+
+#[cfg(test)]
+mod find_tests {
+    use std::fs;
+
+    use crate::keysfile::entry::KeyEntry;
+    use crate::tests_common::{create_keys_file, get_public_key_login, get_temp_dir, KEY1, KEY1_FINGERPRINT, KEY2};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::find::{find_keys, KeyQuery};
+
+    #[test]
+    fn parse_queries() {
+        let dir = get_temp_dir();
+        let public_key_path = dir.path().join("id_ed25519.pub");
+        fs::write(&public_key_path, format!("{KEY1} a@b.com\n")).unwrap();
+
+        assert_eq!(KeyQuery::parse(KEY1_FINGERPRINT).unwrap(),
+                   KeyQuery::Sha256(KEY1_FINGERPRINT.trim_start_matches("SHA256:").to_string()));
+        assert_eq!(KeyQuery::parse("MD5:6E:F1").unwrap(), KeyQuery::Md5("6e:f1".to_string()));
+        assert_eq!(KeyQuery::parse(&public_key_path.to_string_lossy()).unwrap(),
+                   KeyQuery::Key(KeyEntry::parse(&format!("{KEY1} a@b.com")).unwrap()));
+        assert_eq!(KeyQuery::parse("deployer").unwrap(), KeyQuery::Comment("deployer".to_string()));
+    }
+
+    #[test]
+    fn find_keys_in_users_files_with_last_seen() {
+        let dir = get_temp_dir();
+        let files = vec![
+            create_keys_file(dir.path(), "alice", &format!("# keys\n{KEY1} alice@Laptop\n{KEY2} bob@desktop\n")),
+            create_keys_file(dir.path(), "bob", &format!("no-pty {KEY1} alice@laptop\n")),
+        ];
+
+        let login = get_public_key_login(&get_datetime_from_now(0), "bob", KEY1_FINGERPRINT);
+
+        let found_keys = find_keys(&KeyQuery::parse("alice@laptop").unwrap(), &files, true, &[login]);
+
+        let results: Vec<(Option<String>, usize, bool)> = found_keys.iter()
+            .map(|k| (k.username.clone(), k.row_index, k.last_seen.is_some()))
+            .collect();
+
+        assert_eq!(results, vec![
+            (Some("alice".to_string()), 1, false),
+            (Some("bob".to_string()), 0, true),
+        ]);
+
+        assert_eq!(found_keys[0].fingerprint, KEY1_FINGERPRINT);
+
+        let found_keys = find_keys(&KeyQuery::parse("MD5:6e:f1:81:2f:36:aa:49:34:f4:7d:68:3a:36:7b:d6:52").unwrap(),
+                                   &files[..1], false, &[]);
+
+        assert_eq!(found_keys.len(), 1);
+        assert_eq!(found_keys[0].username, None);
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt::{Display, Formatter};
+
+use log::error;
+use serde::Serialize;
+
+use crate::keysfile::entry::KeyEntry;
+
+/// Fingerprint formats as sshd logs them.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FingerprintFormat {
+    Sha256, Md5, Both
+}
+
+impl From<&str> for FingerprintFormat {
+    fn from(value: &str) -> Self {
+        let lowercase_value = value.to_lowercase();
+
+        match lowercase_value.as_str() {
+            "md5" => FingerprintFormat::Md5,
+            "both" => FingerprintFormat::Both,
+            _ => FingerprintFormat::Sha256
+        }
+    }
+}
+
+impl Display for FingerprintFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            FingerprintFormat::Sha256 => write!(f, "sha256"),
+            FingerprintFormat::Md5 => write!(f, "md5"),
+            FingerprintFormat::Both => write!(f, "both"),
+        }
+    }
+}
+
+/// Key with fingerprints and bit length.
+///
+/// Default output is prefixed with fingerprints in `format`, JSON output always contains all fields.
+#[derive(Serialize, Clone)]
+pub struct FingerprintedKey<T: Display + Serialize> {
+    #[serde(flatten)]
+    pub key: T,
+
+    /// `SHA256:...`
+    pub fingerprint: Option<String>,
+
+    /// `MD5:...`
+    pub md5_fingerprint: Option<String>,
+
+    pub bits: Option<usize>,
+
+    #[serde(skip)]
+    pub format: Option<FingerprintFormat>,
+}
+
+impl<T: Display + Serialize> FingerprintedKey<T> {
+    /// `key` display value must be a key line, i.e. `ssh-ed25519 AAAA... a@b.com`.
+    pub fn new(key: T, format: Option<FingerprintFormat>) -> FingerprintedKey<T> {
+        let key_line = key.to_string();
+
+        match KeyEntry::parse(&key_line) {
+            Ok(entry) => FingerprintedKey {
+                key,
+                fingerprint: Some(format!("SHA256:{}", entry.get_fingerprint())),
+                md5_fingerprint: Some(format!("MD5:{}", entry.get_md5_fingerprint())),
+                bits: entry.get_bits(),
+                format,
+            },
+            Err(e) => {
+                error!("unable to parse key '{key_line}': {}", e);
+                FingerprintedKey { key, fingerprint: None, md5_fingerprint: None, bits: None, format }
+            }
+        }
+    }
+}
+
+impl<T: Display + Serialize> Display for FingerprintedKey<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let fingerprints = match &self.format {
+            Some(FingerprintFormat::Sha256) => vec![&self.fingerprint],
+            Some(FingerprintFormat::Md5) => vec![&self.md5_fingerprint],
+            Some(FingerprintFormat::Both) => vec![&self.fingerprint, &self.md5_fingerprint],
+            None => vec![]
+        };
+
+        for fingerprint in fingerprints {
+            write!(f, "{} ", fingerprint.as_deref().unwrap_or("-"))?;
+        }
+
+        write!(f, "{}", self.key)
+    }
+}
+
+pub fn get_fingerprinted_keys<T: Display + Serialize>(keys: Vec<T>,
+                                                      format: Option<FingerprintFormat>) -> Vec<FingerprintedKey<T>> {
+    keys.into_iter().map(|key| FingerprintedKey::new(key, format.clone())).collect()
+}
+
+#[cfg(test)]
+mod fingerprints_tests {
+    use authorized_keys::authorizedkeys::AuthorizedKey;
+
+    use crate::usecases::fingerprints::{FingerprintedKey, FingerprintFormat};
+
+    #[test]
+    fn prefix_key_with_fingerprints() {
+        let key = FingerprintedKey::new(get_authorized_key(), Some(FingerprintFormat::Both));
+
+        assert_eq!(key.to_string(), format!("SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE \
+                                             MD5:6e:f1:81:2f:36:aa:49:34:f4:7d:68:3a:36:7b:d6:52 {}",
+                                            get_authorized_key()));
+
+        let key = FingerprintedKey::new(get_authorized_key(), None);
+
+        assert_eq!(key.to_string(), get_authorized_key().to_string());
+    }
+
+    #[test]
+    fn json_should_contain_fingerprints_and_bits() {
+        let key = FingerprintedKey::new(get_authorized_key(), Some(FingerprintFormat::Md5));
+
+        let json = serde_json::to_value(&key).unwrap();
+
+        assert_eq!(json["fingerprint"], "SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE");
+        assert_eq!(json["md5_fingerprint"], "MD5:6e:f1:81:2f:36:aa:49:34:f4:7d:68:3a:36:7b:d6:52");
+        assert_eq!(json["bits"], 256);
+        assert_eq!(json["key_type"], "ssh-ed25519");
+    }
+
+    fn get_authorized_key() -> AuthorizedKey {
+        AuthorizedKey {
+            key_type: "ssh-ed25519".to_string(),
+            key: "AAAAC3NzaC1lZDI1NTE5AAAAIDOGSbgN43gI+oP5CebK7JsGWsMT69uymML4YHWUPI2G".to_string(),
+            id: "a@b.com".to_string(),
+            row_index: 0,
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::keysfile::entry::{is_known_key_type, KeyEntry, ParseError};
+use crate::keysfile::{KeysFile, LineKind};
+
+const WINDOWS_LINE_ENDING: &str = "\r\n";
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    InvalidBase64,
+    UnknownKeyType,
+    KeyTypeMismatch,
+    MissingKeyData,
+    BrokenOptions,
+    WindowsLineEnding,
+    TrailingGarbage,
+}
+
+impl Display for LintRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            LintRule::InvalidBase64 => write!(f, "invalid-base64"),
+            LintRule::UnknownKeyType => write!(f, "unknown-key-type"),
+            LintRule::KeyTypeMismatch => write!(f, "key-type-mismatch"),
+            LintRule::MissingKeyData => write!(f, "missing-key-data"),
+            LintRule::BrokenOptions => write!(f, "broken-options"),
+            LintRule::WindowsLineEnding => write!(f, "windows-line-ending"),
+            LintRule::TrailingGarbage => write!(f, "trailing-garbage"),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct LintIssue {
+    pub rule: LintRule,
+
+    /// Line number in authorized_keys file, starts from 1
+    pub line: usize,
+
+    pub message: String,
+
+    /// How to fix the line
+    pub suggestion: String,
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: [{}] {}. fix: {}", self.line, self.rule, self.message, self.suggestion)
+    }
+}
+
+/// Reports every problematic line of authorized_keys file with fix suggestion.
+pub fn lint_authorized_keys_file(authorized_keys_file_path: &Path) -> anyhow::Result<Vec<LintIssue>> {
+    info!("lint authorized_keys file '{}'", authorized_keys_file_path.display());
+
+    let content = fs::read_to_string(authorized_keys_file_path)?;
+
+    let issues = lint_authorized_keys(&content);
+    info!("lint issues: {}", issues.len());
+
+    Ok(issues)
+}
+
+pub fn lint_authorized_keys(content: &str) -> Vec<LintIssue> {
+    let mut issues: Vec<LintIssue> = vec![];
+
+    for (index, line) in KeysFile::parse(content).lines.iter().enumerate() {
+        let line_number = index + 1;
+
+        let mut add_issue = |rule: LintRule, message: String, suggestion: &str| {
+            debug!("line {line_number}: {rule}");
+            issues.push(LintIssue { rule, line: line_number, message, suggestion: suggestion.to_string() })
+        };
+
+        match &line.kind {
+            LineKind::Key(entry) => {
+                if let Some(key_type) = get_key_type_in_comment(entry) {
+                    add_issue(LintRule::TrailingGarbage,
+                              format!("line contains one more '{key_type}' key after comment"),
+                              "put each key on separate line");
+                }
+            }
+            LineKind::Invalid(e) => {
+                let (rule, suggestion) = get_rule_with_suggestion(e);
+                add_issue(rule, e.to_string(), &suggestion);
+            }
+            _ => {}
+        }
+
+        if line.kind != LineKind::Comment && line.text.chars().any(|c| c.is_control() && c != '\t') {
+            add_issue(LintRule::TrailingGarbage, "line contains control characters".to_string(),
+                      "remove non-printable characters, i.e. with `cat -A` to find them");
+        }
+
+        if line.line_ending == WINDOWS_LINE_ENDING {
+            add_issue(LintRule::WindowsLineEnding, "line ends with CRLF".to_string(),
+                      "convert file to unix line endings, i.e. with `dos2unix`");
+        }
+    }
+
+    issues
+}
+
+fn get_rule_with_suggestion(parse_error: &ParseError) -> (LintRule, String) {
+    match parse_error {
+        ParseError::MissingKeyType | ParseError::UnknownKeyType(_) =>
+            (LintRule::UnknownKeyType, "check key type, i.e. 'ssh-ed25519' or 'ssh-rsa', \
+                                        and quotes in options before key type".to_string()),
+        ParseError::MissingKeyData =>
+            (LintRule::MissingKeyData, "copy the whole line from public key (.pub) file".to_string()),
+        ParseError::InvalidBase64 =>
+            (LintRule::InvalidBase64, "key data is probably truncated or wrapped, \
+                                       copy it again from public key (.pub) file".to_string()),
+        ParseError::KeyTypeMismatch(blob_key_type) =>
+            (LintRule::KeyTypeMismatch, format!("change key type to '{blob_key_type}'")),
+        ParseError::UnterminatedQuote =>
+            (LintRule::BrokenOptions, "close quote in option value, escape inner quotes as \\\"".to_string()),
+        ParseError::EmptyOption =>
+            (LintRule::BrokenOptions, "remove extra comma between options".to_string()),
+    }
+}
+
+/// Detects two keys pasted on the same line: the second one ends up in comment.
+fn get_key_type_in_comment(entry: &KeyEntry) -> Option<&str> {
+    let mut tokens = entry.get_comment().split_whitespace();
+
+    while let Some(token) = tokens.next() {
+        if is_known_key_type(token) && tokens.next().is_some() {
+            return Some(token)
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use crate::tests_common::KEY1 as ED25519_KEY;
+    use crate::usecases::lint::{lint_authorized_keys, LintRule};
+
+    #[test]
+    fn no_issues_for_valid_file() {
+        let content = format!("# comment\n\nno-pty {ED25519_KEY} a@b.com\n");
+
+        assert!(lint_authorized_keys(&content).is_empty());
+    }
+
+    #[test]
+    fn report_problematic_lines() {
+        let content = format!("{ED25519_KEY} a@b.com\n\
+                               ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAA!!!\n\
+                               ssh-foo AAAA\n\
+                               {}\n\
+                               command=\"ls {ED25519_KEY}\n\
+                               {ED25519_KEY} a@b.com {ED25519_KEY} c@d.com\n\
+                               {ED25519_KEY} a@b.com\x07\n",
+                              ED25519_KEY.replace("ssh-ed25519", "ssh-rsa"));
+
+        let issues = lint_authorized_keys(&content);
+
+        let rules: Vec<(usize, LintRule)> = issues.iter().map(|i| (i.line, i.rule.clone())).collect();
+
+        assert_eq!(rules, vec![
+            (2, LintRule::InvalidBase64),
+            (3, LintRule::UnknownKeyType),
+            (4, LintRule::KeyTypeMismatch),
+            (5, LintRule::BrokenOptions),
+            (6, LintRule::TrailingGarbage),
+            (7, LintRule::TrailingGarbage),
+        ]);
+
+        assert_eq!(issues[1].message, "unknown key type 'ssh-foo'");
+        assert_eq!(issues[2].suggestion, "change key type to 'ssh-ed25519'");
+    }
+
+    #[test]
+    fn report_windows_line_endings() {
+        let content = format!("{ED25519_KEY} a@b.com\r\n{ED25519_KEY} c@d.com");
+
+        let issues = lint_authorized_keys(&content);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].rule, LintRule::WindowsLineEnding);
+        assert_eq!(issues[0].line, 1);
+    }
+}
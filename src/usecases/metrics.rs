@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime};
+use log::info;
+
+use crate::authlog::{LogCoverage, PublicKeyLogin};
+use crate::config::AktConfig;
+use crate::files::write_file_atomically_with_mode;
+use crate::keysfile::KeysFile;
+use crate::usecases::audit::{audit_authorized_keys, AuditRule};
+use crate::usecases::oldkeys::{get_keys_older_than, RemovalReason};
+use crate::usecases::protected::ProtectedKeys;
+use crate::usecases::usage::{get_key_logins, get_logins_by_key};
+use crate::usecases::users::{read_user_keys_files, UserKeysFile};
+
+/// node_exporter usually runs as a separate user
+const METRICS_FILE_MODE: u32 = 0o644;
+
+/// Key counts for authorized_keys file
+struct FileMetrics {
+    username: String,
+
+    file: PathBuf,
+
+    keys: usize,
+
+    stale: usize,
+
+    not_seen: usize,
+
+    weak: usize,
+
+    /// (fingerprint, comment, days since the latest login) for keys with logins
+    last_seen_days: Vec<(String, String, i64)>,
+}
+
+/// Metrics in Prometheus text format for node_exporter textfile collector.
+///
+/// Keys used older than `older_than_days` are stale, protected keys are never counted as stale or not seen.
+/// Without `with_usernames` logins of all users are taken into account.
+pub fn get_metrics(files: &[UserKeysFile], with_usernames: bool, logins: &[PublicKeyLogin],
+                   log_coverage: Option<&LogCoverage>, older_than_days: usize,
+                   protected_keys: &ProtectedKeys, config: &AktConfig) -> anyhow::Result<String> {
+    info!("get metrics for {} authorized_keys files", files.len());
+
+    let now = Local::now().naive_local();
+
+    let mut files_metrics: Vec<FileMetrics> = vec![];
+
+    let logins_by_key = get_logins_by_key(logins);
+
+    for (file, content) in read_user_keys_files(files) {
+        let username = if with_usernames { Some(file.username.as_str()) } else { None };
+
+        let weak = audit_authorized_keys(&content, &file.username, config)?.iter()
+            .filter(|finding| finding.rule == AuditRule::WeakKey).count();
+
+        let file_logins: Cow<[PublicKeyLogin]> = if with_usernames {
+            Cow::Owned(logins.iter().filter(|login| login.username == file.username).cloned().collect())
+
+        } else {
+            Cow::Borrowed(logins)
+        };
+
+        let candidates = get_keys_older_than(&file_logins, older_than_days,
+                                             &file.path.display().to_string(), protected_keys)?;
+
+        let get_count = |reason: RemovalReason| candidates.iter().filter(|candidate| candidate.reason == reason).count();
+
+        let mut metrics = FileMetrics {
+            username: file.username.to_string(),
+            file: file.path.clone(),
+            keys: 0,
+            stale: get_count(RemovalReason::Stale),
+            not_seen: get_count(RemovalReason::NotSeen),
+            weak,
+            last_seen_days: vec![],
+        };
+
+        for entry in KeysFile::parse(&content).lines.iter().filter_map(|line| line.get_entry()) {
+            metrics.keys += 1;
+
+            let fingerprint = format!("SHA256:{}", entry.get_fingerprint());
+
+            let last_seen: Option<NaiveDateTime> = get_key_logins(&logins_by_key, username, &fingerprint).iter()
+                .map(|login| login.timestamp).max();
+
+            if let Some(timestamp) = last_seen {
+                metrics.last_seen_days.push((fingerprint, entry.get_comment().to_string(),
+                                             now.signed_duration_since(timestamp).num_days()));
+            }
+        }
+
+        files_metrics.push(metrics);
+    }
+
+    Ok(format_metrics(&files_metrics, log_coverage))
+}
+
+/// Writes metrics with temp file and rename, so collector never reads partial file.
+pub fn write_metrics_file(path: &Path, metrics: &str) -> anyhow::Result<()> {
+    write_file_atomically_with_mode(path, metrics.as_bytes(), METRICS_FILE_MODE)
+}
+
+fn format_metrics(files_metrics: &[FileMetrics], log_coverage: Option<&LogCoverage>) -> String {
+    let mut text = String::new();
+
+    push_file_gauge(&mut text, "akt_keys", "Number of keys in authorized_keys file", files_metrics, |m| m.keys);
+    push_file_gauge(&mut text, "akt_stale_keys", "Number of keys used older than threshold", files_metrics, |m| m.stale);
+    push_file_gauge(&mut text, "akt_not_seen_keys", "Number of keys not found in auth logs", files_metrics, |m| m.not_seen);
+    push_file_gauge(&mut text, "akt_weak_keys", "Number of keys weaker than policy allows", files_metrics, |m| m.weak);
+
+    text.push_str("# HELP akt_key_last_seen_days Days since the latest login with key\n\
+                   # TYPE akt_key_last_seen_days gauge\n");
+
+    for metrics in files_metrics {
+        for (fingerprint, comment, days) in &metrics.last_seen_days {
+            text.push_str(&format!("akt_key_last_seen_days{{user=\"{}\",fingerprint=\"{}\",comment=\"{}\"}} {days}\n",
+                                   escape_label_value(&metrics.username), escape_label_value(fingerprint),
+                                   escape_label_value(comment)));
+        }
+    }
+
+    if let Some(log_coverage) = log_coverage {
+        text.push_str(&format!("# HELP akt_log_coverage_days Days covered by available auth logs\n\
+                                # TYPE akt_log_coverage_days gauge\nakt_log_coverage_days {}\n", log_coverage.days));
+    }
+
+    text
+}
+
+fn push_file_gauge(text: &mut String, name: &str, help: &str, files_metrics: &[FileMetrics],
+                   get_value: fn(&FileMetrics) -> usize) {
+    text.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+
+    for metrics in files_metrics {
+        text.push_str(&format!("{name}{{user=\"{}\",file=\"{}\"}} {}\n", escape_label_value(&metrics.username),
+                               escape_label_value(&metrics.file.display().to_string()), get_value(metrics)));
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod metrics_tests {
+    use crate::authlog::LogCoverage;
+    use crate::config::AktConfig;
+    use crate::tests_common::{create_keys_file, get_public_key_login, get_temp_dir, KEY1, KEY1_FINGERPRINT, KEY2,
+                              KEY2_FINGERPRINT, RSA_1024_KEY};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::metrics::get_metrics;
+    use crate::usecases::protected::ProtectedKeys;
+
+    #[test]
+    fn metrics_should_contain_key_counts_and_last_seen_days() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice",
+                                          &format!("{KEY1} John \"JD\" Doe\n{KEY2} alice@desktop\n{RSA_1024_KEY} legacy\n"))];
+
+        let logins = vec![
+            get_public_key_login(&get_datetime_from_now(40), "alice", KEY1_FINGERPRINT),
+            get_public_key_login(&get_datetime_from_now(3), "alice", KEY2_FINGERPRINT),
+        ];
+
+        let log_coverage = LogCoverage { from: String::new(), to: String::new(), days: 45, days_since_last_record: 0 };
+
+        let metrics = get_metrics(&files, true, &logins, Some(&log_coverage), 31,
+                                  &ProtectedKeys::default(), &AktConfig::default()).unwrap();
+
+        let labels = format!("user=\"alice\",file=\"{}\"", files[0].path.display());
+
+        assert!(metrics.contains(&format!("akt_keys{{{labels}}} 3\n")));
+        assert!(metrics.contains(&format!("akt_stale_keys{{{labels}}} 1\n")));
+        assert!(metrics.contains(&format!("akt_not_seen_keys{{{labels}}} 1\n")));
+        assert!(metrics.contains(&format!("akt_weak_keys{{{labels}}} 1\n")));
+        assert!(metrics.contains(&format!("akt_key_last_seen_days{{user=\"alice\",fingerprint=\"{KEY1_FINGERPRINT}\",comment=\"John \\\"JD\\\" Doe\"}} 40\n")));
+        assert!(metrics.contains("# TYPE akt_log_coverage_days gauge\nakt_log_coverage_days 45\n"));
+    }
+}
@@ -1 +1,20 @@
+pub mod audit;
+pub mod certs;
+pub mod failures;
+pub mod find;
+pub mod fingerprints;
+pub mod lint;
+pub mod metrics;
 pub mod oldkeys;
+pub mod perms;
+pub mod protected;
+pub mod records;
+pub mod report;
+pub mod revoke;
+pub mod revokedkeys;
+pub mod sharedkeys;
+pub mod stats;
+pub mod suggestfrom;
+pub mod sync;
+pub mod usage;
+pub mod users;
@@ -1,51 +1,177 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
 use std::path::Path;
 
-use authorized_keys::authorizedkeys::{AuthorizedKey, get_authorized_keys_from_file};
+use authorized_keys::authorizedkeys::AuthorizedKey;
 use chrono::{Local, NaiveDateTime};
 use log::{debug, error, info};
-use openssh_keys::PublicKey;
-use ssh_auth_log::{get_login_with_key_attempts, KeyLoginAttempt};
-use ssh_auth_log::provider::AuthLogsProvider;
-use ssh_fingerprint_rs::{get_public_key_fingerprints_from_file, PublicKeyFingerprint};
-
-/// 1. Loads all success login attempts with public keys
-/// 2. Returns key used older than X days (`days_threshold`)
-pub fn get_keys_older_than(auth_logs_provider: &impl AuthLogsProvider,
+use serde::Serialize;
+use ssh_auth_log::KeyLoginAttempt;
+
+use crate::authlog::PublicKeyLogin;
+use crate::keysfile::entry::KeyEntry;
+use crate::keysfile::{KeysFile, LineKind};
+use crate::usecases::protected::ProtectedKeys;
+use crate::usecases::usage::format_timestamp;
+
+const SHA256_PREFIX: &str = "SHA256:";
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemovalReason {
+    /// Key wasn't found in auth logs
+    NotSeen,
+
+    /// Key was used older than days threshold
+    Stale,
+
+    /// Key would be a candidate, but it's in protected keys list
+    Protected
+}
+
+impl Display for RemovalReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            RemovalReason::NotSeen => write!(f, "not-seen"),
+            RemovalReason::Stale => write!(f, "stale"),
+            RemovalReason::Protected => write!(f, "protected")
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct RemovalCandidate {
+    #[serde(flatten)]
+    pub key: AuthorizedKey,
+
+    pub reason: RemovalReason,
+
+    /// Latest login with key, empty if key wasn't found in auth logs
+    pub last_seen: Option<String>,
+
+    /// Days since latest login
+    pub days_since: Option<i64>,
+}
+
+impl RemovalCandidate {
+    pub fn is_protected(&self) -> bool {
+        self.reason == RemovalReason::Protected
+    }
+}
+
+impl Display for RemovalCandidate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Returns keys used older than X days (`days_threshold`) according to `logins` from auth logs.
+///
+/// Keys from `protected_keys` are returned with `RemovalReason::Protected` reason.
+/// Logins with certificates count as usage of `cert-authority` entry.
+pub fn get_keys_older_than(logins: &[PublicKeyLogin],
                            days_threshold: usize,
-                           authorized_keys_file_path: &str) -> anyhow::Result<Vec<AuthorizedKey>> {
-    info!("get public keys older than {days_threshold} day(s)");
+                           authorized_keys_file_path: &str,
+                           protected_keys: &ProtectedKeys) -> anyhow::Result<Vec<RemovalCandidate>> {
+    let mut candidates_for_removal: Vec<RemovalCandidate> = vec![];
+
+    for_each_key_older_than(logins, days_threshold, authorized_keys_file_path,
+                            protected_keys, |candidate| candidates_for_removal.push(candidate))?;
+
+    Ok(candidates_for_removal)
+}
+
+/// Same as `get_keys_older_than`, but candidates are passed to `on_candidate` in authorized_keys file order
+/// instead of being collected. Auth logs must be loaded before, so it doesn't stream logins.
+pub fn for_each_key_older_than(logins: &[PublicKeyLogin],
+                               days_threshold: usize,
+                               authorized_keys_file_path: &str,
+                               protected_keys: &ProtectedKeys,
+                               on_candidate: impl FnMut(RemovalCandidate)) -> anyhow::Result<()> {
     debug!("authorized_keys path '{authorized_keys_file_path}'");
 
-    let attempts = get_login_with_key_attempts(auth_logs_provider)?;
+    let keys_file = KeysFile::parse(&fs::read_to_string(authorized_keys_file_path)?);
+
+    for_each_entry_older_than(logins, days_threshold, &keys_file, protected_keys, on_candidate);
+
+    Ok(())
+}
+
+/// Same as `for_each_key_older_than` for already parsed authorized_keys file.
+pub fn for_each_entry_older_than(logins: &[PublicKeyLogin],
+                                 days_threshold: usize,
+                                 keys_file: &KeysFile,
+                                 protected_keys: &ProtectedKeys,
+                                 on_candidate: impl FnMut(RemovalCandidate)) {
+    info!("get public keys older than {days_threshold} day(s)");
+
+    let attempts = get_key_login_attempts(logins);
     info!("success login attempts received: {}", attempts.len());
 
-    let actual_fingerprints = get_public_key_fingerprints_from_file(&authorized_keys_file_path)?;
+    let key_entries = get_key_entries(keys_file);
+    debug!("authorized keys {}", key_entries.len());
+
+    let actual_fingerprints: Vec<String> = key_entries.iter().map(|(_, entry)| entry.get_fingerprint()).collect();
 
-    let attempts_map: HashMap<String, KeyLoginAttempt> = get_attempts_map(&attempts, &actual_fingerprints);
+    let mut attempts_map: HashMap<String, KeyLoginAttempt> = get_attempts_map(&attempts, &actual_fingerprints);
 
-    let authorized_keys_path = Path::new(authorized_keys_file_path);
+    add_certificate_attempts(&mut attempts_map, logins);
 
-    let authorized_keys = get_authorized_keys_from_file(&authorized_keys_path)?;
-    debug!("authorized keys {}", authorized_keys.len());
+    find_key_candidates_for_removal(&key_entries, &attempts_map, days_threshold as u64, protected_keys, on_candidate);
+}
 
-    let candidates_for_removal: Vec<AuthorizedKey> = get_key_candidates_for_removal(
-        &authorized_keys, &attempts_map, days_threshold as u64);
+/// Keys of authorized_keys file, row index is line index (starts from 0) as in other commands.
+pub fn read_authorized_keys(authorized_keys_file_path: &Path) -> anyhow::Result<Vec<AuthorizedKey>> {
+    let keys_file = KeysFile::parse(&fs::read_to_string(authorized_keys_file_path)?);
 
-    Ok(candidates_for_removal)
+    Ok(get_key_entries(&keys_file).into_iter()
+        .map(|(index, entry)| get_authorized_key(index, entry)).collect())
+}
+
+/// Key entries with line indexes, malformed lines are skipped.
+fn get_key_entries(keys_file: &KeysFile) -> Vec<(usize, &KeyEntry)> {
+    let mut entries: Vec<(usize, &KeyEntry)> = vec![];
+
+    for (index, line) in keys_file.lines.iter().enumerate() {
+        match &line.kind {
+            LineKind::Key(entry) => entries.push((index, entry)),
+            LineKind::Invalid(e) => error!("unable to parse key at row {index}: {e}, use `akt lint` to find malformed lines"),
+            _ => {}
+        }
+    }
+
+    entries
+}
+
+fn get_authorized_key(row_index: usize, entry: &KeyEntry) -> AuthorizedKey {
+    AuthorizedKey {
+        key_type: entry.key_type.to_string(),
+        key: entry.key_data.to_string(),
+        id: entry.get_comment().to_string(),
+        row_index,
+    }
+}
+
+fn get_key_login_attempts(logins: &[PublicKeyLogin]) -> Vec<KeyLoginAttempt> {
+    logins.iter().map(|login| KeyLoginAttempt {
+        timestamp: login.timestamp,
+        key_type: login.key_type.to_string(),
+        fingerprint_type: "SHA256".to_string(),
+        fingerprint: login.fingerprint.trim_start_matches(SHA256_PREFIX).to_string(),
+        username: login.username.to_string(),
+        key_offset: 0,
+    }).collect()
 }
 
 /// Collects latest attempts by key (fingerprint).
-fn get_attempts_map(attempts: &Vec<KeyLoginAttempt>,
-                    actual_fingerprints: &Vec<PublicKeyFingerprint>) -> HashMap<String, KeyLoginAttempt> {
+/// `actual_fingerprints` are fingerprints of authorized_keys file without `SHA256:` prefix.
+fn get_attempts_map(attempts: &[KeyLoginAttempt], actual_fingerprints: &[String]) -> HashMap<String, KeyLoginAttempt> {
 
     let mut attempts_map: HashMap<String, KeyLoginAttempt> = HashMap::new();
 
     for login_attempt in attempts {
-        let fingerprint_found = actual_fingerprints.iter()
-            .find(|af| af.fingerprint == login_attempt.fingerprint);
-
-        if fingerprint_found.is_some() {
+        if actual_fingerprints.contains(&login_attempt.fingerprint) {
             info!("fingerprint '{}' from auth log was found in authorized_keys file",
                                 login_attempt.fingerprint);
 
@@ -71,67 +197,101 @@ fn get_attempts_map(attempts: &Vec<KeyLoginAttempt>,
     attempts_map
 }
 
-fn get_key_candidates_for_removal(authorized_keys: &Vec<AuthorizedKey>,
-                                  attempts_map: &HashMap<String, KeyLoginAttempt>,
-                                  days_threshold: u64) -> Vec<AuthorizedKey> {
+/// Certificate login is recorded as attempt with CA key fingerprint, so CA entry isn't stale
+/// while certificates signed by it are used.
+fn add_certificate_attempts(attempts_map: &mut HashMap<String, KeyLoginAttempt>, certificate_logins: &[PublicKeyLogin]) {
+    for login in certificate_logins {
+        let certificate = match &login.certificate {
+            Some(certificate) => certificate,
+            None => continue
+        };
+
+        let key = certificate.ca_fingerprint.trim_start_matches(SHA256_PREFIX).to_string();
+
+        if attempts_map.get(&key).is_some_and(|saved_attempt| saved_attempt.timestamp >= login.timestamp) {
+            continue
+        }
+
+        debug!("certificate '{}' (serial {}) login is attributed to CA '{}'",
+               certificate.key_id, certificate.serial, certificate.ca_fingerprint);
+
+        attempts_map.insert(key.to_string(), KeyLoginAttempt {
+            timestamp: login.timestamp,
+            key_type: certificate.ca_key_type.to_string(),
+            fingerprint_type: "SHA256".to_string(),
+            fingerprint: key,
+            username: login.username.to_string(),
+            key_offset: 0,
+        });
+    }
+}
+
+fn find_key_candidates_for_removal(key_entries: &[(usize, &KeyEntry)],
+                                   attempts_map: &HashMap<String, KeyLoginAttempt>,
+                                   days_threshold: u64,
+                                   protected_keys: &ProtectedKeys,
+                                   mut on_candidate: impl FnMut(RemovalCandidate)) {
     info!("get key candidates for removal, days threshold: {days_threshold}");
-    debug!("authorized keys: {}", authorized_keys.len());
+    debug!("authorized keys: {}", key_entries.len());
     debug!("attempts map: {}", attempts_map.len());
     let key_days_threshold = days_threshold as i64;
 
-    let mut candidates_for_removal: Vec<AuthorizedKey> = vec![];
-
     let now: NaiveDateTime = Local::now().naive_local();
 
-    for authorized_key in authorized_keys {
-        let authorized_key_str = format!("{}", authorized_key);
-
-        if let Ok(public_key) = PublicKey::parse(&authorized_key_str) {
-            let actual_fingerprint = format!("{}", public_key.fingerprint());
+    for (row_index, entry) in key_entries {
+        let actual_fingerprint = entry.get_fingerprint();
 
-            if attempts_map.contains_key(&actual_fingerprint) {
-                if let Some(latest_login_attempt) = &attempts_map.get(&actual_fingerprint) {
+        let reason = match attempts_map.get(&actual_fingerprint) {
+            Some(latest_login_attempt) => {
+                let since = now.signed_duration_since(latest_login_attempt.timestamp);
+                info!("duration since from now: {}", since.num_seconds());
 
-                    let since = now.signed_duration_since(latest_login_attempt.timestamp);
-                    info!("duration since from now: {}", since.num_seconds());
-
-                    if since.num_days() > key_days_threshold {
-                        debug!("since days {}", since.num_days());
-                        if !candidates_for_removal.contains(authorized_key) {
-                            candidates_for_removal.push(authorized_key.clone());
-                            info!("key with fingerprint '{actual_fingerprint}' was added to candidate list");
-                        }
-                    }
+                if since.num_days() > key_days_threshold {
+                    debug!("since days {}", since.num_days());
+                    Some(RemovalReason::Stale)
 
                 } else {
-                    info!("key with fingerprint '{actual_fingerprint}' wasn't found in auth logs, so it's candidate for removal");
-                    candidates_for_removal.push(authorized_key.clone())
+                    None
                 }
+            }
+            None => {
+                info!("key with fingerprint '{actual_fingerprint}' wasn't found in auth logs, so it's candidate for removal");
+                Some(RemovalReason::NotSeen)
+            }
+        };
 
-            } else {
-                candidates_for_removal.push(authorized_key.clone());
+        let last_seen = attempts_map.get(&actual_fingerprint).map(|attempt| attempt.timestamp);
+
+        if let Some(mut reason) = reason {
+            if protected_keys.is_entry_protected(entry) {
+                info!("key with fingerprint '{actual_fingerprint}' is protected, skip");
+                reason = RemovalReason::Protected;
             }
 
-        } else {
-            error!("unable to parse key: '{authorized_key_str}'")
+            on_candidate(RemovalCandidate {
+                key: get_authorized_key(*row_index, entry),
+                reason,
+                last_seen: last_seen.map(|timestamp| format_timestamp(&timestamp)),
+                days_since: last_seen.map(|timestamp| now.signed_duration_since(timestamp).num_days()),
+            });
+            info!("key with fingerprint '{actual_fingerprint}' was added to candidate list");
         }
-
     }
-
-    candidates_for_removal
 }
 
 #[cfg(test)]
 mod candidate_for_removal_tests {
     use std::collections::HashMap;
+    use std::fs;
 
-    use authorized_keys::authorizedkeys::AuthorizedKey;
-    use openssh_keys::PublicKey;
     use ssh_auth_log::KeyLoginAttempt;
 
-    use crate::tests_common::{get_key_login_attempt, get_random_string, init_logging};
+    use crate::keysfile::entry::KeyEntry;
+    use crate::tests_common::{get_key_login_attempt, get_random_string, get_temp_dir, init_logging, KEY1};
     use crate::tests_common::time::get_datetime_from_now;
-    use crate::usecases::oldkeys::get_key_candidates_for_removal;
+    use crate::usecases::oldkeys::{find_key_candidates_for_removal, get_keys_older_than, RemovalCandidate,
+                                   RemovalReason};
+    use crate::usecases::protected::{get_protected_keys_from_str, ProtectedKeys};
 
     #[test]
     fn exclude_today_attempts() {
@@ -141,7 +301,7 @@ mod candidate_for_removal_tests {
         let auth_key2 = get_authorized_key2();
         let auth_key3 = get_authorized_key3();
 
-        let fingerprint2 = get_fingerprint(&auth_key2);
+        let fingerprint2 = auth_key2.get_fingerprint();
 
         let auth_keys = vec![auth_key1.clone(), auth_key2.clone(), auth_key3.clone()];
 
@@ -150,9 +310,9 @@ mod candidate_for_removal_tests {
         add_attempt(&mut attempts_map, 0, &fingerprint2);
 
         let results = get_key_candidates_for_removal(
-            &auth_keys, &attempts_map, 0);
+            &auth_keys, &attempts_map, 0, &ProtectedKeys::default());
 
-        assert!(!results.contains(&auth_key2));
+        assert!(!contains_key(&results, &auth_key2));
     }
 
     #[test]
@@ -163,7 +323,7 @@ mod candidate_for_removal_tests {
         let auth_key2 = get_authorized_key2();
         let auth_key3 = get_authorized_key3();
 
-        let fingerprint2 = get_fingerprint(&auth_key2);
+        let fingerprint2 = auth_key2.get_fingerprint();
 
         let auth_keys = vec![auth_key1.clone(), auth_key2.clone(), auth_key3.clone()];
 
@@ -172,10 +332,10 @@ mod candidate_for_removal_tests {
         add_attempt(&mut attempts_map, 8, &fingerprint2);
 
         let results = get_key_candidates_for_removal(
-            &auth_keys, &attempts_map, 2);
+            &auth_keys, &attempts_map, 2, &ProtectedKeys::default());
 
-        assert!(results.contains(&auth_key1));
-        assert!(results.contains(&auth_key3));
+        assert!(contains_key(&results, &auth_key1));
+        assert!(contains_key(&results, &auth_key3));
     }
 
     #[test]
@@ -186,9 +346,9 @@ mod candidate_for_removal_tests {
         let auth_key2 = get_authorized_key2();
         let auth_key3 = get_authorized_key3();
 
-        let fingerprint1 = get_fingerprint(&auth_key1);
-        let fingerprint2 = get_fingerprint(&auth_key2);
-        let fingerprint3 = get_fingerprint(&auth_key3);
+        let fingerprint1 = auth_key1.get_fingerprint();
+        let fingerprint2 = auth_key2.get_fingerprint();
+        let fingerprint3 = auth_key3.get_fingerprint();
 
         let auth_keys = vec![auth_key1.clone(), auth_key2.clone(), auth_key3.clone()];
 
@@ -201,62 +361,137 @@ mod candidate_for_removal_tests {
         add_attempt(&mut attempts_map, 8, &fingerprint2);
         add_attempt(&mut attempts_map, 11, &fingerprint3);
 
-        let results = get_key_candidates_for_removal(&auth_keys, &attempts_map, 2);
+        let results = get_key_candidates_for_removal(&auth_keys, &attempts_map, 2, &ProtectedKeys::default());
 
         assert_eq!(results.len(), 2);
 
-        assert!(!results.contains(&auth_key1));
-        assert!(results.contains(&auth_key2));
-        assert!(results.contains(&auth_key3));
+        assert!(!contains_key(&results, &auth_key1));
+        assert!(contains_key(&results, &auth_key2));
+        assert!(contains_key(&results, &auth_key3));
+    }
+
+    #[test]
+    fn protected_keys_should_be_marked_with_protected_reason() {
+        init_logging();
+
+        let auth_key1 = get_authorized_key1();
+        let mut auth_key2 = get_authorized_key2();
+        auth_key2.comment = Some("test@ansible".to_string());
+        let auth_key3 = get_authorized_key3();
+
+        let fingerprint1 = auth_key1.get_fingerprint();
+
+        let auth_keys = vec![auth_key1.clone(), auth_key2.clone(), auth_key3.clone()];
+
+        let attempts_map: HashMap<String, KeyLoginAttempt> = HashMap::new();
+
+        let protected_keys = get_protected_keys_from_str(
+            &format!("SHA256:{fingerprint1}\ntest@ansible")).unwrap();
+
+        let results = get_key_candidates_for_removal(&auth_keys, &attempts_map, 2, &protected_keys);
+
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(get_reason(&results, &auth_key1), Some(RemovalReason::Protected));
+        assert_eq!(get_reason(&results, &auth_key2), Some(RemovalReason::Protected));
+        assert_eq!(get_reason(&results, &auth_key3), Some(RemovalReason::NotSeen));
+    }
+
+    #[test]
+    fn protected_keys_used_recently_should_not_be_returned() {
+        init_logging();
+
+        let auth_key1 = get_authorized_key1();
+        let fingerprint1 = auth_key1.get_fingerprint();
+
+        let auth_keys = vec![auth_key1.clone()];
+
+        let mut attempts_map: HashMap<String, KeyLoginAttempt> = HashMap::new();
+        add_attempt(&mut attempts_map, 1, &fingerprint1);
+
+        let protected_keys = get_protected_keys_from_str(&format!("SHA256:{fingerprint1}")).unwrap();
+
+        let results = get_key_candidates_for_removal(&auth_keys, &attempts_map, 2, &protected_keys);
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn row_index_should_be_line_index() {
+        let dir = get_temp_dir();
+        let file_path = dir.path().join("authorized_keys");
+        fs::write(&file_path, format!("# keys\n\nssh-ed25519 broken\nno-pty {KEY1} a@b.com\n")).unwrap();
+
+        let results = get_keys_older_than(&[], 2, &file_path.to_string_lossy(), &ProtectedKeys::default()).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].key.row_index, 3);
+        assert_eq!(results[0].key.id, "a@b.com");
+    }
+
+    fn contains_key(results: &[RemovalCandidate], entry: &KeyEntry) -> bool {
+        results.iter().any(|c| c.key.key == entry.key_data)
+    }
+
+    fn get_reason(results: &[RemovalCandidate], entry: &KeyEntry) -> Option<RemovalReason> {
+        results.iter().find(|c| c.key.key == entry.key_data).map(|c| c.reason.clone())
     }
 
     fn add_attempt(attempts_map: &mut HashMap<String, KeyLoginAttempt>, days_from_now: u64, fingerprint: &str) {
         let days_before = get_datetime_from_now(days_from_now);
-        let attempt = get_key_login_attempt(&days_before, &fingerprint);
+        let attempt = get_key_login_attempt(&days_before, fingerprint);
         attempts_map.insert(fingerprint.to_string(), attempt);
     }
 
-    fn get_authorized_key1() -> AuthorizedKey {
-        AuthorizedKey {
-            key_type: "rsa".to_string(),
-            key: "AAAAB3NzaC1yc2EAAAADAQABAAABgQDAd6jIpyOMz50jtD+7FrKhQ3yzYjZTr0zCixTHDTZ2w2nEcrnkGqF/2L1HAiYVv1kub/GlL8po1gv7CwOE4O2F5VwtSNco84YEcl8zL7tTKJCdmOVqajvFtRmYP6vQQ8q1ffODlky7u98HkQN/Pgu+zCd1D104Tx3bpPJoFOGfn3nZm5b3zTgM2Ie2qJwyRHdvJwmtJtmf6IAG9XF1GdzPJ15U6g/7SndvfGX++KodYZzSUWsbLDxC0Vpr4nH1+C8JIWApUFXTTKCSyoSm3hmDSXrreOkmMSltVHj8SQYFNmMeMRMvKZwmqi6RMC5AXock4gFxzaxCsDtqrfc4MYb9UE/uUiSeyQ2GSjW6soq+9K/+s8nmCnzxGTuM7gwGG1Ada7qgIrLAHKdQyiDX9/wwwi7Nax8OO3+orWJjfQymoHL3/aYEhXE0c2pscAeYaB6iiw+UkvTUSJ0nun9bjR8jY3iS0DUM4jYSkKaVGl2/kOv/fZdf4I+cCuHs/0stREc=".to_string(),
-            id: get_random_string(),
-            row_index: 0,
-        }
+    fn get_key_candidates_for_removal(entries: &[KeyEntry],
+                                      attempts_map: &HashMap<String, KeyLoginAttempt>,
+                                      days_threshold: u64,
+                                      protected_keys: &ProtectedKeys) -> Vec<RemovalCandidate> {
+        let mut candidates: Vec<RemovalCandidate> = vec![];
+
+        let key_entries: Vec<(usize, &KeyEntry)> = entries.iter().enumerate().collect();
+
+        find_key_candidates_for_removal(&key_entries, attempts_map, days_threshold, protected_keys,
+                                        |candidate| candidates.push(candidate));
+
+        candidates
     }
 
-    fn get_authorized_key2() -> AuthorizedKey {
-        AuthorizedKey {
-            key_type: "ed25519".to_string(),
-            key: "AAAAC3NzaC1lZDI1NTE5AAAAIDOGSbgN43gI+oP5CebK7JsGWsMT69uymML4YHWUPI2G".to_string(),
-            id: get_random_string(),
-            row_index: 0,
+    fn get_authorized_key1() -> KeyEntry {
+        KeyEntry {
+            options: vec![],
+            key_type: "ssh-rsa".to_string(),
+            key_data: "AAAAB3NzaC1yc2EAAAADAQABAAABgQDAd6jIpyOMz50jtD+7FrKhQ3yzYjZTr0zCixTHDTZ2w2nEcrnkGqF/2L1HAiYVv1kub/GlL8po1gv7CwOE4O2F5VwtSNco84YEcl8zL7tTKJCdmOVqajvFtRmYP6vQQ8q1ffODlky7u98HkQN/Pgu+zCd1D104Tx3bpPJoFOGfn3nZm5b3zTgM2Ie2qJwyRHdvJwmtJtmf6IAG9XF1GdzPJ15U6g/7SndvfGX++KodYZzSUWsbLDxC0Vpr4nH1+C8JIWApUFXTTKCSyoSm3hmDSXrreOkmMSltVHj8SQYFNmMeMRMvKZwmqi6RMC5AXock4gFxzaxCsDtqrfc4MYb9UE/uUiSeyQ2GSjW6soq+9K/+s8nmCnzxGTuM7gwGG1Ada7qgIrLAHKdQyiDX9/wwwi7Nax8OO3+orWJjfQymoHL3/aYEhXE0c2pscAeYaB6iiw+UkvTUSJ0nun9bjR8jY3iS0DUM4jYSkKaVGl2/kOv/fZdf4I+cCuHs/0stREc=".to_string(),
+            comment: Some(get_random_string()),
         }
     }
 
-    fn get_authorized_key3() -> AuthorizedKey {
-        AuthorizedKey {
-            key_type: "rsa".to_string(),
-            key: "AAAAB3NzaC1yc2EAAAABIwAAAQEA57gP/iLw2reMq2Yqzd/GShYfK1+6YPktMkJesy5DKQGYiv8ncgR5UslTKbTcUUAtVn5Dq73T/HHXrH7n1iK8yrLCbBc8Es856OvBkSDDLA8iemZwWknTPe0zbUxV6waWub2Ynx+6L8ZeYiOUhw9w0H5pXJhUwmKNu+SDYMTAn4dBkn8sjNUFMlgZRla3lML0/HUyJSX3KskXuUJ6lT98pQ6zGhsaHRkMai7bu+Q9/4/8nFiVZ2rzYAR97fMTvmlM2sWYtvV71d9u1urg2Gbuh4k0xW6OvdScoaIM0GGU81mKWE4F3D7KKmvAGPKYyfwaqtzXAKIsu9ZSpXYE5fPIVQ==".to_string(),
-            id: get_random_string(),
-            row_index: 0,
+    fn get_authorized_key2() -> KeyEntry {
+        KeyEntry {
+            options: vec![],
+            key_type: "ssh-ed25519".to_string(),
+            key_data: "AAAAC3NzaC1lZDI1NTE5AAAAIDOGSbgN43gI+oP5CebK7JsGWsMT69uymML4YHWUPI2G".to_string(),
+            comment: Some(get_random_string()),
         }
     }
 
-    fn get_fingerprint(authorized_key: &AuthorizedKey) -> String {
-        let authorized_key_str = format!("{}", authorized_key);
-        let key = PublicKey::parse(&authorized_key_str).unwrap();
-
-        key.fingerprint()
+    fn get_authorized_key3() -> KeyEntry {
+        KeyEntry {
+            options: vec![],
+            key_type: "ssh-rsa".to_string(),
+            key_data: "AAAAB3NzaC1yc2EAAAABIwAAAQEA57gP/iLw2reMq2Yqzd/GShYfK1+6YPktMkJesy5DKQGYiv8ncgR5UslTKbTcUUAtVn5Dq73T/HHXrH7n1iK8yrLCbBc8Es856OvBkSDDLA8iemZwWknTPe0zbUxV6waWub2Ynx+6L8ZeYiOUhw9w0H5pXJhUwmKNu+SDYMTAn4dBkn8sjNUFMlgZRla3lML0/HUyJSX3KskXuUJ6lT98pQ6zGhsaHRkMai7bu+Q9/4/8nFiVZ2rzYAR97fMTvmlM2sWYtvV71d9u1urg2Gbuh4k0xW6OvdScoaIM0GGU81mKWE4F3D7KKmvAGPKYyfwaqtzXAKIsu9ZSpXYE5fPIVQ==".to_string(),
+            comment: Some(get_random_string()),
+        }
     }
-
 }
 
 #[cfg(test)]
 mod attempts_map_tests {
-    use crate::tests_common::{get_key_login_attempt, get_public_key_fingerprint, get_random_string};
+    use std::collections::HashMap;
+
+    use crate::tests_common::{get_certificate_login, get_key_login_attempt, get_random_string};
     use crate::tests_common::time::get_datetime_from_now;
-    use crate::usecases::oldkeys::get_attempts_map;
+    use crate::usecases::oldkeys::{add_certificate_attempts, get_attempts_map};
 
     #[test]
     fn fingerprint_record_should_contain_the_latest_timestamp() {
@@ -272,7 +507,7 @@ mod attempts_map_tests {
 
         let attempts = vec![attempt1, attempt2, attempt3.clone()];
 
-        let fingerprints = vec![get_public_key_fingerprint(&fingerprint)];
+        let fingerprints = vec![fingerprint.clone()];
 
         let attempts_map = get_attempts_map(&attempts, &fingerprints);
 
@@ -282,4 +517,20 @@ mod attempts_map_tests {
 
         assert_eq!(&attempt3, result_attempt);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn certificate_logins_should_be_recorded_for_ca_fingerprint() {
+        let ca_fingerprint = get_random_string();
+
+        let mut attempts_map = HashMap::new();
+        attempts_map.insert(ca_fingerprint.to_string(), get_key_login_attempt(&get_datetime_from_now(10), &ca_fingerprint));
+
+        let login = get_certificate_login(&get_datetime_from_now(1), "alice", "alice@corp", 42,
+                                          &format!("SHA256:{ca_fingerprint}"));
+
+        add_certificate_attempts(&mut attempts_map, std::slice::from_ref(&login));
+
+        assert_eq!(attempts_map.len(), 1);
+        assert_eq!(attempts_map.get(&ca_fingerprint).unwrap().timestamp, login.timestamp);
+    }
+}
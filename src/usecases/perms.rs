@@ -0,0 +1,234 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::fs::Permissions;
+use std::os::unix::fs::{chown, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+use serde::Serialize;
+
+use crate::passwd::PasswdEntry;
+
+const ROOT_UID: u32 = 0;
+
+/// Group and others write bits
+const WRITABLE_BY_OTHERS_MODE: u32 = 0o022;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionProblem {
+    NotRegularFile,
+    WrongOwner,
+    WritableByOthers,
+}
+
+impl Display for PermissionProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            PermissionProblem::NotRegularFile => write!(f, "not-regular-file"),
+            PermissionProblem::WrongOwner => write!(f, "wrong-owner"),
+            PermissionProblem::WritableByOthers => write!(f, "writable-by-others"),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct PermissionIssue {
+    pub path: PathBuf,
+
+    pub problem: PermissionProblem,
+
+    pub message: String,
+
+    pub fixed: bool,
+}
+
+impl Display for PermissionIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: [{}] {}", self.path.display(), self.problem, self.message)?;
+
+        if self.fixed {
+            write!(f, " (fixed)")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Applies sshd `StrictModes` rules to authorized_keys file and every directory up to user's home
+/// (or `/` if file is outside of home): each must be owned by root or user and must not be
+/// writable by group or others, file must be regular file.
+///
+/// With `fix` bad modes and owners are fixed, but only for paths inside user's home.
+pub fn check_permissions(authorized_keys_file_path: &Path, user: &PasswdEntry,
+                         fix: bool) -> anyhow::Result<Vec<PermissionIssue>> {
+    info!("check permissions of '{}' for user '{}'", authorized_keys_file_path.display(), user.username);
+
+    let file_path = fs::canonicalize(authorized_keys_file_path)
+        .map_err(|e| anyhow::anyhow!("unable to resolve path '{}': {}", authorized_keys_file_path.display(), e))?;
+
+    let home = fs::canonicalize(&user.home).unwrap_or_else(|_| user.home.clone());
+
+    let mut issues: Vec<PermissionIssue> = vec![];
+
+    for (index, path) in file_path.ancestors().enumerate() {
+        let metadata = fs::metadata(path)?;
+        debug!("path '{}' uid {} mode {:o}", path.display(), metadata.uid(), metadata.mode());
+
+        let fixable = fix && path.starts_with(&home);
+
+        if index == 0 && !metadata.is_file() {
+            issues.push(PermissionIssue {
+                path: path.to_path_buf(),
+                problem: PermissionProblem::NotRegularFile,
+                message: "authorized_keys isn't a regular file".to_string(),
+                fixed: false,
+            });
+        }
+
+        if metadata.uid() != ROOT_UID && metadata.uid() != user.uid {
+            let fixed = fixable && fix_owner(path, user);
+
+            issues.push(PermissionIssue {
+                path: path.to_path_buf(),
+                problem: PermissionProblem::WrongOwner,
+                message: format!("owner uid {} isn't root or '{}' ({})", metadata.uid(), user.username, user.uid),
+                fixed,
+            });
+        }
+
+        let mode = metadata.mode() & 0o7777;
+
+        if mode & WRITABLE_BY_OTHERS_MODE != 0 {
+            let fixed = fixable && fix_mode(path, mode & !WRITABLE_BY_OTHERS_MODE);
+
+            issues.push(PermissionIssue {
+                path: path.to_path_buf(),
+                problem: PermissionProblem::WritableByOthers,
+                message: format!("mode {mode:o} allows write for group or others"),
+                fixed,
+            });
+        }
+
+        if path == home {
+            break
+        }
+    }
+
+    info!("permission issues: {}", issues.len());
+
+    Ok(issues)
+}
+
+fn fix_owner(path: &Path, user: &PasswdEntry) -> bool {
+    match chown(path, Some(user.uid), None) {
+        Ok(_) => {
+            info!("owner of '{}' was changed to '{}'", path.display(), user.username);
+            true
+        }
+        Err(e) => {
+            warn!("unable to change owner of '{}': {}", path.display(), e);
+            false
+        }
+    }
+}
+
+fn fix_mode(path: &Path, mode: u32) -> bool {
+    match fs::set_permissions(path, Permissions::from_mode(mode)) {
+        Ok(_) => {
+            info!("mode of '{}' was changed to {mode:o}", path.display());
+            true
+        }
+        Err(e) => {
+            warn!("unable to change mode of '{}': {}", path.display(), e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod perms_tests {
+    use std::fs;
+    use std::fs::Permissions;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    use crate::passwd::PasswdEntry;
+    use crate::tests_common::get_temp_dir;
+    use crate::usecases::perms::{check_permissions, PermissionProblem};
+
+    #[test]
+    fn no_issues_for_strict_permissions() {
+        let home = get_temp_dir();
+        let file_path = create_authorized_keys_file(home.path(), 0o700, 0o600);
+
+        let issues = check_permissions(&file_path, &get_user(home.path()), false).unwrap();
+
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn report_and_fix_writable_paths() {
+        let home = get_temp_dir();
+        let file_path = create_authorized_keys_file(home.path(), 0o775, 0o666);
+
+        let issues = check_permissions(&file_path, &get_user(home.path()), false).unwrap();
+
+        let problems: Vec<(String, PermissionProblem, bool)> = issues.iter()
+            .map(|i| (i.path.file_name().unwrap().to_string_lossy().to_string(), i.problem.clone(), i.fixed))
+            .collect();
+
+        assert_eq!(problems, vec![
+            ("authorized_keys".to_string(), PermissionProblem::WritableByOthers, false),
+            (".ssh".to_string(), PermissionProblem::WritableByOthers, false),
+        ]);
+
+        let issues = check_permissions(&file_path, &get_user(home.path()), true).unwrap();
+
+        assert!(issues.iter().all(|i| i.fixed));
+        assert_eq!(fs::metadata(&file_path).unwrap().mode() & 0o777, 0o644);
+        assert_eq!(fs::metadata(home.path().join(".ssh")).unwrap().mode() & 0o777, 0o755);
+
+        assert!(check_permissions(&file_path, &get_user(home.path()), false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn authorized_keys_should_be_regular_file() {
+        let home = get_temp_dir();
+        let file_path = create_authorized_keys_file(home.path(), 0o700, 0o600);
+        fs::remove_file(&file_path).unwrap();
+        fs::create_dir(&file_path).unwrap();
+        fs::set_permissions(&file_path, Permissions::from_mode(0o700)).unwrap();
+
+        let issues = check_permissions(&file_path, &get_user(home.path()), false).unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].problem, PermissionProblem::NotRegularFile);
+    }
+
+    fn create_authorized_keys_file(home: &Path, ssh_dir_mode: u32, file_mode: u32) -> PathBuf {
+        fs::set_permissions(home, Permissions::from_mode(0o700)).unwrap();
+
+        let ssh_dir = home.join(".ssh");
+        fs::create_dir(&ssh_dir).unwrap();
+        fs::set_permissions(&ssh_dir, Permissions::from_mode(ssh_dir_mode)).unwrap();
+
+        let file_path = ssh_dir.join("authorized_keys");
+        fs::write(&file_path, "").unwrap();
+        fs::set_permissions(&file_path, Permissions::from_mode(file_mode)).unwrap();
+
+        file_path
+    }
+
+    fn get_user(home: &Path) -> PasswdEntry {
+        let metadata = fs::metadata(home).unwrap();
+
+        PasswdEntry {
+            username: "alice".to_string(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            home: home.to_path_buf(),
+            shell: "/bin/sh".to_string(),
+        }
+    }
+}
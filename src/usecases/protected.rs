@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::Path;
+
+use log::{debug, info};
+use regex::Regex;
+
+use crate::keysfile::entry::KeyEntry;
+
+const COMMENT_PREFIX: &str = "#";
+
+const SHA256_PREFIX: &str = "SHA256:";
+
+const MD5_PREFIX: &str = "MD5:";
+
+/// Keys which must never be flagged for removal (break-glass, automation, etc.).
+///
+/// Entries are matched either by fingerprint (SHA256 or MD5) or by key id (comment) pattern.
+#[derive(Clone, Default)]
+pub struct ProtectedKeys {
+    fingerprints: Vec<String>,
+
+    /// Lowercase, without `MD5:` prefix
+    md5_fingerprints: Vec<String>,
+
+    id_patterns: Vec<Regex>,
+}
+
+impl ProtectedKeys {
+    pub fn is_protected(&self, fingerprint: &str, key_id: &str) -> bool {
+        let fingerprint = fingerprint.trim_start_matches(SHA256_PREFIX);
+
+        self.fingerprints.iter().any(|f| f == fingerprint) ||
+            self.id_patterns.iter().any(|p| p.is_match(key_id))
+    }
+
+    /// Matches entry by SHA256 and MD5 fingerprints and by key id.
+    pub fn is_entry_protected(&self, entry: &KeyEntry) -> bool {
+        self.is_protected(&entry.get_fingerprint(), entry.get_comment()) ||
+            self.md5_fingerprints.contains(&entry.get_md5_fingerprint())
+    }
+
+    pub fn extend(&mut self, other: ProtectedKeys) {
+        self.fingerprints.extend(other.fingerprints);
+        self.md5_fingerprints.extend(other.md5_fingerprints);
+        self.id_patterns.extend(other.id_patterns);
+    }
+
+    pub fn count(&self) -> usize {
+        self.fingerprints.len() + self.md5_fingerprints.len() + self.id_patterns.len()
+    }
+}
+
+/// Loads protected keys from allowlist file.
+///
+/// One entry per line:
+/// - `SHA256:...` or `MD5:...` - key fingerprint
+/// - anything else - key id (comment) pattern, `*` matches any characters
+///
+/// Empty lines and lines starting with `#` are ignored.
+pub fn get_protected_keys_from_file(file_path: &Path) -> anyhow::Result<ProtectedKeys> {
+    info!("loading protected keys from '{}'", file_path.display());
+
+    let content = fs::read_to_string(file_path)?;
+
+    get_protected_keys_from_str(&content)
+}
+
+pub fn get_protected_keys_from_str(content: &str) -> anyhow::Result<ProtectedKeys> {
+    let mut protected_keys = ProtectedKeys::default();
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+            continue
+        }
+
+        if let Some(fingerprint) = line.strip_prefix(SHA256_PREFIX) {
+            debug!("protected fingerprint '{fingerprint}'");
+            protected_keys.fingerprints.push(fingerprint.to_string());
+
+        } else if let Some(fingerprint) = line.strip_prefix(MD5_PREFIX) {
+            debug!("protected md5 fingerprint '{fingerprint}'");
+            protected_keys.md5_fingerprints.push(fingerprint.to_lowercase());
+
+        } else {
+            debug!("protected key id pattern '{line}'");
+            protected_keys.id_patterns.push(get_pattern_regex(line)?);
+        }
+    }
+
+    info!("protected keys loaded: {}", protected_keys.count());
+
+    Ok(protected_keys)
+}
+
+fn get_pattern_regex(pattern: &str) -> anyhow::Result<Regex> {
+    let escaped_pattern = regex::escape(pattern).replace("\\*", ".*");
+    Ok(Regex::new(&format!("^{escaped_pattern}$"))?)
+}
+
+#[cfg(test)]
+mod protected_keys_tests {
+    use crate::keysfile::entry::KeyEntry;
+    use crate::tests_common::{KEY1, KEY2};
+    use crate::usecases::protected::get_protected_keys_from_str;
+
+    #[test]
+    fn ignore_comments_and_empty_lines() {
+        let content = "# break-glass keys\n\n   \nSHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8\n";
+
+        let protected_keys = get_protected_keys_from_str(content).unwrap();
+
+        assert_eq!(protected_keys.count(), 1);
+    }
+
+    #[test]
+    fn match_by_fingerprint_with_or_without_prefix() {
+        let content = "SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8";
+
+        let protected_keys = get_protected_keys_from_str(content).unwrap();
+
+        assert!(protected_keys.is_protected("oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8", ""));
+        assert!(protected_keys.is_protected("SHA256:oCUpgneXmI2DtgLvkSGtzVEnrb0gE02N7pCNB3QJmB8", ""));
+        assert!(!protected_keys.is_protected("SNiDjsysmCYSk8fmtxtbHbMaQDDogv7P+IY6/mQKz9U", ""));
+    }
+
+    #[test]
+    fn match_entry_by_md5_fingerprint() {
+        let protected_keys = get_protected_keys_from_str("MD5:6E:F1:81:2F:36:AA:49:34:F4:7D:68:3A:36:7B:D6:52").unwrap();
+
+        assert_eq!(protected_keys.count(), 1);
+        assert!(protected_keys.is_entry_protected(&KeyEntry::parse(KEY1).unwrap()));
+        assert!(!protected_keys.is_entry_protected(&KeyEntry::parse(KEY2).unwrap()));
+    }
+
+    #[test]
+    fn match_by_key_id_pattern() {
+        let content = "test@ansible\n*@backup.local";
+
+        let protected_keys = get_protected_keys_from_str(content).unwrap();
+
+        assert!(protected_keys.is_protected("", "test@ansible"));
+        assert!(protected_keys.is_protected("", "restic@backup.local"));
+        assert!(!protected_keys.is_protected("", "test@ansible.com"));
+        assert!(!protected_keys.is_protected("", "mr.deployer@gmail.com"));
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use authorized_keys::authorizedkeys::AuthorizedKey;
+use serde::Serialize;
+
+use crate::usecases::fingerprints::FingerprintedKey;
+use crate::usecases::oldkeys::{RemovalCandidate, RemovalReason};
+
+/// Key with stable set of columns for csv and tsv output, columns don't depend on command options.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct KeyRecord {
+    /// Owner of authorized_keys file
+    pub user: String,
+
+    pub file: PathBuf,
+
+    /// Line index in file, starts from 0
+    pub row: usize,
+
+    #[serde(rename = "type")]
+    pub key_type: String,
+
+    pub bits: Option<usize>,
+
+    /// `SHA256:...`
+    pub fingerprint: Option<String>,
+
+    pub comment: String,
+
+    pub last_seen: Option<String>,
+
+    pub days_since: Option<i64>,
+
+    pub reason: Option<RemovalReason>,
+}
+
+impl Display for KeyRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}:{} {} {} {}", self.user, self.file.display(), self.row, self.key_type,
+               self.fingerprint.as_deref().unwrap_or("-"), self.comment)?;
+
+        if let Some(reason) = &self.reason {
+            write!(f, " {reason}")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn get_key_records(username: &str, file: &Path, keys: &[FingerprintedKey<AuthorizedKey>]) -> Vec<KeyRecord> {
+    keys.iter().map(|key| get_key_record(username, file, &key.key, key.fingerprint.clone(), key.bits)).collect()
+}
+
+pub fn get_candidate_records(username: &str, file: &Path,
+                             candidates: &[FingerprintedKey<RemovalCandidate>]) -> Vec<KeyRecord> {
+    candidates.iter().map(|candidate| KeyRecord {
+        last_seen: candidate.key.last_seen.clone(),
+        days_since: candidate.key.days_since,
+        reason: Some(candidate.key.reason.clone()),
+        ..get_key_record(username, file, &candidate.key.key, candidate.fingerprint.clone(), candidate.bits)
+    }).collect()
+}
+
+fn get_key_record(username: &str, file: &Path, key: &AuthorizedKey,
+                  fingerprint: Option<String>, bits: Option<usize>) -> KeyRecord {
+    KeyRecord {
+        user: username.to_string(),
+        file: file.to_path_buf(),
+        row: key.row_index,
+        key_type: key.key_type.to_string(),
+        bits,
+        fingerprint,
+        comment: key.id.to_string(),
+        last_seen: None,
+        days_since: None,
+        reason: None,
+    }
+}
+
+#[cfg(test)]
+mod records_tests {
+    use std::path::Path;
+
+    use authorized_keys::authorizedkeys::AuthorizedKey;
+
+    use crate::usecases::fingerprints::FingerprintedKey;
+    use crate::usecases::oldkeys::{RemovalCandidate, RemovalReason};
+    use crate::usecases::records::get_candidate_records;
+
+    #[test]
+    fn record_should_contain_key_and_usage_columns() {
+        let candidate = RemovalCandidate {
+            key: AuthorizedKey {
+                key_type: "ssh-ed25519".to_string(),
+                key: "AAAAC3NzaC1lZDI1NTE5AAAAIDOGSbgN43gI+oP5CebK7JsGWsMT69uymML4YHWUPI2G".to_string(),
+                id: "John \"JD\" Doe, ops".to_string(),
+                row_index: 3,
+            },
+            reason: RemovalReason::Stale,
+            last_seen: Some("2023-03-17 07:41:50".to_string()),
+            days_since: Some(45),
+        };
+
+        let records = get_candidate_records("alice", Path::new("/home/alice/.ssh/authorized_keys"),
+                                            &[FingerprintedKey::new(candidate, None)]);
+
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.user, "alice");
+        assert_eq!(record.row, 3);
+        assert_eq!(record.bits, Some(256));
+        assert_eq!(record.fingerprint, Some("SHA256:FnxtlBYHf7fHuR5+lrb+W9rDsY3kBTfbKMEmkBBHRJE".to_string()));
+        assert_eq!(record.comment, "John \"JD\" Doe, ops");
+        assert_eq!(record.days_since, Some(45));
+        assert_eq!(record.reason, Some(RemovalReason::Stale));
+    }
+}
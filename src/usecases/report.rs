@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use log::info;
+use serde::Serialize;
+
+use crate::authlog::LogCoverage;
+use crate::usecases::audit::{AuditFinding, AuditRule};
+use crate::usecases::oldkeys::RemovalReason;
+use crate::usecases::records::KeyRecord;
+use crate::usecases::usage::format_timestamp;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct ReportSummary {
+    pub keys: usize,
+
+    pub stale: usize,
+
+    pub not_seen: usize,
+
+    /// Keys which would be stale or never seen, but they are protected
+    pub protected: usize,
+
+    pub weak: usize,
+
+    pub duplicates: usize,
+}
+
+/// Audit report for authorized_keys file: keys for removal, weak keys and duplicates.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct KeysReport {
+    pub host: String,
+
+    pub file: PathBuf,
+
+    pub generated_at: String,
+
+    pub older_than_days: usize,
+
+    /// Empty if auth logs are unavailable
+    pub log_coverage: Option<LogCoverage>,
+
+    pub summary: ReportSummary,
+
+    pub stale: Vec<KeyRecord>,
+
+    pub not_seen: Vec<KeyRecord>,
+
+    pub weak: Vec<AuditFinding>,
+
+    pub duplicates: Vec<AuditFinding>,
+}
+
+/// `candidates` are removal candidates including protected keys, `findings` are audit findings for the same file.
+pub fn get_keys_report(host: &str, file: &Path, older_than_days: usize, log_coverage: Option<LogCoverage>,
+                       keys: usize, candidates: &[KeyRecord], findings: &[AuditFinding]) -> KeysReport {
+    info!("get report for authorized_keys file '{}'", file.display());
+
+    let get_candidates = |reason: RemovalReason| -> Vec<KeyRecord> {
+        candidates.iter().filter(|candidate| candidate.reason.as_ref() == Some(&reason)).cloned().collect()
+    };
+
+    let get_findings = |rule: AuditRule| -> Vec<AuditFinding> {
+        findings.iter().filter(|finding| finding.rule == rule).cloned().collect()
+    };
+
+    let stale = get_candidates(RemovalReason::Stale);
+    let not_seen = get_candidates(RemovalReason::NotSeen);
+    let weak = get_findings(AuditRule::WeakKey);
+    let duplicates = get_findings(AuditRule::DuplicateKey);
+
+    KeysReport {
+        host: host.to_string(),
+        file: file.to_path_buf(),
+        generated_at: format_timestamp(&Local::now().naive_local()),
+        older_than_days,
+        log_coverage,
+        summary: ReportSummary {
+            keys,
+            stale: stale.len(),
+            not_seen: not_seen.len(),
+            protected: get_candidates(RemovalReason::Protected).len(),
+            weak: weak.len(),
+            duplicates: duplicates.len(),
+        },
+        stale,
+        not_seen,
+        weak,
+        duplicates,
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::usecases::audit::{AuditFinding, AuditRule};
+    use crate::usecases::oldkeys::RemovalReason;
+    use crate::usecases::records::KeyRecord;
+    use crate::usecases::report::{get_keys_report, ReportSummary};
+
+    #[test]
+    fn group_candidates_and_findings() {
+        let candidates = vec![
+            get_record(0, RemovalReason::Stale),
+            get_record(1, RemovalReason::NotSeen),
+            get_record(2, RemovalReason::NotSeen),
+            get_record(3, RemovalReason::Protected),
+        ];
+
+        let findings = vec![
+            get_finding(AuditRule::WeakKey),
+            get_finding(AuditRule::InvalidComment),
+            get_finding(AuditRule::DuplicateKey),
+        ];
+
+        let report = get_keys_report("host", Path::new("authorized_keys"), 31, None, 6, &candidates, &findings);
+
+        assert_eq!(report.summary, ReportSummary { keys: 6, stale: 1, not_seen: 2, protected: 1, weak: 1, duplicates: 1 });
+        assert_eq!(report.stale[0].row, 0);
+        assert_eq!(report.not_seen.iter().map(|record| record.row).collect::<Vec<usize>>(), vec![1, 2]);
+        assert_eq!(report.duplicates[0].rule, AuditRule::DuplicateKey);
+    }
+
+    fn get_record(row: usize, reason: RemovalReason) -> KeyRecord {
+        KeyRecord {
+            user: "alice".to_string(),
+            file: PathBuf::from("authorized_keys"),
+            row,
+            key_type: "ssh-ed25519".to_string(),
+            bits: Some(256),
+            fingerprint: None,
+            comment: "alice@laptop".to_string(),
+            last_seen: None,
+            days_since: None,
+            reason: Some(reason),
+        }
+    }
+
+    fn get_finding(rule: AuditRule) -> AuditFinding {
+        AuditFinding {
+            rule,
+            file: None,
+            line: 1,
+            fingerprint: None,
+            key_id: "alice@laptop".to_string(),
+            message: String::new(),
+        }
+    }
+}
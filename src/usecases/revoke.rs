@@ -0,0 +1,402 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::files::write_file_atomically;
+use crate::keysfile::entry::KeyEntry;
+use crate::keysfile::KeysFile;
+use crate::krl::Krl;
+use crate::usecases::find::KeyQuery;
+use crate::usecases::revokedkeys::RevokedKeysFile;
+use crate::usecases::users::UserKeysFile;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SHA256_LENGTH: usize = 32;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RevokeAction {
+    /// Key was removed from authorized_keys file
+    Removed,
+
+    /// Key was added to revoked keys file
+    Revoked,
+
+    AlreadyRevoked,
+}
+
+impl Display for RevokeAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            RevokeAction::Removed => write!(f, "removed"),
+            RevokeAction::Revoked => write!(f, "revoked"),
+            RevokeAction::AlreadyRevoked => write!(f, "already-revoked"),
+        }
+    }
+}
+
+/// Audit record of revoke command
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct RevokeRecord {
+    pub timestamp: String,
+
+    pub action: RevokeAction,
+
+    /// Owner of authorized_keys file, empty for revoked keys file
+    pub username: Option<String>,
+
+    pub file: PathBuf,
+
+    /// Line index in authorized_keys file before removal, starts from 0
+    pub row_index: Option<usize>,
+
+    pub fingerprint: String,
+
+    pub message: String,
+}
+
+impl Display for RevokeRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}] ", self.timestamp, self.action)?;
+
+        match &self.username {
+            Some(username) if !username.is_empty() => write!(f, "{username} {}", self.file.display())?,
+            _ => write!(f, "{}", self.file.display())?
+        }
+
+        if let Some(row_index) = self.row_index {
+            write!(f, ":{row_index}")?;
+        }
+
+        write!(f, " {}", self.fingerprint)?;
+
+        if !self.message.is_empty() {
+            write!(f, " {}", self.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes key from authorized_keys files and adds it to revoked keys file (sshd `RevokedKeys`).
+///
+/// Every entry of the key is removed, managed block markers don't apply: compromised key must not stay
+/// in unmanaged entries. Revoked keys file can be plain (public keys) or binary KRL, fingerprint of key
+/// which isn't in any authorized_keys file can be added to KRL only.
+///
+/// New content of all files is prepared before any of them is written, so error in one file leaves
+/// all files unchanged. Revoked keys file is written before authorized_keys files.
+/// With `dry_run` files aren't changed, records show what would be changed.
+pub fn revoke_key(query: &KeyQuery, files: &[UserKeysFile], revoked_keys_file_path: Option<&Path>,
+                  dry_run: bool) -> anyhow::Result<Vec<RevokeRecord>> {
+    info!("revoke key by query {:?}", query);
+
+    if let KeyQuery::Comment(_) = query {
+        anyhow::bail!("key id can't be used to revoke key, use fingerprint or public key file")
+    }
+
+    let mut records: Vec<RevokeRecord> = vec![];
+
+    let mut revoked_entry: Option<KeyEntry> = match query {
+        KeyQuery::Key(entry) => Some(entry.clone()),
+        _ => None
+    };
+
+    // new content of authorized_keys files, file shared by several users is changed once
+    let mut changed_keys_files: Vec<(&Path, Vec<u8>)> = vec![];
+
+    for file in files {
+        if changed_keys_files.iter().any(|(path, _)| *path == file.path) {
+            continue
+        }
+
+        let content = fs::read_to_string(&file.path)
+            .map_err(|e| anyhow::anyhow!("unable to read file '{}': {}", file.path.display(), e))?;
+
+        let mut keys_file = KeysFile::parse(&content);
+
+        let mut removed_lines: Vec<usize> = vec![];
+
+        for (index, line) in keys_file.lines.iter().enumerate() {
+            let entry = match line.get_entry() {
+                Some(entry) if query.is_match(entry) => entry,
+                _ => continue
+            };
+
+            if revoked_entry.is_none() {
+                revoked_entry = Some(entry.clone());
+            }
+
+            removed_lines.push(index);
+
+            records.push(get_record(RevokeAction::Removed, Some(file.username.to_string()), &file.path, Some(index),
+                                    &entry.get_fingerprint(), line.text.trim().to_string()));
+        }
+
+        if !removed_lines.is_empty() {
+            for removed_line in removed_lines.iter().rev() {
+                keys_file.lines.remove(*removed_line);
+            }
+
+            changed_keys_files.push((&file.path, keys_file.to_string().into_bytes()));
+        }
+    }
+
+    // new content of all changed files, revoked keys file goes first
+    let mut changes: Vec<(PathBuf, Vec<u8>)> = vec![];
+
+    match (revoked_keys_file_path, &revoked_entry) {
+        (Some(path), Some(entry)) => {
+            let mut revoked_keys_file = RevokedKeysFile::load(path)?;
+
+            let action = if revoked_keys_file.revoke(entry) {
+                changes.push((path.to_path_buf(), revoked_keys_file.to_bytes()));
+                RevokeAction::Revoked
+
+            } else {
+                RevokeAction::AlreadyRevoked
+            };
+
+            records.push(get_record(action, None, path, None, &entry.get_fingerprint(), entry.get_key()));
+        }
+        (Some(path), None) => {
+            if let RevokedKeysFile::Plain(content) = RevokedKeysFile::load(path)? {
+                if !content.trim().is_empty() {
+                    anyhow::bail!("key wasn't found in authorized_keys files, use public key file to add it \
+                                   to plain revoked keys file '{}'", path.display())
+                }
+            }
+
+            let (record, krl_content) = get_krl_change(path, query)?;
+
+            if let Some(krl_content) = krl_content {
+                changes.push((path.to_path_buf(), krl_content));
+            }
+
+            records.push(record);
+        }
+        (None, Some(_)) => warn!("revoked keys file isn't set, key will be accepted if it's added again"),
+        (None, None) => anyhow::bail!("key wasn't found in authorized_keys files and revoked keys file isn't set")
+    }
+
+    changes.extend(changed_keys_files.into_iter().map(|(path, content)| (path.to_path_buf(), content)));
+
+    if !dry_run {
+        for (path, content) in changes {
+            write_file_atomically(&path, &content)?;
+        }
+    }
+
+    records.iter().for_each(|record| info!("{record}"));
+
+    Ok(records)
+}
+
+/// Adds key or SHA256 fingerprint to KRL file, authorized_keys files aren't changed.
+///
+/// KRL file is created if it doesn't exist.
+pub fn add_to_krl_file(path: &Path, query: &KeyQuery, dry_run: bool) -> anyhow::Result<RevokeRecord> {
+    info!("add key to KRL file '{}' by query {:?}", path.display(), query);
+
+    let (record, krl_content) = get_krl_change(path, query)?;
+
+    if let Some(krl_content) = krl_content {
+        if !dry_run {
+            write_file_atomically(path, &krl_content)?;
+        }
+    }
+
+    Ok(record)
+}
+
+/// Record and new KRL content, content is empty if key is already revoked.
+fn get_krl_change(path: &Path, query: &KeyQuery) -> anyhow::Result<(RevokeRecord, Option<Vec<u8>>)> {
+    let mut krl = match RevokedKeysFile::load(path)? {
+        RevokedKeysFile::Krl(krl) => krl,
+        RevokedKeysFile::Plain(content) if content.trim().is_empty() => Krl::default(),
+        RevokedKeysFile::Plain(_) => anyhow::bail!("file '{}' isn't KRL, use revoke command for plain revoked keys file",
+                                                   path.display())
+    };
+
+    let (revoked, fingerprint, message) = match query {
+        KeyQuery::Key(entry) => {
+            let revoked = !krl.is_revoked(&entry.get_blob());
+            krl.revoke_key(&entry.get_blob());
+            (revoked, entry.get_fingerprint(), entry.get_key())
+        }
+        KeyQuery::Sha256(fingerprint) => {
+            let hash = base64::decode_config(fingerprint, base64::STANDARD_NO_PAD)
+                .ok().filter(|hash| hash.len() == SHA256_LENGTH)
+                .ok_or_else(|| anyhow::anyhow!("invalid SHA256 fingerprint '{fingerprint}'"))?;
+
+            let revoked = !krl.sha256_fingerprints.contains(&hash);
+            krl.revoke_sha256_fingerprint(&hash);
+            (revoked, fingerprint.to_string(), String::new())
+        }
+        _ => anyhow::bail!("only SHA256 fingerprint or public key file can be added to KRL")
+    };
+
+    if !revoked {
+        return Ok((get_record(RevokeAction::AlreadyRevoked, None, path, None, &fingerprint, message), None))
+    }
+
+    Ok((get_record(RevokeAction::Revoked, None, path, None, &fingerprint, message), Some(krl.to_bytes())))
+}
+
+fn get_record(action: RevokeAction, username: Option<String>, file: &Path, row_index: Option<usize>,
+              fingerprint: &str, message: String) -> RevokeRecord {
+    RevokeRecord {
+        timestamp: Local::now().format(TIMESTAMP_FORMAT).to_string(),
+        action,
+        username,
+        file: file.to_path_buf(),
+        row_index,
+        fingerprint: format!("SHA256:{fingerprint}"),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod revoke_tests {
+    use std::fs;
+
+    use crate::keysfile::entry::KeyEntry;
+    use crate::tests_common::{create_keys_file, get_temp_dir, KEY1, KEY1_FINGERPRINT, KEY2};
+    use crate::usecases::find::KeyQuery;
+    use crate::krl::Krl;
+    use crate::usecases::revoke::{add_to_krl_file, revoke_key, RevokeAction};
+    use crate::usecases::users::UserKeysFile;
+
+    #[test]
+    fn remove_key_from_all_files_and_add_to_revoked_keys() {
+        let dir = get_temp_dir();
+        let files = vec![
+            create_keys_file(dir.path(), "alice", &format!("{KEY2} bob@desktop\nno-pty {KEY1} alice@laptop\n")),
+            create_keys_file(dir.path(), "bob", &format!("# BEGIN akt unmanaged\n{KEY1} alice@laptop\n# END akt unmanaged\n")),
+            create_keys_file(dir.path(), "carol", &format!("{KEY2} bob@desktop\n")),
+        ];
+
+        let revoked_keys_file_path = dir.path().join("revoked_keys");
+        fs::write(&revoked_keys_file_path, format!("{KEY2} old")).unwrap();
+
+        let query = KeyQuery::parse(KEY1_FINGERPRINT).unwrap();
+
+        let records = revoke_key(&query, &files, Some(&revoked_keys_file_path), false).unwrap();
+
+        let actions: Vec<(RevokeAction, Option<usize>)> = records.iter()
+            .map(|r| (r.action.clone(), r.row_index)).collect();
+
+        assert_eq!(actions, vec![
+            (RevokeAction::Removed, Some(1)),
+            (RevokeAction::Removed, Some(1)),
+            (RevokeAction::Revoked, None),
+        ]);
+
+        assert_eq!(fs::read_to_string(&files[0].path).unwrap(), format!("{KEY2} bob@desktop\n"));
+        assert_eq!(fs::read_to_string(&files[1].path).unwrap(), "# BEGIN akt unmanaged\n# END akt unmanaged\n");
+        assert_eq!(fs::read_to_string(&revoked_keys_file_path).unwrap(), format!("{KEY2} old\n{KEY1} alice@laptop\n"));
+
+        // key isn't in authorized_keys files anymore, so it's taken from public key
+        let query = KeyQuery::Key(KeyEntry::parse(KEY1).unwrap());
+
+        let records = revoke_key(&query, &files, Some(&revoked_keys_file_path), false).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].action, RevokeAction::AlreadyRevoked);
+    }
+
+    #[test]
+    fn dry_run_should_not_change_files() {
+        let dir = get_temp_dir();
+        let content = format!("{KEY1} alice@laptop\n");
+        let files = vec![create_keys_file(dir.path(), "alice", &content)];
+
+        let revoked_keys_file_path = dir.path().join("revoked_keys");
+
+        let records = revoke_key(&KeyQuery::parse(KEY1_FINGERPRINT).unwrap(), &files,
+                                 Some(&revoked_keys_file_path), true).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(fs::read_to_string(&files[0].path).unwrap(), content);
+        assert!(!revoked_keys_file_path.exists());
+    }
+
+    #[test]
+    fn unknown_key_and_key_id_should_be_rejected() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice", &format!("{KEY2} bob@desktop\n"))];
+
+        assert!(revoke_key(&KeyQuery::parse(KEY1_FINGERPRINT).unwrap(), &files, None, false).is_err());
+        assert!(revoke_key(&KeyQuery::parse("bob").unwrap(), &files, None, false).is_err());
+    }
+
+    #[test]
+    fn fingerprint_of_unknown_key_should_be_added_to_krl() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice", &format!("{KEY2} bob@desktop\n"))];
+
+        let path = dir.path().join("revoked_keys");
+
+        let records = revoke_key(&KeyQuery::parse(KEY1_FINGERPRINT).unwrap(), &files, Some(&path), false).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].action, RevokeAction::Revoked);
+        assert_eq!(Krl::parse(&fs::read(&path).unwrap()).unwrap().sha256_fingerprints.len(), 1);
+    }
+
+    #[test]
+    fn files_should_not_be_changed_if_any_file_is_invalid() {
+        let dir = get_temp_dir();
+        let content = format!("{KEY1} alice@laptop\n");
+        let mut files = vec![create_keys_file(dir.path(), "alice", &content)];
+
+        let query = KeyQuery::parse(KEY1_FINGERPRINT).unwrap();
+
+        // directory can't be read as revoked keys file
+        assert!(revoke_key(&query, &files, Some(dir.path()), false).is_err());
+        assert_eq!(fs::read_to_string(&files[0].path).unwrap(), content);
+
+        let revoked_keys_file_path = dir.path().join("revoked_keys");
+
+        files.push(UserKeysFile { username: "bob".to_string(), path: dir.path().join("missing") });
+
+        assert!(revoke_key(&query, &files, Some(&revoked_keys_file_path), false).is_err());
+
+        assert_eq!(fs::read_to_string(&files[0].path).unwrap(), content);
+        assert!(!revoked_keys_file_path.exists());
+    }
+
+    #[test]
+    fn file_shared_by_users_should_be_changed_once() {
+        let dir = get_temp_dir();
+        let alice_file = create_keys_file(dir.path(), "alice", &format!("{KEY1} alice@laptop\n{KEY2} bob@desktop\n"));
+        let files = vec![alice_file.clone(), UserKeysFile { username: "bob".to_string(), ..alice_file }];
+
+        let records = revoke_key(&KeyQuery::parse(KEY1_FINGERPRINT).unwrap(), &files, None, false).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(fs::read_to_string(&files[0].path).unwrap(), format!("{KEY2} bob@desktop\n"));
+    }
+
+    #[test]
+    fn add_fingerprint_to_new_krl_file() {
+        let dir = get_temp_dir();
+        let path = dir.path().join("revoked_keys");
+
+        let query = KeyQuery::parse(KEY1_FINGERPRINT).unwrap();
+
+        assert_eq!(add_to_krl_file(&path, &query, false).unwrap().action, RevokeAction::Revoked);
+        assert_eq!(add_to_krl_file(&path, &query, false).unwrap().action, RevokeAction::AlreadyRevoked);
+
+        let krl = Krl::parse(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(krl.sha256_fingerprints.len(), 1);
+
+        fs::write(&path, format!("{KEY2}\n")).unwrap();
+        assert!(add_to_krl_file(&path, &query, false).is_err());
+    }
+}
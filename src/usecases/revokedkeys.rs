@@ -0,0 +1,289 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::keysfile::entry::KeyEntry;
+use crate::keysfile::KeysFile;
+use crate::krl::Krl;
+use crate::usecases::users::{read_user_keys_files, UserKeysFile};
+
+/// Revoked keys file (sshd `RevokedKeys`): plain file with public keys or binary KRL.
+pub enum RevokedKeysFile {
+    Plain(String),
+    Krl(Krl),
+}
+
+impl RevokedKeysFile {
+    /// Missing file is loaded as empty plain file.
+    pub fn load(path: &Path) -> anyhow::Result<RevokedKeysFile> {
+        if !path.exists() {
+            debug!("revoked keys file '{}' doesn't exist", path.display());
+            return Ok(RevokedKeysFile::Plain(String::new()))
+        }
+
+        let content = fs::read(path)?;
+
+        if Krl::is_krl(&content) {
+            debug!("revoked keys file '{}' is KRL", path.display());
+
+            let krl = Krl::parse(&content)
+                .map_err(|e| anyhow::anyhow!("unable to parse KRL file '{}': {}", path.display(), e))?;
+
+            return Ok(RevokedKeysFile::Krl(krl))
+        }
+
+        Ok(RevokedKeysFile::Plain(String::from_utf8(content)?))
+    }
+
+    pub fn is_revoked(&self, entry: &KeyEntry) -> bool {
+        match self {
+            RevokedKeysFile::Plain(content) => {
+                let fingerprint = entry.get_fingerprint();
+                get_plain_entries(content).iter().any(|e| e.get_fingerprint() == fingerprint)
+            }
+            RevokedKeysFile::Krl(krl) => krl.is_revoked(&entry.get_blob())
+        }
+    }
+
+    /// Returns `false` if key is already revoked.
+    pub fn revoke(&mut self, entry: &KeyEntry) -> bool {
+        if self.is_revoked(entry) {
+            return false
+        }
+
+        match self {
+            RevokedKeysFile::Plain(content) => {
+                if !content.is_empty() && !content.ends_with('\n') {
+                    content.push('\n');
+                }
+
+                let revoked_entry = KeyEntry { options: vec![], ..entry.clone() };
+                content.push_str(&format!("{revoked_entry}\n"));
+            }
+            RevokedKeysFile::Krl(krl) => krl.revoke_key(&entry.get_blob())
+        }
+
+        true
+    }
+
+    pub fn get_revoked_keys(&self) -> Vec<RevokedKey> {
+        match self {
+            RevokedKeysFile::Plain(content) => get_plain_entries(content).iter()
+                .map(|entry| RevokedKey {
+                    kind: RevocationKind::Key,
+                    fingerprint: format!("SHA256:{}", entry.get_fingerprint()),
+                    key: entry.to_string(),
+                })
+                .collect(),
+
+            RevokedKeysFile::Krl(krl) => {
+                if !krl.certificate_sections.is_empty() {
+                    warn!("KRL has {} certificate sections, they aren't listed", krl.certificate_sections.len());
+                }
+
+                let keys = krl.explicit_keys.iter().map(|blob| RevokedKey {
+                    kind: RevocationKind::Key,
+                    fingerprint: format!("SHA256:{}", encode_hash(&Sha256::digest(blob))),
+                    key: format!("{} {}", get_key_type(blob), base64::encode(blob)),
+                });
+
+                let sha1_hashes = krl.sha1_fingerprints.iter().map(|hash| RevokedKey {
+                    kind: RevocationKind::Sha1,
+                    fingerprint: format!("SHA1:{}", encode_hash(hash)),
+                    key: String::new(),
+                });
+
+                let sha256_hashes = krl.sha256_fingerprints.iter().map(|hash| RevokedKey {
+                    kind: RevocationKind::Sha256,
+                    fingerprint: format!("SHA256:{}", encode_hash(hash)),
+                    key: String::new(),
+                });
+
+                keys.chain(sha1_hashes).chain(sha256_hashes).collect()
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            RevokedKeysFile::Plain(content) => content.as_bytes().to_vec(),
+            RevokedKeysFile::Krl(krl) => krl.to_bytes()
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RevocationKind {
+    /// Public key
+    Key,
+
+    /// SHA1 hash of public key, KRL only
+    Sha1,
+
+    /// SHA256 hash of public key, KRL only
+    Sha256,
+}
+
+impl Display for RevocationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            RevocationKind::Key => write!(f, "key"),
+            RevocationKind::Sha1 => write!(f, "sha1"),
+            RevocationKind::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+/// Entry of revoked keys file
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct RevokedKey {
+    pub kind: RevocationKind,
+
+    pub fingerprint: String,
+
+    /// Public key, empty for hashes
+    pub key: String,
+}
+
+impl Display for RevokedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.kind, self.fingerprint)?;
+
+        if !self.key.is_empty() {
+            write!(f, " {}", self.key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Key in authorized_keys file which is revoked
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct RevokedKeyEntry {
+    /// Owner of authorized_keys file, empty if file was set explicitly
+    pub username: Option<String>,
+
+    pub file: PathBuf,
+
+    /// Line index in file, starts from 0
+    pub row_index: usize,
+
+    pub fingerprint: String,
+
+    pub key_type: String,
+
+    pub key_id: String,
+}
+
+impl Display for RevokedKeyEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(username) = &self.username {
+            write!(f, "{username} ")?;
+        }
+
+        write!(f, "{}:{} {} {} {}", self.file.display(), self.row_index, self.fingerprint, self.key_type, self.key_id)
+    }
+}
+
+/// Returns keys from authorized_keys files which are revoked, sshd rejects them.
+///
+/// Unreadable files are skipped.
+pub fn get_revoked_entries(files: &[UserKeysFile], with_usernames: bool,
+                           revoked_keys_file: &RevokedKeysFile) -> Vec<RevokedKeyEntry> {
+    info!("check authorized_keys files against revoked keys");
+
+    let mut revoked_entries: Vec<RevokedKeyEntry> = vec![];
+
+    for (file, content) in read_user_keys_files(files) {
+        for (index, line) in KeysFile::parse(&content).lines.iter().enumerate() {
+            let entry = match line.get_entry() {
+                Some(entry) if revoked_keys_file.is_revoked(entry) => entry,
+                _ => continue
+            };
+
+            debug!("revoked key found in '{}' at row {index}", file.path.display());
+
+            revoked_entries.push(RevokedKeyEntry {
+                username: if with_usernames { Some(file.username.to_string()) } else { None },
+                file: file.path.clone(),
+                row_index: index,
+                fingerprint: format!("SHA256:{}", entry.get_fingerprint()),
+                key_type: entry.key_type.to_string(),
+                key_id: entry.get_comment().to_string(),
+            });
+        }
+    }
+
+    info!("revoked keys found: {}", revoked_entries.len());
+
+    revoked_entries
+}
+
+fn get_plain_entries(content: &str) -> Vec<KeyEntry> {
+    KeysFile::parse(content).lines.iter()
+        .filter_map(|line| line.get_entry().cloned())
+        .collect()
+}
+
+/// Key type is the first string of public key blob.
+fn get_key_type(blob: &[u8]) -> String {
+    blob.get(..4)
+        .map(|length| u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize)
+        .and_then(|length| blob.get(4..4 + length))
+        .map(|key_type| String::from_utf8_lossy(key_type).to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn encode_hash(hash: &[u8]) -> String {
+    base64::encode_config(hash, base64::STANDARD_NO_PAD)
+}
+
+#[cfg(test)]
+mod revoked_keys_tests {
+    use crate::keysfile::entry::KeyEntry;
+    use crate::krl::Krl;
+    use crate::tests_common::{create_keys_file, get_temp_dir, KEY1, KEY1_FINGERPRINT, KEY2};
+    use crate::usecases::revokedkeys::{get_revoked_entries, RevocationKind, RevokedKeysFile};
+
+    #[test]
+    fn find_revoked_keys_in_authorized_keys_files() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice", &format!("{KEY2} bob@desktop\nno-pty {KEY1} alice@laptop\n"))];
+
+        let mut krl = Krl::default();
+        krl.revoke_key(&KeyEntry::parse(KEY1).unwrap().get_blob());
+        let revoked_keys_file = RevokedKeysFile::Krl(krl);
+
+        let entries = get_revoked_entries(&files, true, &revoked_keys_file);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].row_index, 1);
+        assert_eq!(entries[0].username, Some("alice".to_string()));
+        assert_eq!(entries[0].fingerprint, KEY1_FINGERPRINT);
+    }
+
+    #[test]
+    fn revoke_and_list_keys() {
+        let entry = KeyEntry::parse(&format!("no-pty {KEY1} alice@laptop")).unwrap();
+
+        let mut plain = RevokedKeysFile::Plain(format!("{KEY2} old"));
+        assert!(plain.revoke(&entry));
+        assert!(!plain.revoke(&entry));
+        assert_eq!(String::from_utf8(plain.to_bytes()).unwrap(), format!("{KEY2} old\n{KEY1} alice@laptop\n"));
+
+        let mut krl = RevokedKeysFile::Krl(Krl::default());
+        assert!(krl.revoke(&entry));
+        assert!(!krl.revoke(&entry));
+
+        let revoked_keys = krl.get_revoked_keys();
+
+        assert_eq!(revoked_keys.len(), 1);
+        assert_eq!(revoked_keys[0].kind, RevocationKind::Key);
+        assert_eq!(revoked_keys[0].key, KEY1);
+    }
+}
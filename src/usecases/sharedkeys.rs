@@ -0,0 +1,240 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use log::{debug, info};
+
+use crate::authlog::PublicKeyLogin;
+use crate::cidr::Cidr;
+use crate::config::AktConfig;
+use crate::keysfile::KeysFile;
+use crate::usecases::audit::{AuditFinding, AuditRule};
+use crate::usecases::usage::{get_key_logins, get_logins_by_key};
+use crate::usecases::users::{read_user_keys_files, UserKeysFile};
+
+const NETWORK_IPV4_PREFIX_LENGTH: u8 = 24;
+
+const NETWORK_IPV6_PREFIX_LENGTH: u8 = 64;
+
+/// Key occurrence in authorized_keys file
+struct KeyLocation {
+    username: String,
+
+    file: PathBuf,
+
+    /// Line number, starts from 1
+    line: usize,
+
+    key_id: String,
+}
+
+/// Reports keys which look shared by several people: key is in authorized_keys files of many users,
+/// key is used from many addresses or networks, or key has many concurrent sessions.
+///
+/// Thresholds are taken from config. Every occurrence of shared key in `reported_files` is reported,
+/// all `files` are reported without it.
+pub fn find_shared_keys(files: &[UserKeysFile], reported_files: Option<&[PathBuf]>, logins: &[PublicKeyLogin],
+                        config: &AktConfig) -> Vec<AuditFinding> {
+    info!("find shared keys in {} authorized_keys files", files.len());
+
+    let mut keys: Vec<(String, Vec<KeyLocation>)> = vec![];
+
+    for (file, content) in read_user_keys_files(files) {
+        for (index, line) in KeysFile::parse(&content).lines.iter().enumerate() {
+            let entry = match line.get_entry() {
+                Some(entry) => entry,
+                None => continue
+            };
+
+            let fingerprint = format!("SHA256:{}", entry.get_fingerprint());
+
+            let location = KeyLocation {
+                username: file.username.to_string(),
+                file: file.path.clone(),
+                line: index + 1,
+                key_id: entry.get_comment().to_string(),
+            };
+
+            match keys.iter_mut().find(|(key_fingerprint, _)| key_fingerprint == &fingerprint) {
+                Some((_, locations)) => locations.push(location),
+                None => keys.push((fingerprint, vec![location]))
+            }
+        }
+    }
+
+    let mut findings: Vec<AuditFinding> = vec![];
+
+    let logins_by_key = get_logins_by_key(logins);
+
+    for (fingerprint, locations) in &keys {
+        let mut usernames: Vec<&str> = locations.iter().map(|location| location.username.as_str()).collect();
+        usernames.sort();
+        usernames.dedup();
+
+        let key_logins = get_key_logins(&logins_by_key, None, fingerprint);
+
+        let addresses = get_source_addresses(&key_logins);
+        let networks: HashSet<Cidr> = addresses.iter().map(get_network).collect();
+        let max_sessions = get_max_concurrent_sessions(&key_logins);
+
+        debug!("key '{fingerprint}': users {}, addresses {}, networks {}, concurrent sessions {max_sessions}",
+               usernames.len(), addresses.len(), networks.len());
+
+        let mut messages: Vec<(AuditRule, String)> = vec![];
+
+        if usernames.len() > config.get_shared_key_max_users() {
+            messages.push((AuditRule::SharedKey,
+                           format!("key is in authorized_keys files of {} users: {}", usernames.len(), usernames.join(", "))));
+        }
+
+        if addresses.len() > config.get_shared_key_max_addresses() || networks.len() > config.get_shared_key_max_networks() ||
+            max_sessions > config.get_shared_key_max_sessions() {
+            messages.push((AuditRule::SharedKeyUsage,
+                           format!("key was used from {} addresses in {} networks, up to {max_sessions} concurrent sessions",
+                                   addresses.len(), networks.len())));
+        }
+
+        // file shared by several users is reported once
+        let mut unique_locations: Vec<&KeyLocation> = vec![];
+
+        let reported_locations = locations.iter()
+            .filter(|location| match reported_files {
+                Some(reported_files) => reported_files.contains(&location.file),
+                None => true
+            });
+
+        for location in reported_locations {
+            if !unique_locations.iter().any(|saved| saved.file == location.file && saved.line == location.line) {
+                unique_locations.push(location);
+            }
+        }
+
+        for (rule, message) in messages {
+            findings.extend(unique_locations.iter().map(|location| AuditFinding {
+                rule: rule.clone(),
+                file: Some(location.file.clone()),
+                line: location.line,
+                fingerprint: Some(fingerprint.to_string()),
+                key_id: location.key_id.to_string(),
+                message: message.to_string(),
+            }));
+        }
+    }
+
+    info!("shared keys findings: {}", findings.len());
+
+    findings
+}
+
+fn get_source_addresses(logins: &[&PublicKeyLogin]) -> HashSet<IpAddr> {
+    logins.iter().filter_map(|login| login.source_ip.parse::<IpAddr>().ok()).collect()
+}
+
+fn get_network(address: &IpAddr) -> Cidr {
+    let prefix_length = if address.is_ipv4() { NETWORK_IPV4_PREFIX_LENGTH } else { NETWORK_IPV6_PREFIX_LENGTH };
+    Cidr::new(*address, prefix_length)
+}
+
+/// Sessions overlap if one starts before other one is closed. Session without close time
+/// (still open or logs were rotated) is counted as open till the end of logs.
+fn get_max_concurrent_sessions(logins: &[&PublicKeyLogin]) -> usize {
+    // (time, is_end), session starts go before ends at the same time
+    let mut events: Vec<(NaiveDateTime, bool)> = vec![];
+
+    for login in logins {
+        events.push((login.timestamp, false));
+
+        if let Some(session_closed) = login.session_closed {
+            events.push((session_closed.max(login.timestamp), true));
+        }
+    }
+
+    events.sort();
+
+    let mut sessions: usize = 0;
+    let mut max_sessions: usize = 0;
+
+    for (_, is_end) in events {
+        if is_end {
+            sessions = sessions.saturating_sub(1);
+
+        } else {
+            sessions += 1;
+            max_sessions = max_sessions.max(sessions);
+        }
+    }
+
+    max_sessions
+}
+
+#[cfg(test)]
+mod shared_keys_tests {
+    use chrono::Duration;
+
+    use crate::config::AktConfig;
+    use crate::tests_common::{create_keys_file, get_closed_session_login, get_login_from, get_temp_dir, KEY1,
+                              KEY1_FINGERPRINT, KEY2, KEY2_FINGERPRINT};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::audit::AuditRule;
+    use crate::usecases::sharedkeys::find_shared_keys;
+
+    #[test]
+    fn report_keys_shared_by_users_and_used_concurrently() {
+        let dir = get_temp_dir();
+        let files = vec![
+            create_keys_file(dir.path(), "alice", &format!("{KEY1} team@deploy\n{KEY2} alice@laptop\n")),
+            create_keys_file(dir.path(), "bob", &format!("{KEY1} team@deploy\n")),
+        ];
+        let (alice_path, bob_path) = (&files[0].path, &files[1].path);
+
+        let timestamp = get_datetime_from_now(1);
+
+        // overlapping sessions with KEY2 from different networks
+        let logins = vec![
+            get_closed_session_login(&timestamp, "alice", "10.0.0.1", KEY2_FINGERPRINT, 60),
+            get_closed_session_login(&timestamp, "alice", "10.0.1.1", KEY2_FINGERPRINT, 60),
+            get_closed_session_login(&timestamp, "alice", "10.0.2.1", KEY2_FINGERPRINT, 60),
+            get_closed_session_login(&timestamp, "alice", "10.0.0.2", KEY1_FINGERPRINT, 60),
+        ];
+
+        let config = AktConfig { shared_key_max_sessions: Some(2), ..AktConfig::default() };
+
+        let findings = find_shared_keys(&files, None, &logins, &config);
+
+        let findings: Vec<(AuditRule, String, usize)> = findings.iter()
+            .map(|f| (f.rule.clone(), f.file.clone().unwrap().display().to_string(), f.line)).collect();
+
+        assert_eq!(findings, vec![
+            (AuditRule::SharedKey, alice_path.display().to_string(), 1),
+            (AuditRule::SharedKey, bob_path.display().to_string(), 1),
+            (AuditRule::SharedKeyUsage, alice_path.display().to_string(), 2),
+        ]);
+
+        let findings = find_shared_keys(&files, Some(std::slice::from_ref(bob_path)), &logins, &config);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, Some(bob_path.clone()));
+
+        let config = AktConfig { shared_key_max_users: Some(2), ..AktConfig::default() };
+
+        assert!(find_shared_keys(&files, None, &logins, &config).is_empty());
+    }
+
+    #[test]
+    fn unclosed_sessions_should_be_counted_as_open() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice", &format!("{KEY1} alice@laptop\n"))];
+
+        let timestamp = get_datetime_from_now(1);
+
+        let logins = vec![
+            get_login_from(&(timestamp - Duration::hours(1)), "alice", "10.0.0.1", KEY1_FINGERPRINT),
+            get_closed_session_login(&timestamp, "alice", "10.0.0.1", KEY1_FINGERPRINT, 60),
+        ];
+
+        let config = AktConfig { shared_key_max_sessions: Some(1), ..AktConfig::default() };
+
+        assert_eq!(find_shared_keys(&files, None, &logins, &config).len(), 1);
+    }
+}
@@ -0,0 +1,197 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use log::info;
+use serde::Serialize;
+
+use crate::authlog::PublicKeyLogin;
+use crate::usecases::usage::format_timestamp;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum StatsGroup {
+    Key, User
+}
+
+impl From<&str> for StatsGroup {
+    fn from(value: &str) -> Self {
+        let lowercase_value = value.to_lowercase();
+
+        match lowercase_value.as_str() {
+            "user" => StatsGroup::User,
+            _ => StatsGroup::Key
+        }
+    }
+}
+
+impl Display for StatsGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            StatsGroup::Key => write!(f, "key"),
+            StatsGroup::User => write!(f, "user"),
+        }
+    }
+}
+
+/// Sessions of key or user from auth logs
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SessionStats {
+    /// `SHA256:...` fingerprint for key stats, username for user stats
+    pub name: String,
+
+    /// Key id from authorized_keys files, empty for user stats and unknown keys
+    pub key_id: Option<String>,
+
+    pub sessions: usize,
+
+    /// Sessions with known close time, durations are calculated for them only
+    pub closed_sessions: usize,
+
+    pub total_duration_seconds: i64,
+
+    pub median_duration_seconds: Option<i64>,
+
+    pub first_seen: String,
+
+    pub last_seen: String,
+}
+
+impl Display for SessionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        if let Some(key_id) = &self.key_id {
+            write!(f, " {key_id}")?;
+        }
+
+        write!(f, " sessions: {} total: {} median: {} first seen: {} last seen: {}", self.sessions,
+               format_duration(self.total_duration_seconds),
+               self.median_duration_seconds.map(format_duration).unwrap_or_else(|| "unknown".to_string()),
+               self.first_seen, self.last_seen)
+    }
+}
+
+/// Session counts and durations by key or by user, the most active go first.
+///
+/// Session lasts from login till `session closed` line of the same sshd process. Certificate logins are
+/// counted for CA key. `key_ids` are used to show key ids for fingerprints.
+pub fn get_session_stats(logins: &[PublicKeyLogin], group: &StatsGroup,
+                         key_ids: &HashMap<String, String>) -> Vec<SessionStats> {
+    info!("get session stats by {group}");
+
+    let mut names: Vec<&str> = vec![];
+    let mut group_logins: HashMap<&str, Vec<&PublicKeyLogin>> = HashMap::new();
+
+    for login in logins {
+        let name = match group {
+            StatsGroup::Key => match &login.certificate {
+                Some(certificate) => certificate.ca_fingerprint.as_str(),
+                None => login.fingerprint.as_str()
+            },
+            StatsGroup::User => login.username.as_str()
+        };
+
+        group_logins.entry(name).or_insert_with(|| {
+            names.push(name);
+            vec![]
+        }).push(login);
+    }
+
+    let mut stats: Vec<SessionStats> = names.iter().map(|name| {
+        let logins = &group_logins[name];
+
+        let mut durations: Vec<i64> = logins.iter()
+            .filter_map(|login| login.session_closed.map(|closed| (closed - login.timestamp).num_seconds().max(0)))
+            .collect();
+
+        durations.sort();
+
+        SessionStats {
+            name: name.to_string(),
+            key_id: match group {
+                StatsGroup::Key => key_ids.get(*name).cloned(),
+                StatsGroup::User => None
+            },
+            sessions: logins.len(),
+            closed_sessions: durations.len(),
+            total_duration_seconds: durations.iter().sum(),
+            median_duration_seconds: get_median(&durations),
+            first_seen: logins.iter().map(|login| login.timestamp).min().map(|t| format_timestamp(&t)).unwrap_or_default(),
+            last_seen: logins.iter().map(|login| login.timestamp).max().map(|t| format_timestamp(&t)).unwrap_or_default(),
+        }
+    }).collect();
+
+    stats.sort_by_key(|stats| Reverse(stats.sessions));
+
+    stats
+}
+
+/// `values` must be sorted.
+fn get_median(values: &[i64]) -> Option<i64> {
+    match values.len() {
+        0 => None,
+        length if length % 2 == 0 => Some((values[length / 2 - 1] + values[length / 2]) / 2),
+        length => Some(values[length / 2])
+    }
+}
+
+/// `1h 02m 05s`
+fn format_duration(seconds: i64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use std::collections::HashMap;
+
+    use crate::tests_common::{get_closed_session_login, get_public_key_login, KEY1_FINGERPRINT, KEY2_FINGERPRINT};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::stats::{format_duration, get_session_stats, StatsGroup};
+
+    #[test]
+    fn calculate_session_durations_by_key_and_user() {
+        let timestamp = get_datetime_from_now(1);
+
+        let logins = vec![
+            get_closed_session_login(&timestamp, "alice", "10.0.0.1", KEY1_FINGERPRINT, 60),
+            get_closed_session_login(&timestamp, "alice", "10.0.0.1", KEY1_FINGERPRINT, 300),
+            get_closed_session_login(&timestamp, "alice", "10.0.0.1", KEY1_FINGERPRINT, 120),
+            get_public_key_login(&timestamp, "alice", KEY1_FINGERPRINT),
+            get_closed_session_login(&timestamp, "bob", "10.0.0.1", KEY2_FINGERPRINT, 10),
+            get_closed_session_login(&timestamp, "bob", "10.0.0.1", KEY1_FINGERPRINT, 20),
+        ];
+
+        let key_ids = HashMap::from([(KEY1_FINGERPRINT.to_string(), "alice@laptop".to_string())]);
+
+        let stats = get_session_stats(&logins, &StatsGroup::Key, &key_ids);
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].name, KEY1_FINGERPRINT);
+        assert_eq!(stats[0].key_id, Some("alice@laptop".to_string()));
+        assert_eq!(stats[0].sessions, 5);
+        assert_eq!(stats[0].closed_sessions, 4);
+        assert_eq!(stats[0].total_duration_seconds, 500);
+        assert_eq!(stats[0].median_duration_seconds, Some(90));
+        assert_eq!(stats[1].key_id, None);
+
+        let stats = get_session_stats(&logins, &StatsGroup::User, &key_ids);
+
+        let stats: Vec<(String, usize, Option<i64>)> = stats.iter()
+            .map(|s| (s.name.to_string(), s.sessions, s.median_duration_seconds)).collect();
+
+        assert_eq!(stats, vec![("alice".to_string(), 4, Some(120)), ("bob".to_string(), 2, Some(15))]);
+
+        assert_eq!(format_duration(3725), "1h 02m 05s");
+        assert_eq!(format_duration(65), "1m 05s");
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use log::{debug, info, warn};
+use serde::Serialize;
+
+use crate::authlog::PublicKeyLogin;
+use crate::cidr::{aggregate, Cidr};
+use crate::files::write_file_atomically;
+use crate::keysfile::entry::{KeyEntry, KeyOption, SOURCE_ADDRESSES_OPTION};
+use crate::keysfile::{Line, LineKind, KeysFile};
+use crate::usecases::usage::{get_key_logins, get_logins_by_key};
+use crate::usecases::users::UserKeysFile;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuggestAction {
+    Suggested,
+
+    /// `from` option was added to entry
+    Applied,
+
+    /// `from` option would be added to entry without `--dry-run`
+    WouldApply,
+
+    /// Entry already has the same `from` option
+    Unchanged,
+
+    /// Entry wasn't changed: no logins, entry already has other `from` option
+    /// or entry is outside of entries managed by akt
+    Skipped,
+}
+
+impl Display for SuggestAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            SuggestAction::Suggested => write!(f, "suggested"),
+            SuggestAction::Applied => write!(f, "applied"),
+            SuggestAction::WouldApply => write!(f, "would-apply"),
+            SuggestAction::Unchanged => write!(f, "unchanged"),
+            SuggestAction::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// `from` option suggested for key by source addresses of its logins
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct FromSuggestion {
+    pub action: SuggestAction,
+
+    /// Owner of authorized_keys file, users of shared file are separated by comma.
+    /// Empty if file was set explicitly
+    pub username: Option<String>,
+
+    pub file: PathBuf,
+
+    /// Line index in file, starts from 0
+    pub row_index: usize,
+
+    pub fingerprint: String,
+
+    pub key_id: String,
+
+    pub logins: usize,
+
+    /// Value of current `from` option
+    pub current: Option<String>,
+
+    /// Networks covering source addresses, empty if key has no logins
+    pub suggested: Option<String>,
+
+    pub message: String,
+}
+
+impl Display for FromSuggestion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] ", self.action)?;
+
+        if let Some(username) = &self.username {
+            write!(f, "{username} ")?;
+        }
+
+        write!(f, "{}:{} {} {} logins: {}", self.file.display(), self.row_index, self.fingerprint,
+               self.key_id, self.logins)?;
+
+        if let Some(suggested) = &self.suggested {
+            write!(f, " from=\"{suggested}\"")?;
+        }
+
+        if let Some(current) = &self.current {
+            write!(f, " current: from=\"{current}\"")?;
+        }
+
+        if !self.message.is_empty() {
+            write!(f, " {}", self.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Networks settings for suggestion, i.e. `24` for IPv4 suggests `10.0.0.0/24` for login from `10.0.0.7`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SuggestOptions {
+    pub ipv4_prefix_length: u8,
+
+    pub ipv6_prefix_length: u8,
+
+    /// Add `from` option to entries
+    pub apply: bool,
+
+    pub dry_run: bool,
+}
+
+/// Suggests `from` option for each key: minimal set of networks covering source addresses of key logins.
+///
+/// With `apply` option is added to entries without `from` option, entries outside of managed block
+/// or inside unmanaged block aren't changed. With `dry_run` files aren't changed.
+///
+/// File shared by several users (i.e. `AuthorizedKeysFile /etc/ssh/keys` or symlink) is processed once,
+/// option is built from logins of all its users.
+///
+/// All files are checked before any of them is written, so error in one file leaves all files unchanged.
+pub fn suggest_from(files: &[UserKeysFile], with_usernames: bool, logins: &[PublicKeyLogin],
+                    options: &SuggestOptions) -> anyhow::Result<Vec<FromSuggestion>> {
+    info!("suggest from option, options {:?}", options);
+
+    let mut suggestions: Vec<FromSuggestion> = vec![];
+
+    let logins_by_key = get_logins_by_key(logins);
+
+    let mut changed_files: Vec<(PathBuf, KeysFile)> = vec![];
+
+    for (real_path, usernames, file) in get_shared_files(files) {
+        let mut keys_file = match fs::read_to_string(&real_path) {
+            Ok(content) => KeysFile::parse(&content),
+            Err(e) => {
+                warn!("unable to read file '{}': {}", file.path.display(), e);
+                continue
+            }
+        };
+
+        let editable_lines = if options.apply {
+            keys_file.get_editable_lines().map_err(|e| anyhow::anyhow!("file '{}': {}", file.path.display(), e))?
+        } else {
+            vec![]
+        };
+
+        let mut changed = false;
+
+        for index in 0..keys_file.lines.len() {
+            let entry = match keys_file.lines[index].get_entry() {
+                Some(entry) => entry.clone(),
+                None => continue
+            };
+
+            let fingerprint = format!("SHA256:{}", entry.get_fingerprint());
+
+            let username = if with_usernames { Some(usernames.join(",")) } else { None };
+
+            let key_logins: Vec<&PublicKeyLogin> = if with_usernames {
+                usernames.iter()
+                    .flat_map(|username| get_key_logins(&logins_by_key, Some(username), &fingerprint))
+                    .collect()
+
+            } else {
+                get_key_logins(&logins_by_key, None, &fingerprint)
+            };
+
+            let current = entry.get_option(SOURCE_ADDRESSES_OPTION).and_then(|o| o.value.clone());
+
+            let suggested = get_networks(&key_logins, options);
+
+            let (action, message) = match &suggested {
+                None => (SuggestAction::Skipped, "no logins in auth logs"),
+                Some(suggested) if current.as_ref() == Some(suggested) => (SuggestAction::Unchanged, ""),
+                Some(_) if !options.apply => (SuggestAction::Suggested, ""),
+                Some(_) if current.is_some() => (SuggestAction::Skipped, "entry already has from option"),
+                Some(_) if !editable_lines.contains(&index) =>
+                    (SuggestAction::Skipped, "entry is outside of entries managed by akt"),
+                Some(_) if options.dry_run => (SuggestAction::WouldApply, ""),
+                Some(suggested) => {
+                    set_source_addresses(&mut keys_file.lines[index], &entry, suggested);
+                    changed = true;
+                    (SuggestAction::Applied, "")
+                }
+            };
+
+            debug!("key '{fingerprint}' at row {index}: {action}");
+
+            suggestions.push(FromSuggestion {
+                action,
+                username,
+                file: file.path.clone(),
+                row_index: index,
+                fingerprint,
+                key_id: entry.get_comment().to_string(),
+                logins: key_logins.len(),
+                current,
+                suggested,
+                message: message.to_string(),
+            });
+        }
+
+        if changed {
+            changed_files.push((real_path, keys_file));
+        }
+    }
+
+    // symlink is resolved, so file is replaced instead of link
+    for (path, keys_file) in changed_files {
+        write_file_atomically(&path, keys_file.to_string().as_bytes())?;
+    }
+
+    Ok(suggestions)
+}
+
+/// Groups files by real path: (real path, users of file, first file of group).
+fn get_shared_files(files: &[UserKeysFile]) -> Vec<(PathBuf, Vec<&str>, &UserKeysFile)> {
+    let mut shared_files: Vec<(PathBuf, Vec<&str>, &UserKeysFile)> = vec![];
+
+    for file in files {
+        let real_path = fs::canonicalize(&file.path).unwrap_or_else(|_| file.path.clone());
+
+        match shared_files.iter_mut().find(|(path, _, _)| *path == real_path) {
+            Some((_, usernames, _)) => {
+                if !usernames.contains(&file.username.as_str()) {
+                    usernames.push(&file.username);
+                }
+            }
+            None => shared_files.push((real_path, vec![&file.username], file))
+        }
+    }
+
+    shared_files
+}
+
+fn get_networks(logins: &[&PublicKeyLogin], options: &SuggestOptions) -> Option<String> {
+    let mut networks: Vec<Cidr> = vec![];
+
+    let source_addresses: HashSet<&str> = logins.iter().map(|login| login.source_ip.as_str()).collect();
+
+    for source_address in source_addresses {
+        match source_address.parse::<IpAddr>() {
+            Ok(address) => {
+                let prefix_length = if address.is_ipv4() { options.ipv4_prefix_length } else { options.ipv6_prefix_length };
+                networks.push(Cidr::new(address, prefix_length));
+            }
+            Err(_) => warn!("source address '{source_address}' isn't ip address, skip")
+        }
+    }
+
+    if networks.is_empty() {
+        return None
+    }
+
+    let networks: Vec<String> = aggregate(&networks).iter().map(|network| network.to_string()).collect();
+
+    Some(networks.join(","))
+}
+
+fn set_source_addresses(line: &mut Line, entry: &KeyEntry, networks: &str) {
+    let mut entry = entry.clone();
+
+    entry.options.insert(0, KeyOption {
+        name: SOURCE_ADDRESSES_OPTION.to_string(),
+        value: Some(networks.to_string()),
+    });
+
+    line.text = entry.to_string();
+    line.kind = LineKind::Key(entry);
+}
+
+#[cfg(test)]
+mod suggest_from_tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    use crate::keysfile::MANAGED_BLOCK_BEGIN;
+    use crate::tests_common::{create_keys_file, get_login_from, get_temp_dir, KEY1, KEY1_FINGERPRINT, KEY2,
+                              KEY2_FINGERPRINT};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::suggestfrom::{suggest_from, SuggestAction, SuggestOptions};
+    use crate::usecases::users::UserKeysFile;
+
+    #[test]
+    fn suggest_and_apply_networks_covering_logins() {
+        let dir = get_temp_dir();
+        let content = format!("no-pty {KEY1} alice@laptop\n{KEY2} alice@desktop\n");
+        let files = vec![create_keys_file(dir.path(), "alice", &content)];
+        let path = &files[0].path;
+
+        let timestamp = get_datetime_from_now(1);
+
+        let logins = vec![
+            get_login_from(&timestamp, "alice", "10.0.0.7", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "alice", "10.0.0.200", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "alice", "2001:db8::1", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "alice", "10.0.0.7", "SHA256:unknown"),
+        ];
+
+        let mut options = SuggestOptions { ipv4_prefix_length: 24, ipv6_prefix_length: 64, apply: false, dry_run: false };
+
+        let suggestions = suggest_from(&files, true, &logins, &options).unwrap();
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].action, SuggestAction::Suggested);
+        assert_eq!(suggestions[0].logins, 3);
+        assert_eq!(suggestions[0].suggested, Some("10.0.0.0/24,2001:db8::/64".to_string()));
+        assert_eq!(suggestions[1].action, SuggestAction::Skipped);
+        assert_eq!(suggestions[1].suggested, None);
+
+        options.apply = true;
+        options.dry_run = true;
+        let suggestions = suggest_from(&files, true, &logins, &options).unwrap();
+        assert_eq!(suggestions[0].action, SuggestAction::WouldApply);
+        assert_eq!(fs::read_to_string(path).unwrap(), content);
+
+        options.dry_run = false;
+        let suggestions = suggest_from(&files, true, &logins, &options).unwrap();
+        assert_eq!(suggestions[0].action, SuggestAction::Applied);
+        assert_eq!(fs::read_to_string(path).unwrap(),
+                   format!("from=\"10.0.0.0/24,2001:db8::/64\",no-pty {KEY1} alice@laptop\n{KEY2} alice@desktop\n"));
+
+        let suggestions = suggest_from(&files, true, &logins, &options).unwrap();
+        assert_eq!(suggestions[0].action, SuggestAction::Unchanged);
+    }
+
+    #[test]
+    fn entries_outside_of_managed_block_should_not_be_changed() {
+        let dir = get_temp_dir();
+        let content = format!("{KEY1} alice@laptop\n{MANAGED_BLOCK_BEGIN}\n{KEY2} alice@desktop\n# END akt managed\n");
+        let files = vec![create_keys_file(dir.path(), "alice", &content)];
+        let path = &files[0].path;
+
+        let timestamp = get_datetime_from_now(1);
+
+        let logins = vec![
+            get_login_from(&timestamp, "alice", "10.0.0.7", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "alice", "10.0.0.8", KEY2_FINGERPRINT),
+        ];
+
+        let options = SuggestOptions { ipv4_prefix_length: 32, ipv6_prefix_length: 128, apply: true, dry_run: false };
+
+        let suggestions = suggest_from(&files, false, &logins, &options).unwrap();
+
+        assert_eq!(suggestions[0].action, SuggestAction::Skipped);
+        assert_eq!(suggestions[1].action, SuggestAction::Applied);
+        assert_eq!(fs::read_to_string(path).unwrap(),
+                   format!("{KEY1} alice@laptop\n{MANAGED_BLOCK_BEGIN}\nfrom=\"10.0.0.8\" {KEY2} alice@desktop\n# END akt managed\n"));
+    }
+
+    #[test]
+    fn shared_file_should_get_networks_of_all_users() {
+        let dir = get_temp_dir();
+        let file = create_keys_file(dir.path(), "alice", &format!("{KEY1} deployer\n"));
+
+        let link_path = dir.path().join("bob");
+        symlink(&file.path, &link_path).unwrap();
+
+        let files = vec![
+            file.clone(),
+            UserKeysFile { username: "bob".to_string(), path: link_path.clone() },
+            UserKeysFile { username: "carol".to_string(), ..file.clone() },
+        ];
+
+        let timestamp = get_datetime_from_now(1);
+
+        let logins = vec![
+            get_login_from(&timestamp, "alice", "10.0.0.7", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "bob", "10.0.1.8", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "dave", "10.0.2.9", KEY1_FINGERPRINT),
+        ];
+
+        let options = SuggestOptions { ipv4_prefix_length: 32, ipv6_prefix_length: 128, apply: true, dry_run: false };
+
+        let suggestions = suggest_from(&files, true, &logins, &options).unwrap();
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].action, SuggestAction::Applied);
+        assert_eq!(suggestions[0].username, Some("alice,bob,carol".to_string()));
+        assert_eq!(suggestions[0].logins, 2);
+
+        assert_eq!(fs::read_to_string(&file.path).unwrap(), format!("from=\"10.0.0.7,10.0.1.8\" {KEY1} deployer\n"));
+        assert!(fs::symlink_metadata(&link_path).unwrap().file_type().is_symlink());
+    }
+
+    #[test]
+    fn no_file_should_be_changed_if_any_file_has_broken_markers() {
+        let dir = get_temp_dir();
+        let alice_content = format!("{KEY1} alice@laptop\n");
+        let bob_content = format!("{MANAGED_BLOCK_BEGIN}\n{KEY2} bob@desktop\n");
+        let files = vec![
+            create_keys_file(dir.path(), "alice", &alice_content),
+            create_keys_file(dir.path(), "bob", &bob_content),
+        ];
+
+        let timestamp = get_datetime_from_now(1);
+
+        let logins = vec![
+            get_login_from(&timestamp, "alice", "10.0.0.7", KEY1_FINGERPRINT),
+            get_login_from(&timestamp, "bob", "10.0.0.8", KEY2_FINGERPRINT),
+        ];
+
+        let mut options = SuggestOptions { ipv4_prefix_length: 32, ipv6_prefix_length: 128, apply: true, dry_run: false };
+
+        assert!(suggest_from(&files, false, &logins, &options).is_err());
+        assert_eq!(fs::read_to_string(&files[0].path).unwrap(), alice_content);
+        assert_eq!(fs::read_to_string(&files[1].path).unwrap(), bob_content);
+
+        options.apply = false;
+
+        let suggestions = suggest_from(&files, false, &logins, &options).unwrap();
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[1].action, SuggestAction::Suggested);
+    }
+}
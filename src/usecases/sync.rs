@@ -0,0 +1,348 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::files::write_file_atomically;
+use crate::keysfile::{KeysFile, Line};
+use crate::keysfile::entry::KeyEntry;
+use crate::usecases::protected::ProtectedKeys;
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncActionType {
+    Add,
+    Remove,
+    Change,
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SyncAction {
+    pub action: SyncActionType,
+
+    /// Line number in current authorized_keys file, starts from 1. Empty for additions.
+    pub line: Option<usize>,
+
+    pub fingerprint: String,
+
+    /// Entry after sync, for removal - current entry
+    pub entry: String,
+
+    /// Current entry for changes
+    pub previous_entry: Option<String>,
+}
+
+impl Display for SyncAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            SyncActionType::Add => write!(f, "+ {}", self.entry),
+            SyncActionType::Remove => write!(f, "- {}: {}", self.line.unwrap_or_default(), self.entry),
+            SyncActionType::Change => write!(f, "~ {}: {} -> {}", self.line.unwrap_or_default(),
+                                             self.previous_entry.as_deref().unwrap_or_default(), self.entry)
+        }
+    }
+}
+
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+
+    /// authorized_keys file content after sync
+    pub content: String,
+}
+
+struct DesiredEntry {
+    fingerprint: String,
+    key_entry: KeyEntry,
+
+    /// Entry as written in desired keys file
+    text: String,
+}
+
+/// Reconciles authorized_keys file with desired keys file.
+///
+/// Keys missing in authorized_keys are added, keys absent in desired file are removed,
+/// keys with different options or id (comment) are replaced with desired entry.
+/// Repeated entries of the same key are removed, only the first one is kept.
+///
+/// If file has managed block, only entries inside the block are changed and new keys are added
+/// to the end of the block. Otherwise entries inside unmanaged block are left alone.
+/// Protected keys are never removed.
+///
+/// With `dry_run` the file isn't changed, only plan is returned.
+pub fn sync_authorized_keys_file(authorized_keys_file_path: &Path, desired_keys_file_path: &Path,
+                                 protected_keys: &ProtectedKeys, dry_run: bool) -> anyhow::Result<Vec<SyncAction>> {
+    info!("sync authorized_keys file '{}' with '{}'", authorized_keys_file_path.display(),
+          desired_keys_file_path.display());
+
+    let desired_content = fs::read_to_string(desired_keys_file_path)?;
+
+    let current_content = if authorized_keys_file_path.exists() {
+        fs::read_to_string(authorized_keys_file_path)?
+
+    } else {
+        info!("authorized_keys file doesn't exist, it will be created");
+        String::new()
+    };
+
+    let plan = get_sync_plan(&current_content, &desired_content, protected_keys)?;
+
+    if plan.actions.is_empty() {
+        info!("authorized_keys file is in sync");
+
+    } else if dry_run {
+        info!("dry run, file wasn't changed");
+
+    } else {
+        write_file_atomically(authorized_keys_file_path, plan.content.as_bytes())?;
+    }
+
+    Ok(plan.actions)
+}
+
+pub fn get_sync_plan(current_content: &str, desired_content: &str,
+                     protected_keys: &ProtectedKeys) -> anyhow::Result<SyncPlan> {
+    let desired_entries = get_desired_entries(desired_content)?;
+    debug!("desired entries: {}", desired_entries.len());
+
+    let mut keys_file = KeysFile::parse(current_content);
+
+    let editable_lines = keys_file.get_editable_lines()?;
+
+    let line_ending = keys_file.get_line_ending().to_string();
+
+    let mut actions: Vec<SyncAction> = vec![];
+
+    let mut seen_fingerprints: Vec<String> = vec![];
+
+    let mut removed_lines: Vec<usize> = vec![];
+
+    for (index, line) in keys_file.lines.iter_mut().enumerate() {
+        let line_number = index + 1;
+
+        let entry = match line.get_entry() {
+            Some(entry) => entry,
+            None => continue
+        };
+
+        let fingerprint = entry.get_fingerprint();
+
+        let is_duplicate = seen_fingerprints.contains(&fingerprint);
+
+        if !is_duplicate {
+            seen_fingerprints.push(fingerprint.clone());
+        }
+
+        if !editable_lines.contains(&index) {
+            debug!("line {line_number}: key '{fingerprint}' is outside of managed entries, skip");
+            continue
+        }
+
+        match desired_entries.iter().find(|e| e.fingerprint == fingerprint) {
+            Some(_) if is_duplicate => {
+                debug!("line {line_number}: key '{fingerprint}' is duplicate, remove");
+
+                actions.push(SyncAction {
+                    action: SyncActionType::Remove,
+                    line: Some(line_number),
+                    fingerprint: format!("SHA256:{fingerprint}"),
+                    entry: line.text.trim().to_string(),
+                    previous_entry: None,
+                });
+
+                removed_lines.push(index);
+            }
+            Some(desired_entry) => {
+                if desired_entry.key_entry.options != entry.options ||
+                    desired_entry.key_entry.comment != entry.comment {
+
+                    actions.push(SyncAction {
+                        action: SyncActionType::Change,
+                        line: Some(line_number),
+                        fingerprint: format!("SHA256:{fingerprint}"),
+                        entry: desired_entry.text.clone(),
+                        previous_entry: Some(line.text.trim().to_string()),
+                    });
+
+                    *line = Line::parse(&format!("{}{}", desired_entry.text, line.line_ending));
+                }
+            }
+            None => {
+                if protected_keys.is_entry_protected(entry) {
+                    info!("line {line_number}: key '{fingerprint}' is protected, skip removal");
+
+                } else {
+                    actions.push(SyncAction {
+                        action: SyncActionType::Remove,
+                        line: Some(line_number),
+                        fingerprint: format!("SHA256:{fingerprint}"),
+                        entry: line.text.trim().to_string(),
+                        previous_entry: None,
+                    });
+
+                    removed_lines.push(index);
+                }
+            }
+        }
+    }
+
+    let insert_index = keys_file.get_insert_index()?;
+
+    let new_entries = desired_entries.iter().filter(|e| !seen_fingerprints.contains(&e.fingerprint));
+
+    for (offset, new_entry) in new_entries.enumerate() {
+        actions.push(SyncAction {
+            action: SyncActionType::Add,
+            line: None,
+            fingerprint: format!("SHA256:{}", new_entry.fingerprint),
+            entry: new_entry.text.clone(),
+            previous_entry: None,
+        });
+
+        keys_file.insert_line(insert_index + offset, Line::parse(&format!("{}{line_ending}", new_entry.text)));
+    }
+
+    // removed lines are before inserted ones, so indexes are still valid
+    for removed_line in removed_lines.iter().rev() {
+        keys_file.lines.remove(*removed_line);
+    }
+
+    info!("sync actions: {}", actions.len());
+
+    Ok(SyncPlan { actions, content: keys_file.to_string() })
+}
+
+/// Any invalid line is an error, so broken desired file never removes keys.
+fn get_desired_entries(desired_content: &str) -> anyhow::Result<Vec<DesiredEntry>> {
+    let mut entries: Vec<DesiredEntry> = vec![];
+
+    for (index, line) in desired_content.lines().enumerate() {
+        let trimmed_line = line.trim();
+
+        if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+            continue
+        }
+
+        let key_entry = KeyEntry::parse(trimmed_line)
+            .map_err(|e| anyhow::anyhow!("invalid key at line {} of desired keys file: {}", index + 1, e))?;
+
+        let fingerprint = key_entry.get_fingerprint();
+
+        if entries.iter().any(|e| e.fingerprint == fingerprint) {
+            anyhow::bail!("duplicate key at line {} of desired keys file", index + 1)
+        }
+
+        entries.push(DesiredEntry { fingerprint, key_entry, text: trimmed_line.to_string() });
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod sync_tests {
+    use crate::tests_common::{KEY1, KEY2, KEY3};
+    use crate::usecases::protected::{get_protected_keys_from_str, ProtectedKeys};
+    use crate::usecases::sync::{get_sync_plan, SyncActionType};
+
+    #[test]
+    fn no_actions_for_synced_file() {
+        let current = format!("# keys\n{KEY1} a@b.com\n\n{KEY2} c@d.com\n");
+        let desired = format!("{KEY2} c@d.com\n{KEY1} a@b.com\n");
+
+        let plan = get_sync_plan(&current, &desired, &ProtectedKeys::default()).unwrap();
+
+        assert!(plan.actions.is_empty());
+        assert_eq!(plan.content, current);
+    }
+
+    #[test]
+    fn add_remove_and_change_keys() {
+        let current = format!("# keys\n{KEY1} a@b.com\n{KEY2} c@d.com");
+        let desired = format!("from=\"10.0.0.1\" {KEY2} c@d.com\n{KEY3} e@f.com\n");
+
+        let plan = get_sync_plan(&current, &desired, &ProtectedKeys::default()).unwrap();
+
+        let actions: Vec<(SyncActionType, Option<usize>)> = plan.actions.iter()
+            .map(|a| (a.action.clone(), a.line)).collect();
+
+        assert_eq!(actions, vec![
+            (SyncActionType::Remove, Some(2)),
+            (SyncActionType::Change, Some(3)),
+            (SyncActionType::Add, None),
+        ]);
+
+        assert_eq!(plan.content, format!("# keys\nfrom=\"10.0.0.1\" {KEY2} c@d.com\n{KEY3} e@f.com\n"));
+    }
+
+    #[test]
+    fn remove_duplicate_entries() {
+        let current = format!("{KEY1} a@b.com\nno-pty {KEY1} a@b.com\n{KEY2} c@d.com\n{KEY1} a@b.com\n");
+        let desired = format!("{KEY1} a@b.com\n{KEY2} c@d.com\n");
+
+        let plan = get_sync_plan(&current, &desired, &ProtectedKeys::default()).unwrap();
+
+        let actions: Vec<(SyncActionType, Option<usize>)> = plan.actions.iter()
+            .map(|a| (a.action.clone(), a.line)).collect();
+
+        assert_eq!(actions, vec![
+            (SyncActionType::Remove, Some(2)),
+            (SyncActionType::Remove, Some(4)),
+        ]);
+
+        assert_eq!(plan.content, desired);
+    }
+
+    #[test]
+    fn keep_unmanaged_and_protected_keys() {
+        let current = format!("# BEGIN akt unmanaged\n{KEY1} a@b.com\n# END akt unmanaged\n{KEY2} test@ansible\n");
+        let desired = format!("{KEY3} e@f.com\n");
+
+        let protected_keys = get_protected_keys_from_str("test@ansible").unwrap();
+
+        let plan = get_sync_plan(&current, &desired, &protected_keys).unwrap();
+
+        assert_eq!(plan.actions.len(), 1);
+        assert_eq!(plan.actions[0].action, SyncActionType::Add);
+        assert_eq!(plan.content, format!("{current}{KEY3} e@f.com\n"));
+    }
+
+    #[test]
+    fn edit_only_managed_block() {
+        let current = format!("# hand-edited\r\n{KEY1} a@b.com  \r\n\r\n# BEGIN akt managed\r\n{KEY2} c@d.com\r\n# END akt managed\r\n");
+        let desired = format!("{KEY3} e@f.com\n");
+
+        let plan = get_sync_plan(&current, &desired, &ProtectedKeys::default()).unwrap();
+
+        let actions: Vec<SyncActionType> = plan.actions.iter().map(|a| a.action.clone()).collect();
+
+        assert_eq!(actions, vec![SyncActionType::Remove, SyncActionType::Add]);
+
+        assert_eq!(plan.content, format!("# hand-edited\r\n{KEY1} a@b.com  \r\n\r\n# BEGIN akt managed\r\n{KEY3} e@f.com\r\n# END akt managed\r\n"));
+    }
+
+    #[test]
+    fn keys_outside_managed_block_should_not_be_added() {
+        let current = format!("{KEY1} a@b.com\n# BEGIN akt managed\n# END akt managed\n");
+        let desired = format!("{KEY1} a@b.com\n{KEY2} c@d.com\n");
+
+        let plan = get_sync_plan(&current, &desired, &ProtectedKeys::default()).unwrap();
+
+        assert_eq!(plan.content, format!("{KEY1} a@b.com\n# BEGIN akt managed\n{KEY2} c@d.com\n# END akt managed\n"));
+    }
+
+    #[test]
+    fn broken_managed_block_should_be_rejected() {
+        let current = format!("# BEGIN akt managed\n{KEY1} a@b.com\n");
+
+        assert!(get_sync_plan(&current, &format!("{KEY2} c@d.com\n"), &ProtectedKeys::default()).is_err());
+    }
+
+    #[test]
+    fn invalid_desired_file_should_be_rejected() {
+        let current = format!("{KEY1} a@b.com\n");
+
+        assert!(get_sync_plan(&current, "ssh-ed25519 broken-key", &ProtectedKeys::default()).is_err());
+        assert!(get_sync_plan(&current, &format!("{KEY1} a\n{KEY1} b\n"), &ProtectedKeys::default()).is_err());
+    }
+}
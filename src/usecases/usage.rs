@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::authlog::PublicKeyLogin;
+use crate::keysfile::KeysFile;
+use crate::usecases::users::{read_user_keys_files, UserKeysFile};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Address which key was used from
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SourceAddress {
+    pub address: String,
+
+    /// Number of logins (or failed attempts for key failures) from address
+    pub logins: usize,
+
+    pub first_seen: String,
+
+    pub last_seen: String,
+}
+
+impl Display for SourceAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} logins: {} first seen: {} last seen: {}", self.address, self.logins, self.first_seen, self.last_seen)
+    }
+}
+
+/// Logins with key from auth logs
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct KeyUsage {
+    /// Owner of authorized_keys file, empty if file was set explicitly
+    pub username: Option<String>,
+
+    pub file: PathBuf,
+
+    /// Line index in file, starts from 0
+    pub row_index: usize,
+
+    pub fingerprint: String,
+
+    pub key_type: String,
+
+    pub key_id: String,
+
+    pub logins: usize,
+
+    pub first_seen: Option<String>,
+
+    pub last_seen: Option<String>,
+
+    /// Distinct source addresses, set only if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_addresses: Option<Vec<SourceAddress>>,
+}
+
+impl Display for KeyUsage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(username) = &self.username {
+            write!(f, "{username} ")?;
+        }
+
+        write!(f, "{}:{} {} {} {} logins: {} first seen: {} last seen: {}", self.file.display(), self.row_index,
+               self.fingerprint, self.key_type, self.key_id, self.logins,
+               self.first_seen.as_deref().unwrap_or("never"), self.last_seen.as_deref().unwrap_or("never"))?;
+
+        for source_address in self.source_addresses.iter().flatten() {
+            write!(f, "\n  {source_address}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns logins stats for each key from authorized_keys files.
+///
+/// Logins with certificates are counted for `cert-authority` entry. For files with known user
+/// only user's logins are counted. With `with_addresses` distinct source addresses are collected.
+pub fn get_keys_usage(files: &[UserKeysFile], with_usernames: bool, logins: &[PublicKeyLogin],
+                      with_addresses: bool) -> Vec<KeyUsage> {
+    info!("get keys usage");
+
+    let mut usages: Vec<KeyUsage> = vec![];
+
+    let logins_by_key = get_logins_by_key(logins);
+
+    for (file, content) in read_user_keys_files(files) {
+        for (index, line) in KeysFile::parse(&content).lines.iter().enumerate() {
+            let entry = match line.get_entry() {
+                Some(entry) => entry,
+                None => continue
+            };
+
+            let fingerprint = format!("SHA256:{}", entry.get_fingerprint());
+
+            let username = if with_usernames { Some(file.username.to_string()) } else { None };
+
+            let key_logins = get_key_logins(&logins_by_key, username.as_deref(), &fingerprint);
+
+            debug!("key '{fingerprint}' logins: {}", key_logins.len());
+
+            let timestamps: Vec<NaiveDateTime> = key_logins.iter().map(|login| login.timestamp).collect();
+
+            usages.push(KeyUsage {
+                username,
+                file: file.path.clone(),
+                row_index: index,
+                fingerprint,
+                key_type: entry.key_type.to_string(),
+                key_id: entry.get_comment().to_string(),
+                logins: key_logins.len(),
+                first_seen: timestamps.iter().min().map(format_timestamp),
+                last_seen: timestamps.iter().max().map(format_timestamp),
+                source_addresses: if with_addresses {
+                    Some(get_source_addresses(key_logins.iter().map(|login| (login.source_ip.as_str(), login.timestamp))))
+                } else {
+                    None
+                },
+            });
+        }
+    }
+
+    usages
+}
+
+/// Logins grouped by key (`SHA256:...` fingerprint), certificate logins are attributed to CA key.
+pub fn get_logins_by_key(logins: &[PublicKeyLogin]) -> HashMap<&str, Vec<&PublicKeyLogin>> {
+    let mut logins_by_key: HashMap<&str, Vec<&PublicKeyLogin>> = HashMap::new();
+
+    for login in logins {
+        let fingerprint = match &login.certificate {
+            Some(certificate) => certificate.ca_fingerprint.as_str(),
+            None => login.fingerprint.as_str()
+        };
+
+        logins_by_key.entry(fingerprint).or_default().push(login);
+    }
+
+    logins_by_key
+}
+
+/// Logins with key from `get_logins_by_key` result. Without `username` logins of all users are returned.
+pub fn get_key_logins<'a>(logins_by_key: &HashMap<&str, Vec<&'a PublicKeyLogin>>, username: Option<&str>,
+                          fingerprint: &str) -> Vec<&'a PublicKeyLogin> {
+    logins_by_key.get(fingerprint).into_iter().flatten()
+        .filter(|login| username.is_none() || username == Some(login.username.as_str()))
+        .copied()
+        .collect()
+}
+
+/// Distinct addresses from (address, timestamp) events in order of the first event.
+pub fn get_source_addresses<'a>(events: impl Iterator<Item = (&'a str, NaiveDateTime)>) -> Vec<SourceAddress> {
+    let mut addresses: HashMap<&str, (usize, NaiveDateTime, NaiveDateTime)> = HashMap::new();
+
+    for (source_address, timestamp) in events {
+        let (count, first_seen, last_seen) = addresses.entry(source_address)
+            .or_insert((0, timestamp, timestamp));
+
+        *count += 1;
+        *first_seen = (*first_seen).min(timestamp);
+        *last_seen = (*last_seen).max(timestamp);
+    }
+
+    let mut addresses: Vec<(&str, (usize, NaiveDateTime, NaiveDateTime))> = addresses.into_iter().collect();
+
+    addresses.sort_by_key(|(address, (_, first_seen, _))| (*first_seen, *address));
+
+    addresses.into_iter()
+        .map(|(address, (count, first_seen, last_seen))| SourceAddress {
+            address: address.to_string(),
+            logins: count,
+            first_seen: format_timestamp(&first_seen),
+            last_seen: format_timestamp(&last_seen),
+        })
+        .collect()
+}
+
+/// `SHA256:...` fingerprint -> key id from authorized_keys files, the first one for duplicates
+pub fn get_key_ids(files: &[UserKeysFile]) -> HashMap<String, String> {
+    let mut key_ids: HashMap<String, String> = HashMap::new();
+
+    for (_, content) in read_user_keys_files(files) {
+        for entry in KeysFile::parse(&content).lines.iter().filter_map(|line| line.get_entry()) {
+            key_ids.entry(format!("SHA256:{}", entry.get_fingerprint()))
+                   .or_insert_with(|| entry.get_comment().to_string());
+        }
+    }
+
+    key_ids
+}
+
+pub fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    timestamp.format(TIMESTAMP_FORMAT).to_string()
+}
+
+#[cfg(test)]
+mod usage_tests {
+    use crate::tests_common::{create_keys_file, get_login_from, get_temp_dir, KEY1, KEY1_FINGERPRINT, KEY2};
+    use crate::tests_common::time::get_datetime_from_now;
+    use crate::usecases::usage::get_keys_usage;
+
+    #[test]
+    fn collect_distinct_source_addresses_per_key() {
+        let dir = get_temp_dir();
+        let files = vec![create_keys_file(dir.path(), "alice", &format!("{KEY1} alice@laptop\n{KEY2} bob@desktop\n"))];
+
+        let logins = vec![
+            get_login_from(&get_datetime_from_now(1), "alice", "10.0.0.2", KEY1_FINGERPRINT),
+            get_login_from(&get_datetime_from_now(5), "alice", "10.0.0.1", KEY1_FINGERPRINT),
+            get_login_from(&get_datetime_from_now(3), "alice", "10.0.0.1", KEY1_FINGERPRINT),
+            get_login_from(&get_datetime_from_now(2), "bob", "10.0.0.3", KEY1_FINGERPRINT),
+        ];
+
+        let usages = get_keys_usage(&files, true, &logins, true);
+
+        assert_eq!(usages.len(), 2);
+        assert_eq!(usages[0].logins, 3);
+        assert_eq!(usages[1].logins, 0);
+        assert_eq!(usages[1].last_seen, None);
+
+        let addresses: Vec<(String, usize)> = usages[0].source_addresses.iter().flatten()
+            .map(|a| (a.address.to_string(), a.logins)).collect();
+
+        assert_eq!(addresses, vec![("10.0.0.1".to_string(), 2), ("10.0.0.2".to_string(), 1)]);
+        assert_eq!(usages[0].source_addresses.as_ref().unwrap()[0].first_seen, usages[0].first_seen.clone().unwrap());
+
+        let usages = get_keys_usage(&files, true, &logins, false);
+
+        assert_eq!(usages[0].source_addresses, None);
+    }
+}
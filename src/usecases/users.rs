@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use log::{debug, info, warn};
+
+use crate::passwd::{get_passwd_entries_from_file, PasswdEntry};
+use crate::sshdconfig::get_authorized_keys_file_patterns;
+
+/// authorized_keys file of user
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserKeysFile {
+    pub username: String,
+    pub path: PathBuf,
+}
+
+/// Returns existing authorized_keys files of all users from passwd file.
+///
+/// File locations are taken from `AuthorizedKeysFile` directive of sshd config,
+/// sshd defaults are used if config file doesn't exist. File shared by several users
+/// (i.e. `AuthorizedKeysFile /etc/ssh/keys` or symlink) is returned for each of them.
+pub fn get_users_authorized_keys_files(passwd_file_path: &Path,
+                                       sshd_config_file_path: &Path) -> anyhow::Result<Vec<UserKeysFile>> {
+    info!("get authorized_keys files of all users");
+
+    let users = get_passwd_entries_from_file(passwd_file_path)?;
+
+    let patterns = get_authorized_keys_file_patterns(&get_sshd_config(sshd_config_file_path)?);
+
+    debug!("authorized_keys file patterns: {:?}", patterns);
+
+    let mut files: Vec<UserKeysFile> = vec![];
+
+    for user in &users {
+        for pattern in &patterns {
+            let path = expand_authorized_keys_file_pattern(pattern, user);
+
+            if path.is_file() && !files.iter().any(|f| f.username == user.username && f.path == path) {
+                debug!("user '{}' authorized_keys file '{}'", user.username, path.display());
+                files.push(UserKeysFile { username: user.username.to_string(), path });
+            }
+        }
+    }
+
+    info!("authorized_keys files found: {}", files.len());
+
+    Ok(files)
+}
+
+/// Files with their content, file shared by several users is read once.
+/// Unreadable files are skipped.
+pub fn read_user_keys_files(files: &[UserKeysFile]) -> Vec<(&UserKeysFile, Rc<String>)> {
+    let mut contents: HashMap<&Path, Rc<String>> = HashMap::new();
+
+    let mut result: Vec<(&UserKeysFile, Rc<String>)> = vec![];
+
+    for file in files {
+        let content = match contents.get(file.path.as_path()) {
+            Some(content) => content.clone(),
+            None => match fs::read_to_string(&file.path) {
+                Ok(content) => {
+                    let content = Rc::new(content);
+                    contents.insert(&file.path, content.clone());
+                    content
+                }
+                Err(e) => {
+                    warn!("unable to read file '{}': {}", file.path.display(), e);
+                    continue
+                }
+            }
+        };
+
+        result.push((file, content));
+    }
+
+    result
+}
+
+/// Name of file owner from passwd file, uid is returned if user isn't found there.
+pub fn get_file_owner(file_path: &Path, passwd_file_path: &Path) -> anyhow::Result<String> {
+    let uid = fs::metadata(file_path)
+        .map_err(|e| anyhow::anyhow!("unable to read file '{}': {}", file_path.display(), e))?
+        .uid();
+
+    let username = get_passwd_entries_from_file(passwd_file_path)?
+        .into_iter()
+        .find(|user| user.uid == uid)
+        .map(|user| user.username)
+        .unwrap_or_else(|| uid.to_string());
+
+    debug!("owner of file '{}': {}", file_path.display(), username);
+
+    Ok(username)
+}
+
+/// Empty config if file doesn't exist, so sshd defaults are used.
+pub fn get_sshd_config(sshd_config_file_path: &Path) -> anyhow::Result<String> {
+    if sshd_config_file_path.exists() {
+        Ok(fs::read_to_string(sshd_config_file_path)?)
+
+    } else {
+        warn!("sshd config '{}' wasn't found, use defaults", sshd_config_file_path.display());
+        Ok(String::new())
+    }
+}
+
+/// Expands `%h`, `%u` and `%%` tokens, relative paths are relative to user's home.
+pub fn expand_authorized_keys_file_pattern(pattern: &str, user: &PasswdEntry) -> PathBuf {
+    let mut path = String::new();
+
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            path.push(c);
+            continue
+        }
+
+        match chars.next() {
+            Some('h') => path.push_str(&user.home.to_string_lossy()),
+            Some('u') => path.push_str(&user.username),
+            Some('%') => path.push('%'),
+            Some(other) => {
+                path.push('%');
+                path.push(other);
+            }
+            None => path.push('%')
+        }
+    }
+
+    user.home.join(path)
+}
+
+#[cfg(test)]
+mod users_tests {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    use crate::passwd::PasswdEntry;
+    use crate::tests_common::get_temp_dir;
+    use crate::usecases::users::{expand_authorized_keys_file_pattern, get_file_owner, get_users_authorized_keys_files};
+
+    #[test]
+    fn expand_tokens_in_pattern() {
+        let user = PasswdEntry {
+            username: "alice".to_string(),
+            uid: 1000,
+            gid: 1000,
+            home: PathBuf::from("/home/alice"),
+            shell: "/bin/sh".to_string(),
+        };
+
+        assert_eq!(expand_authorized_keys_file_pattern(".ssh/authorized_keys", &user),
+                   PathBuf::from("/home/alice/.ssh/authorized_keys"));
+        assert_eq!(expand_authorized_keys_file_pattern("/etc/ssh/keys/%u", &user),
+                   PathBuf::from("/etc/ssh/keys/alice"));
+        assert_eq!(expand_authorized_keys_file_pattern("%h/.ssh/keys%%", &user),
+                   PathBuf::from("/home/alice/.ssh/keys%"));
+    }
+
+    #[test]
+    fn shared_file_should_be_returned_for_each_user() {
+        let dir = get_temp_dir();
+
+        let keys_file_path = dir.path().join("keys");
+        fs::write(&keys_file_path, "").unwrap();
+
+        let passwd_file_path = dir.path().join("passwd");
+        fs::write(&passwd_file_path, format!("alice:x:1000:1000::{0}:/bin/sh\nbob:x:1001:1001::{0}:/bin/sh\n",
+                                             dir.path().display())).unwrap();
+
+        let sshd_config_file_path = dir.path().join("sshd_config");
+        fs::write(&sshd_config_file_path, format!("AuthorizedKeysFile {0} {0}\n", keys_file_path.display())).unwrap();
+
+        let files = get_users_authorized_keys_files(&passwd_file_path, &sshd_config_file_path).unwrap();
+
+        let files: Vec<(&str, &PathBuf)> = files.iter().map(|file| (file.username.as_str(), &file.path)).collect();
+
+        assert_eq!(files, vec![("alice", &keys_file_path), ("bob", &keys_file_path)]);
+    }
+
+    #[test]
+    fn owner_should_be_taken_from_passwd_file() {
+        let dir = get_temp_dir();
+
+        let keys_file_path = dir.path().join("authorized_keys");
+        fs::write(&keys_file_path, "").unwrap();
+
+        let uid = fs::metadata(&keys_file_path).unwrap().uid();
+
+        let passwd_file_path = dir.path().join("passwd");
+        fs::write(&passwd_file_path, format!("alice:x:{uid}:{uid}::/home/alice:/bin/sh\n")).unwrap();
+
+        assert_eq!(get_file_owner(&keys_file_path, &passwd_file_path).unwrap(), "alice");
+
+        fs::write(&passwd_file_path, "").unwrap();
+
+        assert_eq!(get_file_owner(&keys_file_path, &passwd_file_path).unwrap(), uid.to_string());
+    }
+}